@@ -3,5 +3,42 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     tonic_build::configure()
         .protoc_arg("--experimental_allow_proto3_optional")
         .compile_protos(&["proto/platform.proto"], &["proto"])?;
+
+    // The response schema also backs the CBOR and bincode formats
+    tonic_build::configure()
+        .protoc_arg("--experimental_allow_proto3_optional")
+        .build_client(false)
+        .build_server(false)
+        .type_attribute(".quorum_list_server.v1", "#[derive(serde::Serialize, bincode::Encode)]")
+        .type_attribute(".quorum_list_server.v1.Response.data", "#[serde(rename_all = \"snake_case\")]")
+        .field_attribute(".quorum_list_server.v1.Response.data.core_json", "#[serde(with = \"serde_bytes\")]")
+        .field_attribute(".quorum_list_server.v1.Response.data.block_hash", "#[serde(with = \"serde_bytes\")]")
+        .field_attribute(".quorum_list_server.v1.QuorumMember.pro_tx_hash", "#[serde(with = \"serde_bytes\")]")
+        .field_attribute(".quorum_list_server.v1.QuorumMember.pub_key_operator", "#[serde(with = \"serde_bytes\")]")
+        .field_attribute(".quorum_list_server.v1.Quorum.quorum_hash", "#[serde(with = \"serde_bytes\")]")
+        .field_attribute(".quorum_list_server.v1.Quorum.key", "#[serde(with = \"serde_bytes\")]")
+        .field_attribute(".quorum_list_server.v1.Quorum.threshold_signature", "#[serde(with = \"serde_bytes\")]")
+        .field_attribute(".quorum_list_server.v1.Masternode.pro_tx_hash", "#[serde(with = \"serde_bytes\")]")
+        .field_attribute(".quorum_list_server.v1.SignedQuorumList.block_hash", "#[serde(with = \"serde_bytes\")]")
+        .field_attribute(".quorum_list_server.v1.SignedQuorumList.signature", "#[serde(with = \"serde_bytes\")]")
+        .field_attribute(".quorum_list_server.v1.SigningKey.public_key", "#[serde(with = \"serde_bytes\")]")
+        .field_attribute(".quorum_list_server.v1.SignatureVerification.quorum_hash", "#[serde(with = \"serde_bytes\")]")
+        .field_attribute(".quorum_list_server.v1.SignatureVerification.sign_hash", "#[serde(with = \"serde_bytes\")]")
+        .field_attribute(".quorum_list_server.v1.LockVerification.quorum_hash", "#[serde(with = \"serde_bytes\")]")
+        .field_attribute(".quorum_list_server.v1.LockVerification.request_id", "#[serde(with = \"serde_bytes\")]")
+        .field_attribute(".quorum_list_server.v1.LockVerification.sign_hash", "#[serde(with = \"serde_bytes\")]")
+        .field_attribute(".quorum_list_server.v1.QuorumLifecycle.quorum_hash", "#[serde(with = \"serde_bytes\")]")
+        .field_attribute(".quorum_list_server.v1.QuorumLifecycle.key", "#[serde(with = \"serde_bytes\")]")
+        .field_attribute(".quorum_list_server.v1.EvoMasternodeState.pro_tx_hash", "#[serde(with = \"serde_bytes\")]")
+        .field_attribute(".quorum_list_server.v1.EvoMasternodeState.platform_node_id", "#[serde(with = \"serde_bytes\")]")
+        .field_attribute(".quorum_list_server.v1.MasternodeUpdate.pro_tx_hash", "#[serde(with = \"serde_bytes\")]")
+        .field_attribute(".quorum_list_server.v1.MasternodeUpdate.changes_json", "#[serde(with = \"serde_bytes\")]")
+        .field_attribute(".quorum_list_server.v1.MasternodeEvent.pro_tx_hash", "#[serde(with = \"serde_bytes\")]")
+        .field_attribute(".quorum_list_server.v1.MasternodeEvent.changes_json", "#[serde(with = \"serde_bytes\")]")
+        .field_attribute(".quorum_list_server.v1.NodeProbeHistory.pro_tx_hash", "#[serde(with = \"serde_bytes\")]")
+        .field_attribute(".quorum_list_server.v1.DapiAddress.pro_tx_hash", "#[serde(with = \"serde_bytes\")]")
+        .field_attribute(".quorum_list_server.v1.BlockAnchor.block_hash", "#[serde(with = \"serde_bytes\")]")
+        .field_attribute(".quorum_list_server.v1.BlockAnchor.chainlock_signature", "#[serde(with = \"serde_bytes\")]")
+        .compile_protos(&["proto/quorum_list_server.proto"], &["proto"])?;
     Ok(())
 }
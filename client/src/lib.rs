@@ -0,0 +1,235 @@
+//! Verification of signed quorum lists, for clients of the server.
+//!
+//! Shared by the server, which signs the lists, and light clients. A light
+//! client that fetches `/quorums/signed` through a CDN or mirror checks it
+//! with [`SignedQuorumList::verify`] against a public key it obtained out of
+//! band (or from `/signing-key` over a channel it trusts).
+//!
+//! The signed message is, with integers big-endian:
+//!
+//! ```text
+//! SNAPSHOT_DOMAIN
+//! height              u32
+//! block_hash          32 bytes, as displayed by Dash Core
+//! quorum_count        u32
+//! quorum_count times, sorted by quorum hash:
+//!     quorum_hash     32 bytes
+//!     public_key      48 bytes
+//! ```
+//!
+//! Ed25519 signs the message itself; secp256k1 signs its SHA-256 digest
+//! (deterministic ECDSA, 64-byte `r || s` signatures, 33-byte compressed keys).
+
+use serde::{Deserialize, Serialize};
+
+/// Prefix of every signed snapshot message, so the signatures can't be
+/// replayed as signatures over anything else.
+pub const SNAPSHOT_DOMAIN: &[u8] = b"quorum-list-server snapshot v1";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SignatureAlgorithm {
+    Ed25519,
+    Secp256k1,
+}
+
+impl std::fmt::Display for SignatureAlgorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SignatureAlgorithm::Ed25519 => write!(f, "ed25519"),
+            SignatureAlgorithm::Secp256k1 => write!(f, "secp256k1"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+pub enum VerifyError {
+    #[error("invalid {0}: {1}")]
+    Malformed(&'static str, String),
+    #[error("signed with {found}, expected {expected}")]
+    WrongAlgorithm {
+        expected: SignatureAlgorithm,
+        found: SignatureAlgorithm,
+    },
+    #[error("signature does not match the quorum list")]
+    BadSignature,
+}
+
+/// The `data` of a `/quorums/signed` response. Fields of the quorum entries
+/// that aren't signed are ignored.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SignedQuorumList {
+    pub height: u32,
+    pub block_hash: String,
+    pub algorithm: SignatureAlgorithm,
+    /// Hex encoded signature over [`snapshot_message`].
+    pub signature: String,
+    pub quorums: Vec<SignedQuorum>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SignedQuorum {
+    pub quorum_hash: String,
+    pub key: String,
+}
+
+impl SignedQuorumList {
+    /// Check that the list was signed by `public_key` using `algorithm`.
+    pub fn verify(&self, algorithm: SignatureAlgorithm, public_key: &[u8]) -> Result<(), VerifyError> {
+        if self.algorithm != algorithm {
+            return Err(VerifyError::WrongAlgorithm { expected: algorithm, found: self.algorithm });
+        }
+        let block_hash = decode_hex("block hash", &self.block_hash)?;
+        let quorums = self
+            .quorums
+            .iter()
+            .map(|quorum| Ok((decode_hex("quorum hash", &quorum.quorum_hash)?, decode_hex("quorum key", &quorum.key)?)))
+            .collect::<Result<Vec<_>, VerifyError>>()?;
+        let message = snapshot_message(self.height, &block_hash, &quorums)?;
+        verify_signature(algorithm, public_key, &message, &decode_hex("signature", &self.signature)?)
+    }
+}
+
+fn decode_hex(what: &'static str, value: &str) -> Result<Vec<u8>, VerifyError> {
+    hex::decode(value).map_err(|e| VerifyError::Malformed(what, e.to_string()))
+}
+
+/// Build the message signed for a quorum list anchored at `height`/`block_hash`.
+/// `quorums` are `(quorum hash, public key)` pairs in any order.
+pub fn snapshot_message(height: u32, block_hash: &[u8], quorums: &[(Vec<u8>, Vec<u8>)]) -> Result<Vec<u8>, VerifyError> {
+    if block_hash.len() != 32 {
+        return Err(VerifyError::Malformed("block hash", format!("{} bytes, expected 32", block_hash.len())));
+    }
+    let mut sorted: Vec<&(Vec<u8>, Vec<u8>)> = quorums.iter().collect();
+    sorted.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut message = Vec::with_capacity(SNAPSHOT_DOMAIN.len() + 40 + sorted.len() * 80);
+    message.extend_from_slice(SNAPSHOT_DOMAIN);
+    message.extend_from_slice(&height.to_be_bytes());
+    message.extend_from_slice(block_hash);
+    message.extend_from_slice(&(sorted.len() as u32).to_be_bytes());
+    for (quorum_hash, key) in sorted {
+        if quorum_hash.len() != 32 {
+            return Err(VerifyError::Malformed("quorum hash", format!("{} bytes, expected 32", quorum_hash.len())));
+        }
+        if key.len() != 48 {
+            return Err(VerifyError::Malformed("quorum key", format!("{} bytes, expected 48", key.len())));
+        }
+        message.extend_from_slice(quorum_hash);
+        message.extend_from_slice(key);
+    }
+    Ok(message)
+}
+
+/// Verify `signature` over `message`.
+pub fn verify_signature(
+    algorithm: SignatureAlgorithm,
+    public_key: &[u8],
+    message: &[u8],
+    signature: &[u8],
+) -> Result<(), VerifyError> {
+    match algorithm {
+        SignatureAlgorithm::Ed25519 => {
+            let key: [u8; 32] = public_key
+                .try_into()
+                .map_err(|_| VerifyError::Malformed("public key", format!("{} bytes, expected 32", public_key.len())))?;
+            let key = ed25519_dalek::VerifyingKey::from_bytes(&key)
+                .map_err(|e| VerifyError::Malformed("public key", e.to_string()))?;
+            let signature = ed25519_dalek::Signature::from_slice(signature)
+                .map_err(|e| VerifyError::Malformed("signature", e.to_string()))?;
+            key.verify_strict(message, &signature).map_err(|_| VerifyError::BadSignature)
+        }
+        SignatureAlgorithm::Secp256k1 => {
+            use k256::ecdsa::signature::Verifier;
+
+            let key = k256::ecdsa::VerifyingKey::from_sec1_bytes(public_key)
+                .map_err(|e| VerifyError::Malformed("public key", e.to_string()))?;
+            let signature = k256::ecdsa::Signature::from_slice(signature)
+                .map_err(|e| VerifyError::Malformed("signature", e.to_string()))?;
+            key.verify(message, &signature).map_err(|_| VerifyError::BadSignature)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quorums() -> Vec<(Vec<u8>, Vec<u8>)> {
+        vec![(vec![2; 32], vec![0x82; 48]), (vec![1; 32], vec![0x81; 48])]
+    }
+
+    fn signed_list(algorithm: SignatureAlgorithm, signature: &[u8]) -> SignedQuorumList {
+        SignedQuorumList {
+            height: 1100050,
+            block_hash: hex::encode([0xaa; 32]),
+            algorithm,
+            signature: hex::encode(signature),
+            quorums: quorums()
+                .into_iter()
+                .map(|(hash, key)| SignedQuorum { quorum_hash: hex::encode(hash), key: hex::encode(key) })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn message_is_independent_of_quorum_order() {
+        let mut reversed = quorums();
+        reversed.reverse();
+        let message = snapshot_message(7, &[0xaa; 32], &quorums()).unwrap();
+        assert_eq!(message, snapshot_message(7, &[0xaa; 32], &reversed).unwrap());
+        assert_eq!(message.len(), SNAPSHOT_DOMAIN.len() + 4 + 32 + 4 + 2 * 80);
+        assert_eq!(&message[SNAPSHOT_DOMAIN.len() + 40..][..32], &[1; 32]);
+    }
+
+    #[test]
+    fn ed25519_lists_verify_until_tampered_with() {
+        use ed25519_dalek::Signer;
+
+        let key = ed25519_dalek::SigningKey::from_bytes(&[7; 32]);
+        let message = snapshot_message(1100050, &[0xaa; 32], &quorums()).unwrap();
+        let list = signed_list(SignatureAlgorithm::Ed25519, &key.sign(&message).to_bytes());
+        let public_key = key.verifying_key().to_bytes();
+        assert_eq!(list.verify(SignatureAlgorithm::Ed25519, &public_key), Ok(()));
+
+        let mut tampered = list.clone();
+        tampered.quorums[0].key = hex::encode([0x83; 48]);
+        assert_eq!(tampered.verify(SignatureAlgorithm::Ed25519, &public_key), Err(VerifyError::BadSignature));
+
+        let mut dropped = list.clone();
+        dropped.quorums.pop();
+        assert_eq!(dropped.verify(SignatureAlgorithm::Ed25519, &public_key), Err(VerifyError::BadSignature));
+
+        let mut moved = list.clone();
+        moved.height += 1;
+        assert_eq!(moved.verify(SignatureAlgorithm::Ed25519, &public_key), Err(VerifyError::BadSignature));
+
+        assert!(matches!(
+            list.verify(SignatureAlgorithm::Secp256k1, &public_key),
+            Err(VerifyError::WrongAlgorithm { .. })
+        ));
+    }
+
+    #[test]
+    fn secp256k1_lists_verify_until_tampered_with() {
+        use k256::ecdsa::signature::Signer;
+
+        let key = k256::ecdsa::SigningKey::from_slice(&[7; 32]).unwrap();
+        let message = snapshot_message(1100050, &[0xaa; 32], &quorums()).unwrap();
+        let signature: k256::ecdsa::Signature = key.sign(&message);
+        let list = signed_list(SignatureAlgorithm::Secp256k1, &signature.to_bytes());
+        let public_key = key.verifying_key().to_sec1_bytes();
+        assert_eq!(list.verify(SignatureAlgorithm::Secp256k1, &public_key), Ok(()));
+
+        let mut tampered = list.clone();
+        tampered.block_hash = hex::encode([0xab; 32]);
+        assert_eq!(tampered.verify(SignatureAlgorithm::Secp256k1, &public_key), Err(VerifyError::BadSignature));
+
+        let mut truncated = list;
+        truncated.quorums[1].key.truncate(94);
+        assert!(matches!(
+            truncated.verify(SignatureAlgorithm::Secp256k1, &public_key),
+            Err(VerifyError::Malformed("quorum key", _))
+        ));
+    }
+}
@@ -0,0 +1,114 @@
+//! Binding quorum data to the block it was read at, and to that block's
+//! ChainLock when there is one.
+
+use crate::data_source::CoreDataSource;
+use crate::error::AppError;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// The block quorum data was loaded at.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct BlockAnchor {
+    pub height: u32,
+    /// Hex encoded hash of the block at `height`.
+    pub block_hash: String,
+    /// Hex encoded BLS signature of the ChainLock for `block_hash`, if the
+    /// block is the one Dash Core's best ChainLock covers.
+    pub chainlock_signature: Option<String>,
+}
+
+/// The parts of a `getbestchainlock` response the server uses.
+#[derive(Debug, Clone, Deserialize)]
+pub struct BestChainLock {
+    pub blockhash: String,
+    pub height: u32,
+    pub signature: String,
+}
+
+impl BlockAnchor {
+    /// Anchor at the best ChainLocked block, so clients can bind data to a
+    /// finalized block. Falls back to the chain tip, without a signature,
+    /// when Dash Core has no ChainLock (e.g. right after startup or on regtest).
+    pub async fn best(source: &dyn CoreDataSource) -> Result<Self, AppError> {
+        match best_chainlock(source).await? {
+            Some(chainlock) => Ok(Self {
+                height: chainlock.height,
+                block_hash: chainlock.blockhash,
+                chainlock_signature: Some(chainlock.signature),
+            }),
+            None => {
+                let height = source.block_count().await?;
+                Ok(Self {
+                    height,
+                    block_hash: source.block_hash(height).await?,
+                    chainlock_signature: None,
+                })
+            }
+        }
+    }
+
+    /// Anchor at `height`, with the ChainLock signature if the best
+    /// ChainLock is for exactly that block.
+    pub async fn at_height(source: &dyn CoreDataSource, height: u32) -> Result<Self, AppError> {
+        let block_hash = source.block_hash(height).await?;
+        let chainlock_signature = best_chainlock(source)
+            .await?
+            .filter(|chainlock| chainlock.height == height && chainlock.blockhash == block_hash)
+            .map(|chainlock| chainlock.signature);
+        Ok(Self { height, block_hash, chainlock_signature })
+    }
+}
+
+/// The best ChainLock, or `None` if Dash Core doesn't have a usable one.
+/// Only failing to reach Dash Core at all is an error.
+async fn best_chainlock(source: &dyn CoreDataSource) -> Result<Option<BestChainLock>, AppError> {
+    match source.best_chainlock().await {
+        Ok(result) => Ok(serde_json::from_value(result).ok()),
+        Err(e @ (AppError::RpcUnavailable(_) | AppError::UpstreamTimeout(_))) => Err(e),
+        Err(_) => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_source::FixtureDataSource;
+
+    fn write_fixture(name: &str, exchanges: serde_json::Value) -> FixtureDataSource {
+        let path = std::env::temp_dir().join(format!("quorum-list-server-{}-{}.json", std::process::id(), name));
+        std::fs::write(&path, exchanges.to_string()).unwrap();
+        let source = FixtureDataSource::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        source
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_the_tip_without_a_chainlock() {
+        let source = write_fixture("no-chainlock", serde_json::json!([
+            { "method": "getbestchainlock", "error": { "code": -32603, "message": "Unable to find any ChainLock" } },
+            { "method": "getblockcount", "result": 120 },
+            { "method": "getblockhash", "params": [120], "result": "ab".repeat(32) },
+            { "method": "getblockhash", "params": [100], "result": "cd".repeat(32) },
+        ]));
+
+        assert_eq!(
+            BlockAnchor::best(&source).await.unwrap(),
+            BlockAnchor { height: 120, block_hash: "ab".repeat(32), chainlock_signature: None }
+        );
+        assert_eq!(BlockAnchor::at_height(&source, 100).await.unwrap().chainlock_signature, None);
+    }
+
+    #[tokio::test]
+    async fn chainlock_signature_is_only_attached_to_its_block() {
+        let source = write_fixture("chainlock", serde_json::json!([
+            { "method": "getbestchainlock", "result": { "blockhash": "ab".repeat(32), "height": 120, "signature": "99".repeat(96), "known_block": true } },
+            { "method": "getblockhash", "params": [120], "result": "ab".repeat(32) },
+            { "method": "getblockhash", "params": [100], "result": "cd".repeat(32) },
+        ]));
+
+        let best = BlockAnchor::best(&source).await.unwrap();
+        assert_eq!((best.height, best.chainlock_signature), (120, Some("99".repeat(96))));
+        assert_eq!(BlockAnchor::at_height(&source, 120).await.unwrap(), BlockAnchor::best(&source).await.unwrap());
+        assert_eq!(BlockAnchor::at_height(&source, 100).await.unwrap().chainlock_signature, None);
+    }
+}
@@ -1,27 +1,87 @@
-use crate::config::Config;
+use crate::anchor::BlockAnchor;
+use crate::bls;
+use crate::config::{Config, LlmqParams};
+use crate::dapi_addresses::{self, DapiAddress};
+use crate::data_source::SharedDataSource;
+use crate::error::AppError;
+use crate::health::{self, CoreSyncStatus, FreshnessCheck, ReadinessReport};
+use crate::locks::{self, LockQuorums, LockVerification};
+use crate::quorum_cache::{QuorumCache, QuorumState};
+use crate::quorum_history::QuorumLifecycle;
 use crate::quorum_list::{QuorumList, QuorumListEntry};
-use crate::masternode::EvoMasternodeList;
+use crate::masternode::{EvoMasternodeInfo, EvoMasternodeState, MasternodeUpdate};
 use crate::masternode_cache::MasternodeCache;
+use crate::masternode_history::{MasternodeEvent, MasternodeEventKind};
+use crate::masternode_loader;
+use crate::probe_history::{self, NodeProbeHistory, ProbeOutcome, ProbeResult, Uptime};
+use crate::response_format::{self, encode, ResponseFormat};
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
-    response::Json,
-    routing::{get, post},
-    Router,
+    extract::{FromRequest, Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    middleware,
+    response::{IntoResponse, Response},
+    routing::{get, post, MethodRouter},
+    Json, Router,
 };
-use serde::Serialize;
-use std::sync::{Arc, RwLock};
+use quorum_list_client::SignatureAlgorithm;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 use tower_http::cors::CorsLayer;
+use utoipa::{IntoParams, OpenApi, ToSchema};
+use utoipa_swagger_ui::SwaggerUi;
 
-pub type SharedQuorumList = Arc<RwLock<QuorumList>>;
 pub type SharedConfig = Arc<Config>;
 pub type SharedMasternodeCache = Arc<MasternodeCache>;
+pub type SharedQuorumCache = Arc<QuorumCache>;
+pub type SharedNetworkState = Arc<NetworkState>;
 
-#[derive(Serialize)]
+/// Everything the handlers need to serve one network. Each configured
+/// network has its own instance, so quorum and masternode state never mix.
+pub struct NetworkState {
+    pub name: String,
+    pub config: SharedConfig,
+    pub quorum_cache: SharedQuorumCache,
+    pub masternode_cache: SharedMasternodeCache,
+    pub source: SharedDataSource,
+}
+
+#[derive(Clone, Serialize, ToSchema)]
+#[aliases(
+    StringResponse = ApiResponse<String>,
+    ErrorResponse = ApiResponse<NoData>,
+    ReadinessResponse = ApiResponse<ReadinessReport>,
+    QuorumListResponse = ApiResponse<Vec<QuorumEntryResponse>>,
+    QuorumResponse = ApiResponse<QuorumEntryResponse>,
+    QuorumStatsResponse = ApiResponse<QuorumStats>,
+    PreviousQuorumsResponse = ApiResponse<QuorumsAtHeightResponse>,
+    MasternodeListResponse = ApiResponse<Vec<EvoMasternodeInfo>>,
+    MasternodesAtHeightApiResponse = ApiResponse<MasternodesAtHeightResponse>,
+    MasternodeDiffApiResponse = ApiResponse<MasternodeDiffResponse>,
+    MasternodeHistoryResponse = ApiResponse<Vec<MasternodeEvent>>,
+    ProbeHistoryResponse = ApiResponse<NodeProbeHistory>,
+    DapiAddressesResponse = ApiResponse<Vec<DapiAddress>>,
+    CoreDataResponse = ApiResponse<serde_json::Value>,
+    CoreStatusResponse = ApiResponse<CoreSyncStatus>,
+    BlockCountResponse = ApiResponse<u32>,
+    BlockHashResponse = ApiResponse<BlockHash>,
+    SignedQuorumsResponse = ApiResponse<SignedQuorumListResponse>,
+    SigningKeyApiResponse = ApiResponse<SigningKeyResponse>,
+    SignatureVerificationResponse = ApiResponse<SignatureVerification>,
+    LockVerificationResponse = ApiResponse<LockVerification>,
+    QuorumHistoryResponse = ApiResponse<Vec<QuorumLifecycle>>,
+    QuorumLifecycleResponse = ApiResponse<QuorumLifecycle>,
+    QuorumDiffApiResponse = ApiResponse<QuorumDiffResponse>,
+)]
 pub struct ApiResponse<T> {
     pub success: bool,
     pub data: Option<T>,
     pub message: Option<String>,
+    /// Machine-readable error code, set whenever `success` is false.
+    #[schema(value_type = Option<String>, example = "not_found")]
+    pub code: Option<&'static str>,
+    /// The block quorum data in `data` was loaded at. Only set on quorum responses.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub anchor: Option<BlockAnchor>,
 }
 
 impl<T> ApiResponse<T> {
@@ -30,33 +90,157 @@ impl<T> ApiResponse<T> {
             success: true,
             data: Some(data),
             message: None,
+            code: None,
+            anchor: None,
         }
     }
 
-    pub fn error(message: String) -> Self {
+    pub fn error(code: &'static str, message: String) -> Self {
         Self {
             success: false,
             data: None,
             message: Some(message),
+            code: Some(code),
+            anchor: None,
         }
     }
+
+    pub fn with_anchor(mut self, anchor: Option<BlockAnchor>) -> Self {
+        self.anchor = anchor;
+        self
+    }
 }
 
-#[derive(Serialize)]
+pub type ApiResult<T> = Result<ApiResponse<T>, AppError>;
+
+/// The `data` of an error response, which is always null.
+#[derive(Clone, Copy, Serialize, ToSchema)]
+pub struct NoData;
+
+/// A block hash, hex encoded in JSON and raw bytes in the binary formats.
+#[derive(Clone, Serialize, ToSchema)]
+#[serde(transparent)]
+#[schema(value_type = String)]
+pub struct BlockHash(pub String);
+
+#[derive(Serialize, ToSchema)]
 pub struct QuorumStats {
     pub total_count: usize,
     pub is_empty: bool,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct QuorumsAtHeightResponse {
     pub height: u32,
     pub quorums: Vec<QuorumEntryResponse>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
+pub struct MasternodesAtHeightResponse {
+    pub height: u32,
+    pub masternodes: Vec<EvoMasternodeState>,
+}
+
+/// How the Evo masternode list changed between two heights.
+#[derive(Serialize, ToSchema)]
+pub struct MasternodeDiffResponse {
+    pub base_height: u32,
+    pub height: u32,
+    /// Nodes registered after the base height, in their state at `height`.
+    pub added: Vec<EvoMasternodeState>,
+    /// Nodes removed after the base height, in their state at the base height.
+    pub removed: Vec<EvoMasternodeState>,
+    pub updated: Vec<MasternodeUpdate>,
+}
+
+/// How the quorum lists changed between two heights, like `protx diff`
+/// does for masternodes.
+#[derive(Serialize, ToSchema)]
+pub struct QuorumDiffResponse {
+    /// Height the diff starts from.
+    pub base_height: u32,
+    /// Height the diff leads to; the response's anchor is the block at this height.
+    pub height: u32,
+    pub llmq_types: Vec<QuorumTypeDiff>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct QuorumTypeDiff {
+    pub llmq_type: String,
+    /// Quorums listed at `height` but not at `base_height`.
+    pub added: Vec<QuorumEntryResponse>,
+    /// Quorums listed at `base_height` but not at `height`.
+    pub removed: Vec<QuorumEntryResponse>,
+}
+
+/// The current quorum list, anchored to a block and signed. Verify it with
+/// `quorum_list_client::SignedQuorumList::verify`.
+#[derive(Serialize, ToSchema)]
+pub struct SignedQuorumListResponse {
+    pub height: u32,
+    /// Hex encoded hash of the block at `height`.
+    pub block_hash: String,
+    #[schema(value_type = String, example = "ed25519")]
+    pub algorithm: SignatureAlgorithm,
+    /// Hex encoded signature over the height, block hash and the sorted quorum hashes and keys.
+    pub signature: String,
+    pub quorums: Vec<QuorumEntryResponse>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct SigningKeyResponse {
+    #[schema(value_type = String, example = "ed25519")]
+    pub algorithm: SignatureAlgorithm,
+    /// Hex encoded public key: 32 bytes for ed25519, 33-byte compressed for secp256k1.
+    pub public_key: String,
+}
+
+/// A JSON request body. Unlike `Json`'s own rejections, a body that doesn't
+/// parse is answered in the `ApiResponse` envelope like every other error.
+#[derive(FromRequest)]
+#[from_request(via(Json), rejection(AppError))]
+struct JsonBody<T>(T);
+
+/// Body of `POST /verify`. Hashes are hex encoded as Dash Core displays them.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct VerifyRequest {
+    /// LLMQ type id. Defaults to the network's platform quorum type; the ChainLock and
+    /// InstantSend types are served when lock verification is enabled.
+    pub llmq_type: Option<u32>,
+    /// The quorum that signed. Selected from `request_id` when omitted.
+    pub quorum_hash: Option<String>,
+    pub request_id: Option<String>,
+    /// Hash of the signed message. Together with `request_id` it determines the sign hash.
+    pub msg_hash: Option<String>,
+    /// The hash that was signed, instead of `request_id` and `msg_hash`.
+    pub sign_hash: Option<String>,
+    /// Hex encoded 96-byte BLS threshold signature.
+    pub signature: String,
+}
+
+/// Body of `POST /verify/chainlock` and `POST /verify/islock`.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct LockVerifyRequest {
+    /// Hex encoded network message: a `clsig` or an `isdlock`.
+    pub message: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct SignatureVerification {
+    /// Whether the signature is the quorum's threshold signature of `sign_hash`.
+    pub verified: bool,
+    pub llmq_type: u32,
+    /// Hex encoded hash of the quorum the signature was checked against.
+    pub quorum_hash: String,
+    /// Hex encoded hash the signature was checked against.
+    pub sign_hash: String,
+}
+
+#[derive(Serialize, ToSchema)]
 pub struct QuorumEntryResponse {
+    /// Hex encoded 32-byte quorum hash.
     pub quorum_hash: String,
+    /// Hex encoded 48-byte BLS quorum public key.
     pub key: String,
     pub height: u32,
     pub members: Vec<QuorumMemberResponse>,
@@ -65,7 +249,7 @@ pub struct QuorumEntryResponse {
     pub valid_members_count: u32,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct QuorumMemberResponse {
     pub proTxHash: String,
     pub pubKeyOperator: String,
@@ -99,132 +283,1247 @@ impl From<&QuorumListEntry> for QuorumEntryResponse {
 }
 
 
-pub fn create_router(shared_list: SharedQuorumList, config: Config, masternode_cache: SharedMasternodeCache) -> Router {
-    let shared_config = Arc::new(config);
-    Router::new()
-        .route("/health", get(health_check))
-        .route("/quorums", get(get_all_quorums))
-        .route("/quorums/stats", get(get_quorum_stats))
-        .route("/quorums/clear", post(clear_quorums))
-        .route("/previous", get(get_previous_quorums))
-        .route("/quorums/:hash", get(get_quorum_by_hash))
-        .route("/masternodes", get(get_masternodes))
-        .with_state((shared_list, shared_config, masternode_cache))
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Quorum List Server",
+        description = "Dash LLMQ quorum lists and Evo masternode status. \
+            Every route is served for the default network as documented and \
+            for each configured network under `/{network}/...`."
+    ),
+    paths(
+        health_check,
+        liveness_check,
+        readiness_check,
+        get_all_quorums,
+        get_quorum_stats,
+        clear_quorums,
+        get_previous_quorums,
+        get_quorum_by_hash,
+        get_quorum_history,
+        get_quorum_lifecycle,
+        get_quorum_diff,
+        get_signed_quorums,
+        select_quorum,
+        get_signing_key,
+        verify_quorum_signature,
+        verify_chainlock,
+        verify_islock,
+        get_masternodes,
+        get_masternode_diff,
+        get_masternodes_at_height,
+        get_masternode_history,
+        get_probe_history,
+        get_dapi_addresses,
+        core_status,
+        core_block_count,
+        core_block_hash,
+        core_block_header,
+        core_best_chainlock,
+        core_list_quorums,
+        core_quorum_info,
+        core_masternode_list,
+        core_masternode_list_at,
+        core_masternode_list_diff,
+    ),
+    components(schemas(
+        StringResponse,
+        ErrorResponse,
+        NoData,
+        ReadinessResponse,
+        QuorumListResponse,
+        QuorumResponse,
+        QuorumStatsResponse,
+        PreviousQuorumsResponse,
+        MasternodeListResponse,
+        MasternodesAtHeightApiResponse,
+        MasternodeDiffApiResponse,
+        MasternodeHistoryResponse,
+        ProbeHistoryResponse,
+        DapiAddressesResponse,
+        CoreDataResponse,
+        CoreStatusResponse,
+        BlockCountResponse,
+        BlockHashResponse,
+        SignedQuorumsResponse,
+        SigningKeyApiResponse,
+        SignatureVerificationResponse,
+        LockVerificationResponse,
+        QuorumHistoryResponse,
+        QuorumLifecycleResponse,
+        QuorumDiffApiResponse,
+        SignedQuorumListResponse,
+        SigningKeyResponse,
+        VerifyRequest,
+        SignatureVerification,
+        LockVerifyRequest,
+        LockVerification,
+        QuorumLifecycle,
+        ReadinessReport,
+        CoreSyncStatus,
+        FreshnessCheck,
+        BlockAnchor,
+        QuorumEntryResponse,
+        QuorumMemberResponse,
+        QuorumStats,
+        QuorumsAtHeightResponse,
+        QuorumDiffResponse,
+        QuorumTypeDiff,
+        EvoMasternodeInfo,
+        EvoMasternodeState,
+        MasternodeUpdate,
+        MasternodesAtHeightResponse,
+        MasternodeDiffResponse,
+        MasternodeEvent,
+        MasternodeEventKind,
+        NodeProbeHistory,
+        ProbeResult,
+        ProbeOutcome,
+        Uptime,
+        DapiAddress,
+    )),
+    tags(
+        (name = "health", description = "Liveness and readiness probes"),
+        (name = "quorums", description = "LLMQ quorum lists"),
+        (name = "masternodes", description = "Evo masternodes"),
+        (name = "core", description = "Raw data source responses, used to chain servers"),
+    )
+)]
+pub struct ApiDoc;
+
+/// Build the router for all configured networks. Every network is served
+/// under `/<name>/...`; the default network is additionally served by the
+/// un-prefixed routes. The OpenAPI document is served at `/openapi.json`
+/// with Swagger UI at `/docs`.
+pub fn create_router(networks: &[SharedNetworkState], default_network: &str) -> Router {
+    let mut router = Router::new()
+        .merge(SwaggerUi::new("/docs").url("/openapi.json", ApiDoc::openapi()));
+    for network in networks {
+        let prefix = format!("/{}", network.name);
+        let is_default = network.name == default_network;
+        let routes = network_router(network.clone());
+        if is_default {
+            router = router.merge(routes.clone());
+        }
+        router = router.nest(&prefix, routes);
+    }
+    router
+        .layer(middleware::from_fn(response_format::negotiate_format))
         .layer(CorsLayer::permissive())
 }
 
-async fn health_check() -> Json<ApiResponse<String>> {
-    Json(ApiResponse::success("Quorum List Server is running".to_string()))
+/// Routes served for every network, relative to the network prefix.
+/// Each one must be listed in `ApiDoc`.
+fn network_routes() -> Vec<(&'static str, MethodRouter<SharedNetworkState>)> {
+    vec![
+        ("/health", get(health_check)),
+        ("/health/live", get(liveness_check)),
+        ("/health/ready", get(readiness_check)),
+        ("/quorums", get(get_all_quorums)),
+        ("/quorums/stats", get(get_quorum_stats)),
+        ("/quorums/clear", post(clear_quorums)),
+        ("/previous", get(get_previous_quorums)),
+        ("/quorums/signed", get(get_signed_quorums)),
+        ("/quorums/select", get(select_quorum)),
+        ("/quorums/history", get(get_quorum_history)),
+        ("/quorums/diff", get(get_quorum_diff)),
+        ("/quorums/:hash", get(get_quorum_by_hash)),
+        ("/quorums/:hash/lifecycle", get(get_quorum_lifecycle)),
+        ("/signing-key", get(get_signing_key)),
+        ("/verify", post(verify_quorum_signature)),
+        ("/verify/chainlock", post(verify_chainlock)),
+        ("/verify/islock", post(verify_islock)),
+        ("/masternodes", get(get_masternodes)),
+        ("/masternodes/diff", get(get_masternode_diff)),
+        ("/masternodes/at/:height", get(get_masternodes_at_height)),
+        ("/masternodes/history", get(get_masternode_history)),
+        ("/masternodes/:pro_tx_hash/history", get(get_probe_history)),
+        ("/dapi/addresses", get(get_dapi_addresses)),
+        ("/core/status", get(core_status)),
+        ("/core/blockcount", get(core_block_count)),
+        ("/core/blockhash/:height", get(core_block_hash)),
+        ("/core/blockheader/:hash", get(core_block_header)),
+        ("/core/chainlock", get(core_best_chainlock)),
+        ("/core/quorums", get(core_list_quorums)),
+        ("/core/quorums/:llmq_type/:quorum_hash", get(core_quorum_info)),
+        ("/core/masternodes", get(core_masternode_list)),
+        ("/core/masternodes/at/:height", get(core_masternode_list_at)),
+        ("/core/masternodes/diff", get(core_masternode_list_diff)),
+    ]
 }
 
+fn network_router(state: SharedNetworkState) -> Router {
+    let expose_core = state.config.server.expose_core;
+    network_routes()
+        .into_iter()
+        .filter(|(path, _)| expose_core || !path.starts_with("/core/"))
+        .fold(Router::new(), |router, (path, method_router)| router.route(path, method_router))
+        .with_state(state)
+}
+
+#[utoipa::path(
+    get,
+    path = "/health",
+    tag = "health",
+    responses((status = 200, description = "Server is running", body = StringResponse))
+)]
+async fn health_check() -> ApiResponse<String> {
+    ApiResponse::success("Quorum List Server is running".to_string())
+}
+
+/// Liveness only says the process is serving requests; it never touches upstreams.
+#[utoipa::path(
+    get,
+    path = "/health/live",
+    tag = "health",
+    responses((status = 200, description = "Process is alive", body = StringResponse))
+)]
+async fn liveness_check() -> ApiResponse<String> {
+    ApiResponse::success("alive".to_string())
+}
+
+#[utoipa::path(
+    get,
+    path = "/health/ready",
+    tag = "health",
+    responses(
+        (status = 200, description = "Ready to serve traffic", body = ReadinessResponse),
+        (status = 503, description = "Not ready; `data` lists the failing checks", body = ReadinessResponse),
+    )
+)]
+async fn readiness_check(
+    State(network): State<SharedNetworkState>,
+) -> (StatusCode, ApiResponse<ReadinessReport>) {
+    let quorums = network.quorum_cache.snapshot();
+    let report = health::check_readiness(
+        &network.config,
+        network.source.as_ref(),
+        quorums.data.list.len(),
+        quorums.refreshed_at,
+        network.masternode_cache.last_update(),
+    ).await;
+
+    if report.ready {
+        (StatusCode::OK, ApiResponse::success(report))
+    } else {
+        let message = report.problems.join("; ");
+        (
+            StatusCode::SERVICE_UNAVAILABLE,
+            ApiResponse { success: false, data: Some(report), message: Some(message), code: Some("not_ready"), anchor: None },
+        )
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+struct QuorumsQuery {
+    /// Only quorums created at or above this height.
+    min_height: Option<u32>,
+    /// Only quorums created at or below this height.
+    max_height: Option<u32>,
+    /// Only the N most recently created quorums, newest first.
+    /// Can't be combined with `min_height`/`max_height`.
+    latest: Option<usize>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/quorums",
+    tag = "quorums",
+    params(QuorumsQuery),
+    responses(
+        (status = 200, description = "Current quorums as of the last refresh (loaded on first use). \
+            Without filters they keep Dash Core's order; height filters sort by height.", body = QuorumListResponse),
+        (status = 304, description = "Unfiltered list unchanged since the `If-None-Match` ETag"),
+        (status = 400, description = "Conflicting filters or min_height above max_height", body = ErrorResponse),
+        (status = 502, description = "Dash Core returned an error", body = ErrorResponse),
+        (status = 503, description = "Dash Core unavailable", body = ErrorResponse),
+        (status = 504, description = "Dash Core timed out", body = ErrorResponse),
+    )
+)]
 async fn get_all_quorums(
-    State((shared_list, config, _)): State<(SharedQuorumList, SharedConfig, SharedMasternodeCache)>,
-) -> Result<Json<ApiResponse<Vec<QuorumEntryResponse>>>, StatusCode> {
-    // Fetch fresh quorums from Dash Core
-    match crate::quorum_loader::load_initial_quorums(&config).await {
-        Ok(new_quorums) => {
-            // Update the shared list with fresh data
-            match shared_list.write() {
-                Ok(mut list) => {
-                    *list = new_quorums;
-                }
-                Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
-            }
-            
-            // Now read and return the updated list
-            match shared_list.read() {
-                Ok(list) => {
-                    let quorums: Vec<QuorumEntryResponse> = list.iter().map(|entry| entry.into()).collect();
-                    Ok(Json(ApiResponse::success(quorums)))
-                }
-                Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
-            }
+    State(network): State<SharedNetworkState>,
+    Query(query): Query<QuorumsQuery>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    let height_filtered = query.min_height.is_some() || query.max_height.is_some();
+    if height_filtered && query.latest.is_some() {
+        return Err(AppError::BadRequest("latest can't be combined with min_height/max_height".to_string()));
+    }
+    if let (Some(min_height), Some(max_height)) = (query.min_height, query.max_height) {
+        if min_height > max_height {
+            return Err(AppError::BadRequest(format!(
+                "min_height {} is above max_height {}",
+                min_height, max_height
+            )));
         }
-        Err(e) => Ok(Json(ApiResponse::error(format!("Failed to load quorums: {}", e))))
     }
+
+    // Load from Dash Core if the background refresh hasn't yet
+    if network.quorum_cache.last_update().is_none() {
+        network.quorum_cache.refresh().await?;
+    }
+
+    let snapshot = network.quorum_cache.snapshot();
+    let list = &snapshot.data.list;
+    let quorums: Vec<QuorumEntryResponse> = match query.latest {
+        Some(count) => list.latest(count).map(|entry| entry.into()).collect(),
+        None if height_filtered => {
+            let heights = query.min_height.unwrap_or(0)..=query.max_height.unwrap_or(u32::MAX);
+            list.range_by_height(heights).map(|entry| entry.into()).collect()
+        }
+        // The unfiltered list is serialized and compressed once per refresh
+        None => {
+            let format = ResponseFormat::current();
+            let body = snapshot.response_body(format, |state| {
+                let quorums = state.list.iter().map(QuorumEntryResponse::from).collect::<Vec<_>>();
+                encode(format, ApiResponse::success(quorums).with_anchor(state.anchor.clone()))
+            });
+            let max_age = snapshot.max_age(network.quorum_cache.update_interval());
+            return Ok(body.respond(&headers, max_age).await);
+        }
+    };
+    Ok(ApiResponse::success(quorums).with_anchor(snapshot.data.anchor.clone()).into_response())
 }
 
+#[utoipa::path(
+    get,
+    path = "/quorums/stats",
+    tag = "quorums",
+    responses((status = 200, description = "Quorum list statistics", body = QuorumStatsResponse))
+)]
 async fn get_quorum_stats(
-    State((shared_list, _, _)): State<(SharedQuorumList, SharedConfig, SharedMasternodeCache)>,
-) -> Result<Json<ApiResponse<QuorumStats>>, StatusCode> {
-    match shared_list.read() {
-        Ok(list) => {
-            let stats = QuorumStats {
-                total_count: list.len(),
-                is_empty: list.is_empty(),
-            };
-            Ok(Json(ApiResponse::success(stats)))
-        }
-        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
-    }
+    State(network): State<SharedNetworkState>,
+) -> ApiResult<QuorumStats> {
+    let snapshot = network.quorum_cache.snapshot();
+    let stats = QuorumStats {
+        total_count: snapshot.data.list.len(),
+        is_empty: snapshot.data.list.is_empty(),
+    };
+    Ok(ApiResponse::success(stats).with_anchor(snapshot.data.anchor.clone()))
 }
 
+#[utoipa::path(
+    get,
+    path = "/quorums/{hash}",
+    tag = "quorums",
+    params(("hash" = String, Path, description = "Hex encoded 32-byte quorum hash")),
+    responses(
+        (status = 200, description = "The quorum", body = QuorumResponse),
+        (status = 400, description = "Malformed quorum hash", body = ErrorResponse),
+        (status = 404, description = "Quorum not found", body = ErrorResponse),
+    )
+)]
 async fn get_quorum_by_hash(
     Path(hash): Path<String>,
-    State((shared_list, _, _)): State<(SharedQuorumList, SharedConfig, SharedMasternodeCache)>,
-) -> Result<Json<ApiResponse<QuorumEntryResponse>>, StatusCode> {
+    State(network): State<SharedNetworkState>,
+) -> ApiResult<QuorumEntryResponse> {
     let hash_bytes = match hex::decode(&hash) {
         Ok(bytes) if bytes.len() == 32 => bytes,
-        _ => return Ok(Json(ApiResponse::error("Invalid hash format. Must be 32 bytes hex encoded.".to_string()))),
+        _ => return Err(AppError::BadRequest("Invalid hash format. Must be 32 bytes hex encoded.".to_string())),
     };
 
-    match shared_list.read() {
-        Ok(list) => {
-            if let Some(entry) = list.get_entry(&hash_bytes) {
-                Ok(Json(ApiResponse::success(entry.into())))
-            } else {
-                Ok(Json(ApiResponse::error("Quorum not found".to_string())))
-            }
-        }
-        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    let snapshot = network.quorum_cache.snapshot();
+    match snapshot.data.list.get_entry(&hash_bytes) {
+        Some(entry) => Ok(ApiResponse::success(entry.into()).with_anchor(snapshot.data.anchor.clone())),
+        None => Err(AppError::NotFound("Quorum not found".to_string())),
     }
 }
 
+#[derive(Deserialize, IntoParams)]
+struct HistoryQuery {
+    /// LLMQ type id or name. Defaults to every tracked type.
+    #[serde(rename = "type")]
+    llmq_type: Option<String>,
+    /// Only quorums active at or after this height.
+    from: Option<u32>,
+    /// Only quorums active at or before this height.
+    to: Option<u32>,
+}
 
-async fn clear_quorums(
-    State((shared_list, _, _)): State<(SharedQuorumList, SharedConfig, SharedMasternodeCache)>,
-) -> Result<Json<ApiResponse<String>>, StatusCode> {
-    match shared_list.write() {
-        Ok(mut list) => {
-            list.clear();
-            Ok(Json(ApiResponse::success("All quorums cleared successfully".to_string())))
+#[utoipa::path(
+    get,
+    path = "/quorums/history",
+    tag = "quorums",
+    params(HistoryQuery),
+    responses(
+        (status = 200, description = "Every quorum published since the server started that was active \
+            in the height range, in the order they were first seen", body = QuorumHistoryResponse),
+        (status = 400, description = "An LLMQ type that isn't served or an empty height range", body = ErrorResponse),
+    )
+)]
+async fn get_quorum_history(
+    State(network): State<SharedNetworkState>,
+    Query(query): Query<HistoryQuery>,
+) -> ApiResult<Vec<QuorumLifecycle>> {
+    let llmq_type = match query.llmq_type.as_deref() {
+        Some(requested) => Some(served_llmq(&network.config, Some(requested))?.name),
+        None => None,
+    };
+    let (from, to) = (query.from.unwrap_or(0), query.to.unwrap_or(u32::MAX));
+    if from > to {
+        return Err(AppError::BadRequest("from must not be greater than to".to_string()));
+    }
+    if network.quorum_cache.last_update().is_none() {
+        network.quorum_cache.refresh().await?;
+    }
+
+    let quorums = network.quorum_cache.history().active_between(llmq_type, from, to).cloned().collect();
+    Ok(ApiResponse::success(quorums).with_anchor(network.quorum_cache.snapshot().data.anchor.clone()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/quorums/{hash}/lifecycle",
+    tag = "quorums",
+    params(("hash" = String, Path, description = "Hex encoded 32-byte quorum hash")),
+    responses(
+        (status = 200, description = "The quorum's key and the heights it was active between, \
+            also after it rotated out", body = QuorumLifecycleResponse),
+        (status = 400, description = "Malformed quorum hash", body = ErrorResponse),
+        (status = 404, description = "Quorum never published by this server", body = ErrorResponse),
+    )
+)]
+async fn get_quorum_lifecycle(
+    Path(hash): Path<String>,
+    State(network): State<SharedNetworkState>,
+) -> ApiResult<QuorumLifecycle> {
+    let hash_bytes = bls::parse_hash("quorum hash", &hash)?;
+    if network.quorum_cache.last_update().is_none() {
+        network.quorum_cache.refresh().await?;
+    }
+
+    let lifecycle = network.quorum_cache.history().get(&hash_bytes).cloned();
+    match lifecycle {
+        Some(lifecycle) => Ok(ApiResponse::success(lifecycle).with_anchor(network.quorum_cache.snapshot().data.anchor.clone())),
+        None => Err(AppError::NotFound("Quorum not found in history".to_string())),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/quorums/signed",
+    tag = "quorums",
+    responses(
+        (status = 200, description = "The current quorum list with its block and signature", body = SignedQuorumsResponse),
+        (status = 404, description = "Signing is not configured", body = ErrorResponse),
+        (status = 503, description = "No signed list has been published yet", body = ErrorResponse),
+    )
+)]
+async fn get_signed_quorums(
+    State(network): State<SharedNetworkState>,
+) -> ApiResult<SignedQuorumListResponse> {
+    let Some(signer) = network.quorum_cache.signer() else {
+        return Err(AppError::NotFound("Quorum list signing is not configured".to_string()));
+    };
+    if network.quorum_cache.last_update().is_none() {
+        network.quorum_cache.refresh().await?;
+    }
+
+    let snapshot = network.quorum_cache.snapshot();
+    let (Some(anchor), Some(signature)) = (&snapshot.data.anchor, &snapshot.data.signature) else {
+        return Err(AppError::StaleData("No signed quorum list has been published since the last clear".to_string()));
+    };
+    Ok(ApiResponse::success(SignedQuorumListResponse {
+        height: anchor.height,
+        block_hash: anchor.block_hash.clone(),
+        algorithm: signer.algorithm(),
+        signature: hex::encode(signature),
+        quorums: snapshot.data.list.iter().map(QuorumEntryResponse::from).collect(),
+    })
+    .with_anchor(Some(anchor.clone())))
+}
+
+#[utoipa::path(
+    get,
+    path = "/signing-key",
+    tag = "quorums",
+    responses(
+        (status = 200, description = "The public key quorum lists are signed with", body = SigningKeyApiResponse),
+        (status = 404, description = "Signing is not configured", body = ErrorResponse),
+    )
+)]
+async fn get_signing_key(State(network): State<SharedNetworkState>) -> ApiResult<SigningKeyResponse> {
+    match network.quorum_cache.signer() {
+        Some(signer) => Ok(ApiResponse::success(SigningKeyResponse {
+            algorithm: signer.algorithm(),
+            public_key: hex::encode(signer.public_key()),
+        })),
+        None => Err(AppError::NotFound("Quorum list signing is not configured".to_string())),
+    }
+}
+
+/// The LLMQ types whose quorums are stored: the platform type, plus the
+/// ChainLock and InstantSend types when lock verification is enabled.
+fn served_llmqs(config: &Config) -> Vec<LlmqParams> {
+    let mut served = vec![config.network.platform_llmq()];
+    if config.quorum.verify_locks {
+        served.extend([config.network.chainlock_llmq(), config.network.instantsend_llmq()]);
+    }
+    served
+}
+
+/// The served LLMQ type named by `requested`, an id or a name. Defaults to
+/// the platform type.
+fn served_llmq(config: &Config, requested: Option<&str>) -> Result<LlmqParams, AppError> {
+    let served = served_llmqs(config);
+    let Some(requested) = requested else {
+        return Ok(served[0]);
+    };
+    served
+        .iter()
+        .find(|llmq| requested == llmq.name || requested.parse() == Ok(llmq.id))
+        .copied()
+        .ok_or_else(|| {
+            let names: Vec<String> = served.iter().map(|llmq| format!("{} ({})", llmq.id, llmq.name)).collect();
+            AppError::BadRequest(format!("LLMQ type {} is not served, only {}", requested, names.join(", ")))
+        })
+}
+
+/// The quorums of `llmq`, one of `served_llmqs`, out of a quorum list and
+/// the lock quorums loaded with it.
+fn llmq_quorums<'a>(
+    list: &'a QuorumList,
+    locks: Option<&'a LockQuorums>,
+    config: &Config,
+    llmq: LlmqParams,
+) -> Option<&'a QuorumList> {
+    if llmq == config.network.platform_llmq() {
+        return Some(list);
+    }
+    locks.map(|locks| if llmq == config.network.chainlock_llmq() { &locks.chainlock } else { &locks.instantsend })
+}
+
+/// The stored quorums of `llmq`, one of `served_llmqs`.
+fn stored_quorums<'a>(state: &'a QuorumState, config: &Config, llmq: LlmqParams) -> Result<&'a QuorumList, AppError> {
+    llmq_quorums(&state.list, state.locks.as_ref(), config, llmq)
+        .ok_or_else(|| AppError::StaleData("No lock quorums have been loaded since the last clear".to_string()))
+}
+
+/// The quorum of `llmq` that signs `request_id` at `height`, by default the
+/// height the quorum list was loaded at. When the stored list no longer holds
+/// every quorum the signer is picked from, they are loaded at that height.
+async fn select_signing_quorum(
+    network: &NetworkState,
+    state: &QuorumState,
+    llmq: LlmqParams,
+    request_id: &bls::Hash,
+    height: Option<u32>,
+) -> Result<QuorumListEntry, AppError> {
+    let Some(anchor) = &state.anchor else {
+        return Err(AppError::NotFound("No quorums to select from".to_string()));
+    };
+    let height = height.unwrap_or(anchor.height);
+    if height > anchor.height {
+        return Err(AppError::BadRequest(format!(
+            "Height {} is above the height quorums were loaded at ({})",
+            height, anchor.height
+        )));
+    }
+    let not_found = || AppError::NotFound(format!("No stored quorums sign at height {}", height));
+    if let Some(quorums) = bls::signing_quorums(stored_quorums(state, &network.config, llmq)?, llmq, height) {
+        return bls::select_signer(quorums, llmq, request_id).cloned().ok_or_else(not_found);
+    }
+
+    // Dash Core lists exactly the quorums a signer is picked from at the
+    // height they are taken at
+    let start = height.saturating_sub(bls::SIGN_HEIGHT_OFFSET);
+    let loaded = network.quorum_cache.quorums_at(start, &network.source.block_hash(start).await?).await?;
+    let (list, locks) = loaded.as_ref();
+    llmq_quorums(list, locks.as_ref(), &network.config, llmq)
+        .and_then(|quorums| bls::signing_quorums(quorums, llmq, height))
+        .and_then(|quorums| bls::select_signer(quorums, llmq, request_id).cloned())
+        .ok_or_else(not_found)
+}
+
+#[derive(Deserialize, IntoParams)]
+struct SelectQuery {
+    /// LLMQ type id or name. Defaults to the network's platform quorum type; the
+    /// ChainLock and InstantSend types are served when lock verification is enabled.
+    #[serde(rename = "type")]
+    llmq_type: Option<String>,
+    /// Hex encoded 32-byte request id, as Dash Core displays it.
+    request_id: String,
+    /// Height the request is signed at. Defaults to the height the quorum list was loaded at.
+    height: Option<u32>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/quorums/select",
+    tag = "quorums",
+    params(SelectQuery),
+    responses(
+        (status = 200, description = "The quorum Dash Core selects to sign the request id", body = QuorumResponse),
+        (status = 400, description = "Malformed request id, an LLMQ type that isn't served, \
+            or a height above the anchor", body = ErrorResponse),
+        (status = 404, description = "No stored quorums sign at the height", body = ErrorResponse),
+    )
+)]
+async fn select_quorum(
+    State(network): State<SharedNetworkState>,
+    Query(query): Query<SelectQuery>,
+) -> ApiResult<QuorumEntryResponse> {
+    let llmq = served_llmq(&network.config, query.llmq_type.as_deref())?;
+    let request_id = bls::parse_hash("request id", &query.request_id)?;
+    if network.quorum_cache.last_update().is_none() {
+        network.quorum_cache.refresh().await?;
+    }
+
+    let snapshot = network.quorum_cache.snapshot();
+    let quorum = select_signing_quorum(&network, &snapshot.data, llmq, &request_id, query.height).await?;
+    Ok(ApiResponse::success((&quorum).into()).with_anchor(snapshot.data.anchor.clone()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/verify",
+    tag = "quorums",
+    request_body = VerifyRequest,
+    responses(
+        (status = 200, description = "Whether the signature is valid", body = SignatureVerificationResponse),
+        (status = 400, description = "Malformed input, an LLMQ type that isn't served, or a network still on the legacy BLS scheme", body = ErrorResponse),
+        (status = 404, description = "Quorum not found", body = ErrorResponse),
+    )
+)]
+async fn verify_quorum_signature(
+    State(network): State<SharedNetworkState>,
+    JsonBody(request): JsonBody<VerifyRequest>,
+) -> ApiResult<SignatureVerification> {
+    let llmq = served_llmq(&network.config, request.llmq_type.map(|id| id.to_string()).as_deref())?;
+    let signature = hex::decode(&request.signature)
+        .map_err(|_| AppError::BadRequest("Invalid signature. Must be hex encoded.".to_string()))?;
+    let request_id = request.request_id.as_deref().map(|id| bls::parse_hash("request id", id)).transpose()?;
+    if network.quorum_cache.last_update().is_none() {
+        network.quorum_cache.refresh().await?;
+    }
+
+    let snapshot = network.quorum_cache.snapshot();
+    snapshot.data.bls_scheme.ensure_verifiable()?;
+    let quorum = match (&request.quorum_hash, &request_id) {
+        (Some(quorum_hash), _) => stored_quorums(&snapshot.data, &network.config, llmq)?
+            .get_entry(&bls::parse_hash("quorum hash", quorum_hash)?)
+            .cloned()
+            .ok_or_else(|| AppError::NotFound("Quorum not found".to_string()))?,
+        (None, Some(request_id)) => select_signing_quorum(&network, &snapshot.data, llmq, request_id, None).await?,
+        (None, None) => return Err(AppError::BadRequest("Either quorum_hash or request_id is required".to_string())),
+    };
+    let quorum_hash: bls::Hash = quorum
+        .quorum_hash
+        .as_slice()
+        .try_into()
+        .map_err(|_| AppError::Internal("Stored quorum hash is not 32 bytes".to_string()))?;
+
+    let sign_hash = match (&request.sign_hash, &request_id, &request.msg_hash) {
+        (Some(sign_hash), _, _) => bls::parse_hash("sign hash", sign_hash)?,
+        (None, Some(request_id), Some(msg_hash)) => {
+            bls::sign_hash(llmq.id as u8, &quorum_hash, request_id, &bls::parse_hash("message hash", msg_hash)?)
+        }
+        _ => {
+            return Err(AppError::BadRequest(
+                "Either sign_hash or request_id and msg_hash are required".to_string(),
+            ))
         }
-        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    };
+
+    let verified = bls::verify_signature(&quorum.key, &sign_hash, &signature)?;
+    Ok(ApiResponse::success(SignatureVerification {
+        verified,
+        llmq_type: llmq.id,
+        quorum_hash: hex::encode(quorum_hash),
+        sign_hash: hex::encode(sign_hash),
+    })
+    .with_anchor(snapshot.data.anchor.clone()))
+}
+
+/// Decode a lock message and check it against the lock quorums stored with
+/// the current quorum list.
+async fn verify_lock(
+    network: &NetworkState,
+    message: &str,
+    verify: impl AsyncFnOnce(&[u8], &LockQuorums, u32) -> Result<LockVerification, AppError>,
+) -> ApiResult<LockVerification> {
+    if !network.config.quorum.verify_locks {
+        return Err(AppError::NotFound("Lock verification is not enabled".to_string()));
+    }
+    let message = hex::decode(message)
+        .map_err(|_| AppError::BadRequest("Invalid message. Must be hex encoded.".to_string()))?;
+    if network.quorum_cache.last_update().is_none() {
+        network.quorum_cache.refresh().await?;
     }
+
+    let snapshot = network.quorum_cache.snapshot();
+    let (Some(lock_quorums), Some(anchor)) = (&snapshot.data.locks, &snapshot.data.anchor) else {
+        return Err(AppError::StaleData("No lock quorums have been loaded since the last clear".to_string()));
+    };
+    snapshot.data.bls_scheme.ensure_verifiable()?;
+    Ok(ApiResponse::success(verify(&message, lock_quorums, anchor.height).await?).with_anchor(Some(anchor.clone())))
 }
 
-#[axum::debug_handler]
-async fn get_previous_quorums(
-    State((_, config, _)): State<(SharedQuorumList, SharedConfig, SharedMasternodeCache)>,
-) -> Result<Json<ApiResponse<QuorumsAtHeightResponse>>, StatusCode> {
-    match crate::quorum_loader::get_current_block_height(&config).await {
-        Ok(current_height) => {
-            let previous_height = if current_height >= config.quorum.previous_blocks_offset { 
-                current_height - config.quorum.previous_blocks_offset 
-            } else { 
-                0 
-            };
-            
-            match crate::quorum_loader::load_quorums_at_height(&config, previous_height).await {
-                Ok(quorum_list) => {
-                    let quorums: Vec<QuorumEntryResponse> = quorum_list.iter().map(|entry| entry.into()).collect();
-                    let response = QuorumsAtHeightResponse { height: previous_height, quorums };
-                    Ok(Json(ApiResponse::success(response)))
-                }
-                Err(e) => Ok(Json(ApiResponse::error(format!("Failed to load quorums: {}", e))))
+#[utoipa::path(
+    post,
+    path = "/verify/chainlock",
+    tag = "quorums",
+    request_body = LockVerifyRequest,
+    responses(
+        (status = 200, description = "Whether the ChainLock is signed by the quorum Dash Core selects for it", body = LockVerificationResponse),
+        (status = 400, description = "Malformed clsig message, a lock whose sign height is above the chain tip, \
+            or a network still on the legacy BLS scheme", body = ErrorResponse),
+        (status = 404, description = "Lock verification is not enabled", body = ErrorResponse),
+        (status = 503, description = "No lock quorums have been loaded yet", body = ErrorResponse),
+    )
+)]
+async fn verify_chainlock(
+    State(network): State<SharedNetworkState>,
+    JsonBody(request): JsonBody<LockVerifyRequest>,
+) -> ApiResult<LockVerification> {
+    let llmq = network.config.network.chainlock_llmq();
+    verify_lock(&network, &request.message, async |message, lock_quorums, anchor_height| {
+        // The stored quorums don't hold the signers of locks above the anchor,
+        // nor those that rotated out since the lock's sign height. Those locks
+        // are checked against the quorums listed at their sign height.
+        let sign_height = locks::chainlock_sign_height(message)?;
+        let signers_stored = sign_height <= anchor_height
+            && bls::signing_quorums(&lock_quorums.chainlock, llmq, sign_height + bls::SIGN_HEIGHT_OFFSET).is_some();
+        if signers_stored {
+            return locks::verify_chainlock(message, &lock_quorums.chainlock, llmq);
+        }
+        if sign_height > anchor_height {
+            let tip = network.source.block_count().await?;
+            if sign_height > tip {
+                return Err(AppError::BadRequest(format!(
+                    "ChainLock sign height {} is above the chain tip ({})",
+                    sign_height, tip
+                )));
             }
         }
-        Err(e) => Ok(Json(ApiResponse::error(format!("Failed to get block height: {}", e))))
+        let block_hash = network.source.block_hash(sign_height).await?;
+        let loaded = network.quorum_cache.quorums_at(sign_height, &block_hash).await?;
+        let Some(locks) = &loaded.1 else {
+            return Err(AppError::Internal("Lock quorums were not loaded with the quorum list".to_string()));
+        };
+        locks::verify_chainlock(message, &locks.chainlock, llmq)
+    })
+    .await
+}
+
+#[utoipa::path(
+    post,
+    path = "/verify/islock",
+    tag = "quorums",
+    request_body = LockVerifyRequest,
+    responses(
+        (status = 200, description = "Whether the InstantSend lock is signed by the quorum Dash Core selects for it", body = LockVerificationResponse),
+        (status = 400, description = "Malformed isdlock message, or a network still on the legacy BLS scheme", body = ErrorResponse),
+        (status = 404, description = "Lock verification is not enabled", body = ErrorResponse),
+        (status = 503, description = "No lock quorums have been loaded yet", body = ErrorResponse),
+    )
+)]
+async fn verify_islock(
+    State(network): State<SharedNetworkState>,
+    JsonBody(request): JsonBody<LockVerifyRequest>,
+) -> ApiResult<LockVerification> {
+    let llmq = network.config.network.instantsend_llmq();
+    verify_lock(&network, &request.message, async |message, lock_quorums, height| {
+        locks::verify_instantlock(message, &lock_quorums.instantsend, llmq, height)
+    })
+    .await
+}
+
+#[utoipa::path(
+    post,
+    path = "/quorums/clear",
+    tag = "quorums",
+    responses((status = 200, description = "Quorum list cleared", body = StringResponse))
+)]
+async fn clear_quorums(
+    State(network): State<SharedNetworkState>,
+) -> ApiResult<String> {
+    network.quorum_cache.clear();
+    Ok(ApiResponse::success("All quorums cleared successfully".to_string()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/previous",
+    tag = "quorums",
+    responses(
+        (status = 200, description = "Quorums as of `previous_blocks_offset` blocks below the tip", body = PreviousQuorumsResponse),
+        (status = 502, description = "Dash Core returned an error", body = ErrorResponse),
+        (status = 503, description = "Dash Core unavailable", body = ErrorResponse),
+        (status = 504, description = "Dash Core timed out", body = ErrorResponse),
+    )
+)]
+#[axum::debug_handler]
+async fn get_previous_quorums(
+    State(network): State<SharedNetworkState>,
+) -> ApiResult<QuorumsAtHeightResponse> {
+    let config = &network.config;
+    let current_height = network.source.block_count().await?;
+    let previous_height = current_height.saturating_sub(config.quorum.previous_blocks_offset);
+
+    let anchor = BlockAnchor::at_height(network.source.as_ref(), previous_height).await?;
+    let quorum_list = crate::quorum_loader::load_quorums_at_height(network.source.as_ref(), config, previous_height).await?;
+    let quorums: Vec<QuorumEntryResponse> = quorum_list.iter().map(|entry| entry.into()).collect();
+    let response = QuorumsAtHeightResponse { height: previous_height, quorums };
+    Ok(ApiResponse::success(response).with_anchor(Some(anchor)))
+}
+
+#[derive(Deserialize, IntoParams)]
+struct DiffQuery {
+    /// Height of the list the client has. At most `diff_window_blocks` (576 by
+    /// default) below the published list.
+    from: u32,
+    /// Height to diff to. Defaults to the height the current quorum list was loaded at.
+    to: Option<u32>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/quorums/diff",
+    tag = "quorums",
+    params(DiffQuery),
+    responses(
+        (status = 200, description = "Quorums added and removed between the two heights, per LLMQ type", body = QuorumDiffApiResponse),
+        (status = 400, description = "from above to, from more than `diff_window_blocks` below the published list, \
+            or to above the chain tip", body = ErrorResponse),
+        (status = 502, description = "Dash Core returned an error", body = ErrorResponse),
+        (status = 503, description = "Dash Core unavailable", body = ErrorResponse),
+    )
+)]
+async fn get_quorum_diff(
+    State(network): State<SharedNetworkState>,
+    Query(query): Query<DiffQuery>,
+) -> ApiResult<QuorumDiffResponse> {
+    let config = &network.config;
+    let source = network.source.as_ref();
+    if network.quorum_cache.last_update().is_none() {
+        network.quorum_cache.refresh().await?;
     }
+    let published = match &network.quorum_cache.snapshot().data.anchor {
+        Some(anchor) => anchor.height,
+        None => return Err(AppError::StaleData("No quorum list has been published since the last clear".to_string())),
+    };
+    let to = query.to.unwrap_or(published);
+    if query.from > to {
+        return Err(AppError::BadRequest("from must not be greater than to".to_string()));
+    }
+    // Every height diffed costs a full quorum load, so only recent ones are served
+    let window_start = published.saturating_sub(config.quorum.diff_window_blocks);
+    if query.from < window_start {
+        return Err(AppError::BadRequest(format!(
+            "from {} is more than {} blocks below the published list ({})",
+            query.from, config.quorum.diff_window_blocks, published
+        )));
+    }
+    let tip = source.block_count().await?;
+    if to > tip {
+        return Err(AppError::BadRequest(format!("Height {} is above the chain tip ({})", to, tip)));
+    }
+
+    let anchor = BlockAnchor::at_height(source, to).await?;
+    let base_hash = source.block_hash(query.from).await?;
+    let base = network.quorum_cache.quorums_at(query.from, &base_hash).await?;
+    let current = network.quorum_cache.quorums_at(to, &anchor.block_hash).await?;
+    let mut lists = vec![(config.get_llmq_type(), &base.0, &current.0)];
+    if let (Some(base), Some(locks)) = (&base.1, &current.1) {
+        lists.push((config.network.chainlock_llmq().name, &base.chainlock, &locks.chainlock));
+        lists.push((config.network.instantsend_llmq().name, &base.instantsend, &locks.instantsend));
+    }
+
+    let llmq_types = lists
+        .iter()
+        .map(|(llmq_type, base, list)| {
+            let (added, removed) = base.diff(list);
+            QuorumTypeDiff {
+                llmq_type: llmq_type.to_string(),
+                added: added.into_iter().map(Into::into).collect(),
+                removed: removed.into_iter().map(Into::into).collect(),
+            }
+        })
+        .collect();
+    Ok(ApiResponse::success(QuorumDiffResponse { base_height: query.from, height: to, llmq_types }).with_anchor(Some(anchor)))
 }
 
+#[utoipa::path(
+    get,
+    path = "/masternodes",
+    tag = "masternodes",
+    responses(
+        (status = 200, description = "Evo masternodes with their platform version check", body = MasternodeListResponse),
+        (status = 304, description = "List unchanged since the `If-None-Match` ETag"),
+        (status = 503, description = "Dash Core unavailable or cached list too old", body = ErrorResponse),
+        (status = 504, description = "Dash Core timed out", body = ErrorResponse),
+    )
+)]
 #[axum::debug_handler]
 async fn get_masternodes(
-    State((_, _, masternode_cache)): State<(SharedQuorumList, SharedConfig, SharedMasternodeCache)>,
-) -> Result<Json<ApiResponse<EvoMasternodeList>>, StatusCode> {
-    match masternode_cache.get_masternodes().await {
-        Ok(masternodes) => Ok(Json(ApiResponse::success(masternodes))),
-        Err(e) => Ok(Json(ApiResponse::error(format!("Failed to load masternodes: {}", e))))
+    State(network): State<SharedNetworkState>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    let snapshot = network.masternode_cache.get_masternodes().await?;
+    let format = ResponseFormat::current();
+    let body = snapshot.response_body(format, |masternodes| encode(format, ApiResponse::success(masternodes.clone())));
+    let max_age = snapshot.max_age(network.masternode_cache.update_interval());
+    Ok(body.respond(&headers, max_age).await)
+}
+
+/// Fail with 400 unless `height` is at or below the chain tip; returns the tip.
+async fn check_below_tip(network: &NetworkState, height: u32) -> Result<u32, AppError> {
+    let tip = network.source.block_count().await?;
+    if height > tip {
+        return Err(AppError::BadRequest(format!("Height {} is above the chain tip ({})", height, tip)));
+    }
+    Ok(tip)
+}
+
+#[utoipa::path(
+    get,
+    path = "/masternodes/at/{height}",
+    tag = "masternodes",
+    params(("height" = u32, Path, description = "Block height")),
+    responses(
+        (status = 200, description = "Evo masternodes registered at the height, without version checks", body = MasternodesAtHeightApiResponse),
+        (status = 400, description = "Height above the chain tip", body = ErrorResponse),
+        (status = 502, description = "Dash Core returned an error", body = ErrorResponse),
+        (status = 503, description = "Dash Core unavailable", body = ErrorResponse),
+    )
+)]
+async fn get_masternodes_at_height(
+    State(network): State<SharedNetworkState>,
+    Path(height): Path<u32>,
+) -> ApiResult<MasternodesAtHeightResponse> {
+    check_below_tip(&network, height).await?;
+    let masternodes = masternode_loader::load_masternode_list_at(network.source.as_ref(), height).await?;
+    let masternodes = masternodes.into_iter().map(Into::into).collect();
+    Ok(ApiResponse::success(MasternodesAtHeightResponse { height, masternodes }))
+}
+
+#[derive(Deserialize, IntoParams)]
+struct MasternodeDiffQuery {
+    /// Height to diff from.
+    from: u32,
+    /// Height to diff to. Defaults to the chain tip.
+    to: Option<u32>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/masternodes/diff",
+    tag = "masternodes",
+    params(MasternodeDiffQuery),
+    responses(
+        (status = 200, description = "Evo masternodes added, removed and updated between the two heights", body = MasternodeDiffApiResponse),
+        (status = 400, description = "from above to, or to above the chain tip", body = ErrorResponse),
+        (status = 502, description = "Dash Core returned an error", body = ErrorResponse),
+        (status = 503, description = "Dash Core unavailable", body = ErrorResponse),
+    )
+)]
+async fn get_masternode_diff(
+    State(network): State<SharedNetworkState>,
+    Query(query): Query<MasternodeDiffQuery>,
+) -> ApiResult<MasternodeDiffResponse> {
+    let source = network.source.as_ref();
+    let to = match query.to {
+        Some(to) => {
+            check_below_tip(&network, to).await?;
+            to
+        }
+        None => source.block_count().await?,
+    };
+    if query.from > to {
+        return Err(AppError::BadRequest("from must not be greater than to".to_string()));
+    }
+
+    let base = masternode_loader::load_masternode_list_at(source, query.from).await?;
+    let mut diff = masternode_loader::load_masternode_diff(source, query.from, to).await?;
+    diff.retain_evo(&base.iter().map(|masternode| masternode.pro_tx_hash.clone()).collect());
+    let updated = diff.updates().collect();
+    let removed = base
+        .into_iter()
+        .filter(|masternode| diff.removed.contains(&masternode.pro_tx_hash))
+        .map(Into::into)
+        .collect();
+    Ok(ApiResponse::success(MasternodeDiffResponse {
+        base_height: query.from,
+        height: to,
+        added: diff.added.into_iter().map(Into::into).collect(),
+        removed,
+        updated,
+    }))
+}
+
+#[derive(Deserialize, IntoParams)]
+struct MasternodeHistoryQuery {
+    /// Only events at or after this height.
+    from: Option<u32>,
+    /// Only events at or before this height.
+    to: Option<u32>,
+    /// Only events of this masternode.
+    pro_tx_hash: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/masternodes/history",
+    tag = "masternodes",
+    params(MasternodeHistoryQuery),
+    responses(
+        (status = 200, description = "Registrations, updates, PoSe bans, revivals and removals of Evo masternodes \
+            since the server started, oldest first", body = MasternodeHistoryResponse),
+        (status = 400, description = "An empty height range", body = ErrorResponse),
+    )
+)]
+async fn get_masternode_history(
+    State(network): State<SharedNetworkState>,
+    Query(query): Query<MasternodeHistoryQuery>,
+) -> ApiResult<Vec<MasternodeEvent>> {
+    let (from, to) = (query.from.unwrap_or(0), query.to.unwrap_or(u32::MAX));
+    if from > to {
+        return Err(AppError::BadRequest("from must not be greater than to".to_string()));
     }
+    if network.masternode_cache.history().height().is_none() {
+        network.masternode_cache.record_history(network.source.block_count().await?).await;
+    }
+
+    let history = network.masternode_cache.history();
+    Ok(ApiResponse::success(history.events_between(query.pro_tx_hash.as_deref(), from, to).cloned().collect()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/masternodes/{pro_tx_hash}/history",
+    tag = "masternodes",
+    params(("pro_tx_hash" = String, Path, description = "Hex encoded proTxHash of an Evo masternode")),
+    responses(
+        (status = 200, description = "The node's uptime over the last hour, day and week, and its latest \
+            version checks, oldest first", body = ProbeHistoryResponse),
+        (status = 404, description = "Node never probed, or not within the last week", body = ErrorResponse),
+    )
+)]
+async fn get_probe_history(
+    State(network): State<SharedNetworkState>,
+    Path(pro_tx_hash): Path<String>,
+) -> ApiResult<NodeProbeHistory> {
+    let history = network.masternode_cache.probe_history().node(&pro_tx_hash.to_lowercase(), probe_history::unix_now());
+    history
+        .map(ApiResponse::success)
+        .ok_or_else(|| AppError::NotFound(format!("No probes recorded for masternode {}", pro_tx_hash)))
+}
+
+#[derive(Deserialize, IntoParams)]
+struct DapiAddressesQuery {
+    /// Return at most this many addresses.
+    limit: Option<usize>,
+    /// Shuffle the addresses instead of ranking them, to spread clients' load.
+    #[serde(default)]
+    shuffle: bool,
+}
+
+#[utoipa::path(
+    get,
+    path = "/dapi/addresses",
+    tag = "masternodes",
+    params(DapiAddressesQuery),
+    responses(
+        (status = 200, description = "DAPI endpoints of Evo masternodes whose latest version check succeeded, \
+            ranked by uptime over the last day and then by latency. Checks use TLS, so the URIs are https", body = DapiAddressesResponse),
+        (status = 503, description = "Dash Core unavailable or cached list too old", body = ErrorResponse),
+        (status = 504, description = "Dash Core timed out", body = ErrorResponse),
+    )
+)]
+async fn get_dapi_addresses(
+    State(network): State<SharedNetworkState>,
+    Query(query): Query<DapiAddressesQuery>,
+) -> ApiResult<Vec<DapiAddress>> {
+    let snapshot = network.masternode_cache.get_masternodes().await?;
+    let probes = network.masternode_cache.probe_history();
+    let mut addresses = dapi_addresses::healthy_addresses(&snapshot.data, &probes, &network.config, probe_history::unix_now());
+    if query.shuffle {
+        dapi_addresses::shuffle(&mut addresses);
+    }
+    if let Some(limit) = query.limit {
+        addresses.truncate(limit);
+    }
+    Ok(ApiResponse::success(addresses))
+}
+
+// Raw data source passthrough, only routed with `server.expose_core`. A
+// downstream server configured with an `http` source reads these instead of
+// talking to Dash Core itself.
+
+#[utoipa::path(
+    get,
+    path = "/core/status",
+    tag = "core",
+    responses(
+        (status = 200, description = "Sync state of the node behind the data source", body = CoreStatusResponse),
+        (status = 503, description = "Data source unavailable", body = ErrorResponse),
+    )
+)]
+async fn core_status(State(network): State<SharedNetworkState>) -> ApiResult<CoreSyncStatus> {
+    Ok(ApiResponse::success(network.source.sync_status().await?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/core/blockcount",
+    tag = "core",
+    responses(
+        (status = 200, description = "`getblockcount`", body = BlockCountResponse),
+        (status = 503, description = "Data source unavailable", body = ErrorResponse),
+    )
+)]
+async fn core_block_count(State(network): State<SharedNetworkState>) -> ApiResult<u32> {
+    Ok(ApiResponse::success(network.source.block_count().await?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/core/blockhash/{height}",
+    tag = "core",
+    params(("height" = u32, Path, description = "Block height")),
+    responses(
+        (status = 200, description = "`getblockhash`", body = BlockHashResponse),
+        (status = 502, description = "Data source returned an error", body = ErrorResponse),
+        (status = 503, description = "Data source unavailable", body = ErrorResponse),
+    )
+)]
+async fn core_block_hash(
+    State(network): State<SharedNetworkState>,
+    Path(height): Path<u32>,
+) -> ApiResult<BlockHash> {
+    Ok(ApiResponse::success(BlockHash(network.source.block_hash(height).await?)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/core/blockheader/{hash}",
+    tag = "core",
+    params(("hash" = String, Path, description = "Hex encoded block hash")),
+    responses(
+        (status = 200, description = "`getblockheader`", body = CoreDataResponse),
+        (status = 502, description = "Data source returned an error", body = ErrorResponse),
+        (status = 503, description = "Data source unavailable", body = ErrorResponse),
+    )
+)]
+async fn core_block_header(
+    State(network): State<SharedNetworkState>,
+    Path(hash): Path<String>,
+) -> ApiResult<serde_json::Value> {
+    Ok(ApiResponse::success(network.source.block_header(&hash).await?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/core/chainlock",
+    tag = "core",
+    responses(
+        (status = 200, description = "`getbestchainlock`", body = CoreDataResponse),
+        (status = 503, description = "Data source unavailable", body = ErrorResponse),
+    )
+)]
+async fn core_best_chainlock(State(network): State<SharedNetworkState>) -> ApiResult<serde_json::Value> {
+    Ok(ApiResponse::success(network.source.best_chainlock().await?))
+}
+
+#[derive(Deserialize, IntoParams)]
+struct CoreQuorumsQuery {
+    /// List the quorums as of this height instead of the chain tip.
+    height: Option<u32>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/core/quorums",
+    tag = "core",
+    params(CoreQuorumsQuery),
+    responses(
+        (status = 200, description = "`quorum listextended`", body = CoreDataResponse),
+        (status = 503, description = "Data source unavailable", body = ErrorResponse),
+    )
+)]
+async fn core_list_quorums(
+    State(network): State<SharedNetworkState>,
+    Query(query): Query<CoreQuorumsQuery>,
+) -> ApiResult<serde_json::Value> {
+    Ok(ApiResponse::success(network.source.list_quorums(query.height).await?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/core/quorums/{llmq_type}/{quorum_hash}",
+    tag = "core",
+    params(
+        ("llmq_type" = u32, Path, description = "Numeric LLMQ type"),
+        ("quorum_hash" = String, Path, description = "Hex encoded quorum hash"),
+    ),
+    responses(
+        (status = 200, description = "`quorum info`", body = CoreDataResponse),
+        (status = 502, description = "Data source returned an error", body = ErrorResponse),
+        (status = 503, description = "Data source unavailable", body = ErrorResponse),
+    )
+)]
+async fn core_quorum_info(
+    State(network): State<SharedNetworkState>,
+    Path((llmq_type, quorum_hash)): Path<(u32, String)>,
+) -> ApiResult<serde_json::Value> {
+    Ok(ApiResponse::success(network.source.quorum_info(llmq_type, &quorum_hash).await?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/core/masternodes",
+    tag = "core",
+    responses(
+        (status = 200, description = "`masternode list`", body = CoreDataResponse),
+        (status = 503, description = "Data source unavailable", body = ErrorResponse),
+    )
+)]
+async fn core_masternode_list(State(network): State<SharedNetworkState>) -> ApiResult<serde_json::Value> {
+    Ok(ApiResponse::success(network.source.masternode_list().await?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/core/masternodes/at/{height}",
+    tag = "core",
+    params(("height" = u32, Path, description = "Block height")),
+    responses(
+        (status = 200, description = "`protx list evo true`", body = CoreDataResponse),
+        (status = 502, description = "Data source returned an error", body = ErrorResponse),
+        (status = 503, description = "Data source unavailable", body = ErrorResponse),
+    )
+)]
+async fn core_masternode_list_at(
+    State(network): State<SharedNetworkState>,
+    Path(height): Path<u32>,
+) -> ApiResult<serde_json::Value> {
+    Ok(ApiResponse::success(network.source.masternode_list_at(height).await?))
+}
+
+#[derive(Deserialize, IntoParams)]
+struct CoreMasternodeDiffQuery {
+    from: u32,
+    to: u32,
+}
+
+#[utoipa::path(
+    get,
+    path = "/core/masternodes/diff",
+    tag = "core",
+    params(CoreMasternodeDiffQuery),
+    responses(
+        (status = 200, description = "`protx listdiff`", body = CoreDataResponse),
+        (status = 502, description = "Data source returned an error", body = ErrorResponse),
+        (status = 503, description = "Data source unavailable", body = ErrorResponse),
+    )
+)]
+async fn core_masternode_list_diff(
+    State(network): State<SharedNetworkState>,
+    Query(query): Query<CoreMasternodeDiffQuery>,
+) -> ApiResult<serde_json::Value> {
+    Ok(ApiResponse::success(network.source.masternode_list_diff(query.from, query.to).await?))
 }
 
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,1322 @@
+use super::*;
+use crate::test_support::mock_rpc::{Exchange, MockRpcServer};
+use crate::test_support::{network_state, test_config};
+use axum::body::{to_bytes, Body};
+use axum::http::Request;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use tower::ServiceExt;
+
+// Values from tests/fixtures/rpc/testnet.json
+const QUORUM_0: &str = "0000003e67c3146720fa1b9504927ece5f5daefadaad467dddfd2ff04ba039be";
+const QUORUM_1: &str = "000000c1b7c3ae5252091267a736a9bf57001d80e82668b3cb3cd09e2f6a43cb";
+const QUORUM_2: &str = "000000f120e8906382754c6be52860ac5dbc65a1ca4dbee7576267d8fd3367e1";
+const KEY_0: &str = "81a5ac9a015fac2ef7b341673635512a1511f41fe37d111b267f039eec5d4f588fa2d1f05eba0c8275b6a912afaebbde";
+const KEY_1: &str = "8ab9f1eb8f7d3388f4f9d586f66e99fd54080df2c446f0e58668b09c08a16dd06ea799169df728d7e4acb061232750c2";
+const KEY_2: &str = "815f7e6bc5aeaf483724089e9252cc13b50951a6b69412522765cff4d780306e058d942ffac9f7237367363b02c349c4";
+const TIP_HASH: &str = "000000aa00000000000000000000000000000000000000000000000000000000";
+const PREVIOUS_HASH: &str = "0000cfe54786a1d98a5c2dbfade51f0af6913c8bbbee7a0cec9968be5b3c7a61";
+const CHAINLOCK_SIGNATURE: &str = "a30239bebbedff92b35bc50a4528755db71b463889360851ff391958bc708bd3283c9d3e74f799f263598270aadbda723a9eeb23d5e4736cea9c38c6feb4f63f07f42d55eccdbc74a6801b505beb897f8ec15d48479d0f0aa4847987616f7613";
+const QUORUM_50_60: &str = "0000001934fdd9cfdd6d0e892cb6ca4894abc58559de0ec04d51bc2801bad291";
+const EVO_BANNED: &str = "d37c2332e5c67e3777ed934e840e9739cfeb35d03a39e163c120a72c86757819";
+const EVO_BAD_ADDRESS: &str = "074720f4a9b8aa9cf929fa6c5afcb348b978226f7ecde7e391bf37f0f64e254b";
+const EVO_RETIRED: &str = "5c0fd2a8d2b1be4c9a27b0f1f0b1f58b0e3a1e7f8d8c09d6d2a0d3e6d4c9b1a2";
+
+fn quorum_json(hash: &str, key: &str, height: u32, valid_members: u32) -> Value {
+    json!({
+        "quorum_hash": hash,
+        "key": key,
+        "height": height,
+        "members": [],
+        "threshold_signature": "",
+        "mining_members_count": 0,
+        "valid_members_count": valid_members,
+    })
+}
+
+fn success(data: Value) -> Value {
+    json!({ "success": true, "data": data, "message": null, "code": null })
+}
+
+/// A successful quorum response anchored at the ChainLocked tip of the fixture.
+fn anchored(data: Value) -> Value {
+    let mut response = success(data);
+    response["anchor"] = json!({ "height": 1100050, "block_hash": TIP_HASH, "chainlock_signature": CHAINLOCK_SIGNATURE });
+    response
+}
+
+fn fixture_exchanges(name: &str) -> Vec<Exchange> {
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join(format!("tests/fixtures/rpc/{}.json", name));
+    serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+}
+
+/// Replace the key of every quorum `quorum info` describes with one derived
+/// from a known secret, so tests can sign as the quorums. Returns the secret
+/// of each quorum by hash.
+fn key_quorums(exchanges: &mut [Exchange]) -> HashMap<String, blst::min_pk::SecretKey> {
+    let mut secrets = HashMap::new();
+    for exchange in exchanges {
+        if exchange.method == "quorum" && exchange.params.first() == Some(&json!("info")) {
+            let quorum_hash = exchange.params[2].as_str().unwrap().to_string();
+            let secret = blst::min_pk::SecretKey::key_gen(quorum_hash.as_bytes(), &[]).unwrap();
+            exchange.result["quorumPublicKey"] = json!(hex::encode(secret.sk_to_pk().to_bytes()));
+            secrets.insert(quorum_hash, secret);
+        }
+    }
+    secrets
+}
+
+/// The testnet fixture with quorum keys derived from known secrets.
+fn testnet_with_quorum_secrets() -> (MockRpcServer, HashMap<String, blst::min_pk::SecretKey>) {
+    let mut exchanges = fixture_exchanges("testnet");
+    let secrets = key_quorums(&mut exchanges);
+    (MockRpcServer::start(exchanges), secrets)
+}
+
+/// The testnet fixture with keys for the ChainLock quorum it lists and one
+/// full llmq_60_75 rotation cycle, created at 1099872, added at the tip.
+fn testnet_with_lock_quorums() -> (MockRpcServer, HashMap<String, blst::min_pk::SecretKey>) {
+    let (exchanges, secrets) = lock_quorum_exchanges();
+    (MockRpcServer::start(exchanges), secrets)
+}
+
+fn lock_quorum_exchanges() -> (Vec<Exchange>, HashMap<String, blst::min_pk::SecretKey>) {
+    let mut exchanges = fixture_exchanges("testnet");
+    let mut quorums = vec![(1, "llmq_50_60", QUORUM_50_60.to_string(), 1099920)];
+    quorums.extend((0..32).map(|index| (5, "llmq_60_75", rotated_quorum(index), 1099872 + index)));
+
+    let tip_list = exchanges
+        .iter_mut()
+        .find(|exchange| exchange.params == [json!("listextended"), json!(1100050)])
+        .unwrap();
+    tip_list.result["llmq_60_75"] = quorums
+        .iter()
+        .filter(|(llmq_type, ..)| *llmq_type == 5)
+        .map(|(_, _, hash, height)| json!({ hash: { "creationHeight": height, "quorumIndex": height - 1099872, "numValidMembers": 60 } }))
+        .collect();
+    for (llmq_type, name, hash, height) in quorums {
+        exchanges.push(Exchange {
+            method: "quorum".to_string(),
+            params: vec![json!("info"), json!(llmq_type), json!(hash)],
+            result: json!({ "height": height, "type": name, "quorumHash": hash, "quorumPublicKey": "" }),
+            error: None,
+        });
+    }
+    let secrets = key_quorums(&mut exchanges);
+    (exchanges, secrets)
+}
+
+fn rotated_quorum(index: u32) -> String {
+    format!("{:02x}", 0x60 + index).repeat(32)
+}
+
+/// Router serving a single "testnet" network backed by `rpc`.
+fn testnet_router(rpc: &MockRpcServer) -> (Router, SharedNetworkState) {
+    let network = network_state("testnet", test_config(&rpc.url));
+    (create_router(std::slice::from_ref(&network), "testnet"), network)
+}
+
+async fn send(router: &Router, method: &str, uri: &str) -> (StatusCode, axum::http::HeaderMap, Vec<u8>) {
+    let request = Request::builder().method(method).uri(uri).body(Body::empty()).unwrap();
+    let response = router.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let headers = response.headers().clone();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, headers, body.to_vec())
+}
+
+async fn send_with_headers(
+    router: &Router,
+    uri: &str,
+    headers: &[(&str, &str)],
+) -> (StatusCode, axum::http::HeaderMap, Vec<u8>) {
+    let mut request = Request::builder().uri(uri);
+    for (name, value) in headers {
+        request = request.header(*name, *value);
+    }
+    let response = router.clone().oneshot(request.body(Body::empty()).unwrap()).await.unwrap();
+    let status = response.status();
+    let headers = response.headers().clone();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, headers, body.to_vec())
+}
+
+async fn send_json(router: &Router, method: &str, uri: &str) -> (StatusCode, Value) {
+    let (status, _, body) = send(router, method, uri).await;
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+async fn post_json(router: &Router, uri: &str, body: Value) -> (StatusCode, Value) {
+    let request = Request::builder()
+        .method("POST")
+        .uri(uri)
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap();
+    let response = router.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+/// Documentation routes, served alongside the network routes but not part of the API.
+const DOCUMENTATION_ROUTES: [&str; 2] = ["/docs", "/openapi.json"];
+
+#[test]
+fn every_network_route_is_documented() {
+    let doc = ApiDoc::openapi();
+    for (path, _) in network_routes() {
+        // axum uses `:param`, OpenAPI uses `{param}`
+        let openapi_path = path
+            .split('/')
+            .map(|segment| match segment.strip_prefix(':') {
+                Some(param) => format!("{{{}}}", param),
+                None => segment.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("/");
+        assert!(
+            doc.paths.paths.contains_key(&openapi_path),
+            "route {} is missing from ApiDoc",
+            path
+        );
+    }
+}
+
+/// Whether the router has a route for `method` and `uri`: axum answers
+/// unrouted requests with an empty 404 or 405, while every handler error
+/// carries an `ApiResponse` body.
+async fn is_routed(router: &Router, method: &str, uri: &str) -> bool {
+    let (status, _, body) = send(router, method, uri).await;
+    let unrouted = matches!(status, StatusCode::NOT_FOUND | StatusCode::METHOD_NOT_ALLOWED) && body.is_empty();
+    !unrouted
+}
+
+#[tokio::test]
+async fn every_documented_route_is_served() {
+    // Nothing listens on port 1, so handlers needing Dash Core fail fast
+    let mut config = test_config("http://127.0.0.1:1");
+    config.server.expose_core = true;
+    let router = create_router(&[network_state("testnet", config)], "testnet");
+    let doc = ApiDoc::openapi();
+
+    for (path, item) in &doc.paths.paths {
+        let uri = path
+            .split('/')
+            .map(|segment| if segment.starts_with('{') { "00".repeat(32) } else { segment.to_string() })
+            .collect::<Vec<_>>()
+            .join("/");
+        for method in item.operations.keys() {
+            let method = serde_json::to_value(method).unwrap().as_str().unwrap().to_uppercase();
+            for uri in [uri.clone(), format!("/testnet{}", uri)] {
+                assert!(is_routed(&router, &method, &uri).await, "{} {} is documented but not served", method, uri);
+            }
+        }
+    }
+    for path in DOCUMENTATION_ROUTES {
+        assert!(!doc.paths.paths.contains_key(path));
+        let uri = if path == "/docs" { "/docs/" } else { path };
+        assert!(is_routed(&router, "GET", uri).await, "{} is not served", path);
+    }
+    assert!(!is_routed(&router, "GET", "/undocumented").await);
+    assert!(!is_routed(&router, "DELETE", "/quorums").await);
+}
+
+#[tokio::test]
+async fn core_passthrough_is_opt_in() {
+    let router = create_router(&[network_state("testnet", test_config("http://127.0.0.1:1"))], "testnet");
+    for (path, _) in network_routes().into_iter().filter(|(path, _)| path.starts_with("/core/")) {
+        let uri = path.split('/').map(|segment| if segment.starts_with(':') { "1" } else { segment }).collect::<Vec<_>>().join("/");
+        assert!(!is_routed(&router, "GET", &uri).await, "{} is served without expose_core", uri);
+    }
+    assert!(is_routed(&router, "GET", "/quorums").await);
+}
+
+#[test]
+fn error_responses_are_documented_as_sent() {
+    let body = serde_json::to_value(ApiResponse::<NoData>::error("not_found", "Quorum not found".to_string())).unwrap();
+    assert_eq!(body, json!({ "success": false, "data": null, "message": "Quorum not found", "code": "not_found" }));
+
+    let doc = serde_json::to_value(ApiDoc::openapi()).unwrap();
+    let data = &doc["components"]["schemas"]["ErrorResponse"]["properties"]["data"];
+    assert_eq!(data["allOf"][0]["$ref"], "#/components/schemas/NoData");
+    assert_eq!(doc["components"]["schemas"]["NoData"], json!({ "default": null, "nullable": true }));
+}
+
+#[test]
+fn network_names_cannot_shadow_routes() {
+    for (path, _) in network_routes() {
+        let segment = path.trim_start_matches('/').split('/').next().unwrap();
+        assert!(
+            crate::config::RESERVED_NETWORK_NAMES.contains(&segment),
+            "{} is missing from RESERVED_NETWORK_NAMES",
+            segment
+        );
+    }
+
+    let section = |name: &str| crate::config::NetworkSection {
+        name: name.to_string(),
+        network: crate::config::Network::Testnet,
+        rpc: Default::default(),
+        source: None,
+        quorum: None,
+        docker: None,
+        dapi: None,
+    };
+    let mut config = test_config("http://127.0.0.1:1");
+    config.networks = vec![section("testnet"), section("core")];
+    assert_eq!(config.network_configs().unwrap_err(), "Network name 'core' is reserved for a route");
+    config.networks = vec![section("testnet"), section("testnet")];
+    assert_eq!(config.network_configs().unwrap_err(), "Duplicate network name 'testnet'");
+    config.networks = vec![section("testnet"), section("devnet")];
+    assert_eq!(config.network_configs().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn health_and_liveness() {
+    let rpc = MockRpcServer::from_fixture("testnet");
+    let (router, _) = testnet_router(&rpc);
+
+    assert_eq!(
+        send_json(&router, "GET", "/health").await,
+        (StatusCode::OK, success(json!("Quorum List Server is running")))
+    );
+    assert_eq!(
+        send_json(&router, "GET", "/health/live").await,
+        (StatusCode::OK, success(json!("alive")))
+    );
+}
+
+#[tokio::test]
+async fn readiness_fails_before_first_refresh() {
+    let rpc = MockRpcServer::from_fixture("testnet");
+    let (router, _) = testnet_router(&rpc);
+
+    let (status, body) = send_json(&router, "GET", "/health/ready").await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(body["success"], json!(false));
+    assert_eq!(body["code"], json!("not_ready"));
+    assert_eq!(body["data"]["rpc_reachable"], json!(true));
+    assert_eq!(
+        body["data"]["problems"],
+        json!([
+            "Quorum list has never been loaded",
+            "Quorum list is empty",
+            "Masternode cache has never been populated",
+        ])
+    );
+}
+
+#[tokio::test]
+async fn readiness_passes_once_caches_are_fresh() {
+    let rpc = MockRpcServer::from_fixture("testnet");
+    let (router, network) = testnet_router(&rpc);
+    network.quorum_cache.refresh().await.unwrap();
+    network.masternode_cache.get_masternodes().await.unwrap();
+
+    let (status, body) = send_json(&router, "GET", "/health/ready").await;
+    assert_eq!(status, StatusCode::OK);
+    let report = &body["data"];
+    assert_eq!(report["ready"], json!(true));
+    assert_eq!(report["quorum_count"], json!(2));
+    assert_eq!(report["problems"], json!([]));
+    assert_eq!(
+        report["core"],
+        json!({
+            "chain": "test",
+            "blocks": 1100050,
+            "headers": 1100050,
+            "verification_progress": 0.9999987,
+            "initial_block_download": false,
+            "blockchain_synced": true,
+            "masternode_sync_asset": "MASTERNODE_SYNC_FINISHED",
+            "masternode_sync_finished": true,
+            "bls_legacy_scheme": false,
+        })
+    );
+}
+
+#[tokio::test]
+async fn quorums_are_loaded_from_core() {
+    let rpc = MockRpcServer::from_fixture("testnet");
+    let (router, _) = testnet_router(&rpc);
+
+    let (status, body) = send_json(&router, "GET", "/quorums").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        body,
+        anchored(json!([
+            quorum_json(QUORUM_0, KEY_0, 1100016, 25),
+            quorum_json(QUORUM_1, KEY_1, 1099992, 24),
+        ]))
+    );
+
+    assert_eq!(
+        send_json(&router, "GET", "/quorums/stats").await,
+        (StatusCode::OK, anchored(json!({ "total_count": 2, "is_empty": false })))
+    );
+}
+
+#[tokio::test]
+async fn quorums_are_served_from_the_published_snapshot() {
+    let rpc = MockRpcServer::from_fixture("testnet");
+    let (router, network) = testnet_router(&rpc);
+    let listextended_calls = || {
+        rpc.calls()
+            .iter()
+            .filter(|(method, params)| method == "quorum" && params.first() == Some(&json!("listextended")))
+            .count()
+    };
+
+    let (_, headers, first) = send(&router, "GET", "/quorums").await;
+    assert_eq!(headers["content-type"], "application/json");
+    let held = network.quorum_cache.snapshot();
+    let (_, _, second) = send(&router, "GET", "/quorums").await;
+    assert_eq!(first, second);
+    assert_eq!(listextended_calls(), 1);
+
+    // Publishing a new snapshot leaves readers of the old one untouched
+    send(&router, "POST", "/quorums/clear").await;
+    assert_eq!(held.data.list.len(), 2);
+    assert_eq!(network.quorum_cache.snapshot().data.list.len(), 0);
+    assert_eq!(send_json(&router, "GET", "/quorums").await, (StatusCode::OK, success(json!([]))));
+
+    network.quorum_cache.refresh().await.unwrap();
+    assert_eq!(send(&router, "GET", "/quorums").await.2, first);
+    assert_eq!(listextended_calls(), 2);
+}
+
+#[tokio::test]
+async fn concurrent_masternode_requests_share_one_refresh() {
+    let rpc = MockRpcServer::from_fixture("testnet");
+    let (router, _) = testnet_router(&rpc);
+
+    let responses = futures::future::join_all((0..4).map(|_| send(&router, "GET", "/masternodes"))).await;
+    assert!(responses.iter().all(|(status, _, body)| *status == StatusCode::OK && *body == responses[0].2));
+    let list_calls = rpc.calls().iter().filter(|(method, _)| method == "masternode").count();
+    assert_eq!(list_calls, 1);
+}
+
+#[tokio::test]
+async fn concurrent_quorum_refreshes_run_one_at_a_time() {
+    let rpc = MockRpcServer::from_fixture("testnet");
+    let (_, network) = testnet_router(&rpc);
+
+    let (first, second) = tokio::join!(network.quorum_cache.refresh(), network.quorum_cache.refresh());
+    assert_eq!(first.unwrap(), second.unwrap());
+    // The second refresh starts only once the first has made all its calls
+    let methods: Vec<String> = rpc.calls().into_iter().map(|(method, _)| method).collect();
+    let second_start = methods.iter().rposition(|method| method == "getbestchainlock").unwrap();
+    let first_end = methods.iter().position(|method| method == "mnsync").unwrap();
+    assert!(first_end < second_start, "refreshes overlapped: {:?}", methods);
+}
+
+/// `/masternodes` once no node's version check is `pending`.
+async fn checked_masternodes(router: &Router) -> Value {
+    for _ in 0..100 {
+        let body = send_json(router, "GET", "/masternodes").await.1;
+        if !body["data"].as_array().unwrap().iter().any(|node| node["versionCheck"] == "pending") {
+            return body;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    panic!("version checks didn't complete");
+}
+
+#[tokio::test]
+async fn cached_lists_are_compressed_and_revalidated() {
+    let rpc = MockRpcServer::from_fixture("testnet");
+    let (router, network) = testnet_router(&rpc);
+    // Published version checks would change the list between requests
+    checked_masternodes(&router).await;
+
+    for uri in ["/quorums", "/masternodes"] {
+        let (status, headers, plain) = send(&router, "GET", uri).await;
+        assert_eq!(status, StatusCode::OK, "{}", uri);
+        assert!(headers.get("content-encoding").is_none());
+        assert_eq!(headers["vary"], "accept, accept-encoding");
+        assert!(headers.contains_key("last-modified"));
+        let max_age: u64 = headers["cache-control"]
+            .to_str()
+            .unwrap()
+            .strip_prefix("public, max-age=")
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!(max_age > 0 && max_age <= 600, "{}: {}", uri, max_age);
+        let etag = headers["etag"].to_str().unwrap().to_string();
+
+        let (status, headers, zstd_body) =
+            send_with_headers(&router, uri, &[("accept-encoding", "gzip;q=0.5, zstd")]).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(headers["content-encoding"], "zstd");
+        assert_ne!(headers["etag"].to_str().unwrap(), etag);
+        assert_eq!(zstd::decode_all(&zstd_body[..]).unwrap(), plain);
+
+        let (status, headers, body) = send_with_headers(&router, uri, &[("if-none-match", &etag)]).await;
+        assert_eq!(status, StatusCode::NOT_MODIFIED, "{}", uri);
+        assert_eq!(headers["etag"], etag.as_str());
+        assert!(body.is_empty());
+
+        // A different encoding is a different representation
+        let (status, _, _) =
+            send_with_headers(&router, uri, &[("if-none-match", &etag), ("accept-encoding", "gzip")]).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    // A refresh with unchanged quorums keeps the ETag and Last-Modified;
+    // changed content gets new ones. Last-Modified has one second resolution.
+    let (_, headers, _) = send(&router, "GET", "/quorums").await;
+    tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+    network.quorum_cache.refresh().await.unwrap();
+    let (_, refreshed, _) = send(&router, "GET", "/quorums").await;
+    assert_eq!(refreshed["etag"], headers["etag"]);
+    assert_eq!(refreshed["last-modified"], headers["last-modified"]);
+    send(&router, "POST", "/quorums/clear").await;
+    let (status, cleared, _) =
+        send_with_headers(&router, "/quorums", &[("if-none-match", headers["etag"].to_str().unwrap())]).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_ne!(cleared["etag"], headers["etag"]);
+    assert_ne!(cleared["last-modified"], headers["last-modified"]);
+}
+
+#[tokio::test]
+async fn binary_formats_are_negotiated_on_every_route() {
+    use crate::response_format::proto::{self, response::Data};
+    use prost::Message;
+
+    let rpc = MockRpcServer::from_fixture("testnet");
+    let mut config = test_config(&rpc.url);
+    config.server.expose_core = true;
+    let router = create_router(&[network_state("testnet", config)], "testnet");
+    let protobuf = [("accept", "application/x-protobuf")];
+
+    let (status, headers, body) = send_with_headers(&router, "/quorums", &protobuf).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(headers["content-type"], "application/x-protobuf");
+    assert_eq!(headers["vary"], "accept, accept-encoding");
+    let response = proto::Response::decode(&body[..]).unwrap();
+    let anchor = response.anchor.expect("quorum lists are anchored");
+    assert_eq!(
+        (anchor.height, hex::encode(anchor.block_hash), anchor.chainlock_signature.map(hex::encode)),
+        (1100050, TIP_HASH.to_string(), Some(CHAINLOCK_SIGNATURE.to_string()))
+    );
+    let Some(Data::Quorums(list)) = response.data else {
+        panic!("expected a quorum list");
+    };
+    let quorums: Vec<(String, String, u32)> = list
+        .quorums
+        .iter()
+        .map(|quorum| (hex::encode(&quorum.quorum_hash), hex::encode(&quorum.key), quorum.height))
+        .collect();
+    assert_eq!(
+        quorums,
+        vec![
+            (QUORUM_0.to_string(), KEY_0.to_string(), 1100016),
+            (QUORUM_1.to_string(), KEY_1.to_string(), 1099992),
+        ]
+    );
+    let json_etag = send(&router, "GET", "/quorums").await.1["etag"].clone();
+    assert_ne!(headers["etag"], json_etag);
+
+    // Uncached routes and errors are negotiated the same way
+    let (_, headers, body) = send_with_headers(&router, "/quorums/stats", &protobuf).await;
+    assert_eq!(headers["vary"], "accept");
+    let stats = proto::Response::decode(&body[..]).unwrap();
+    assert_eq!(stats.data, Some(Data::QuorumStats(proto::QuorumStats { total_count: 2, is_empty: false })));
+
+    let (status, headers, body) =
+        send_with_headers(&router, &format!("/quorums/{}", QUORUM_2), &[("accept", "application/x-bincode")]).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(headers["content-type"], "application/x-bincode");
+    let expected = proto::Response {
+        success: false,
+        message: Some("Quorum not found".to_string()),
+        code: Some("not_found".to_string()),
+        anchor: None,
+        data: None,
+    };
+    assert_eq!(body, bincode::encode_to_vec(&expected, bincode::config::standard()).unwrap());
+
+    let (_, headers, body) = send_with_headers(&router, "/core/blockcount", &[("accept", "application/cbor")]).await;
+    assert_eq!(headers["content-type"], "application/cbor");
+    let cbor: ciborium::Value = ciborium::from_reader(&body[..]).unwrap();
+    let data = cbor.as_map().unwrap().iter().find(|(key, _)| key.as_text() == Some("data")).unwrap();
+    assert_eq!(data.1, ciborium::Value::Map(vec![("block_count".into(), 1100050.into())]));
+
+    // JSON stays the default for browsers and other clients accepting anything
+    let (_, headers, _) = send_with_headers(&router, "/quorums/stats", &[("accept", "text/html,*/*;q=0.8")]).await;
+    assert_eq!(headers["content-type"], "application/json");
+}
+
+#[tokio::test]
+async fn signed_quorum_lists_verify_with_the_published_key() {
+    use quorum_list_client::{SignatureAlgorithm, SignedQuorumList, VerifyError};
+
+    let rpc = MockRpcServer::from_fixture("testnet");
+    let (unsigned, _) = testnet_router(&rpc);
+    assert_eq!(send_json(&unsigned, "GET", "/signing-key").await.0, StatusCode::NOT_FOUND);
+    assert_eq!(send_json(&unsigned, "GET", "/quorums/signed").await.0, StatusCode::NOT_FOUND);
+
+    for algorithm in [SignatureAlgorithm::Ed25519, SignatureAlgorithm::Secp256k1] {
+        let key_path = std::env::temp_dir().join(format!("quorum-list-server-{}-{}.key", std::process::id(), algorithm));
+        std::fs::write(&key_path, format!("{}\n", "42".repeat(32))).unwrap();
+        let mut config = test_config(&rpc.url);
+        config.signing = Some(crate::config::SigningConfig {
+            algorithm,
+            key_path: key_path.to_string_lossy().into_owned(),
+        });
+        let network = network_state("testnet", config);
+        let router = create_router(std::slice::from_ref(&network), "testnet");
+        std::fs::remove_file(&key_path).unwrap();
+
+        let (status, key) = send_json(&router, "GET", "/signing-key").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(key["data"]["algorithm"], algorithm.to_string());
+        let public_key = hex::decode(key["data"]["public_key"].as_str().unwrap()).unwrap();
+
+        let (status, body) = send_json(&router, "GET", "/quorums/signed").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["height"], 1100050);
+        assert_eq!(body["data"]["block_hash"], format!("000000aa{}", "0".repeat(56)));
+        assert_eq!(body["data"]["quorums"][0], quorum_json(QUORUM_0, KEY_0, 1100016, 25));
+        let signed: SignedQuorumList = serde_json::from_value(body["data"].clone()).unwrap();
+        assert_eq!(signed.verify(algorithm, &public_key), Ok(()));
+
+        // A mirror swapping in another key is caught
+        let mut tampered = signed.clone();
+        tampered.quorums[1].key = KEY_2.to_string();
+        assert_eq!(tampered.verify(algorithm, &public_key), Err(VerifyError::BadSignature));
+
+        network.quorum_cache.clear();
+        assert_eq!(send_json(&router, "GET", "/quorums/signed").await.0, StatusCode::SERVICE_UNAVAILABLE);
+    }
+}
+
+#[tokio::test]
+async fn threshold_signatures_verify_against_served_keys() {
+    let (rpc, secrets) = testnet_with_quorum_secrets();
+    let (router, network) = testnet_router(&rpc);
+
+    // A cold cache is loaded by the first request
+    let sign_hash = hex::encode([0x44; 32]);
+    let signature = hex::encode(bls::sign(&secrets[QUORUM_0], &[0x44; 32]));
+    let (status, body) = post_json(
+        &router,
+        "/verify",
+        json!({ "llmq_type": 6, "quorum_hash": QUORUM_0, "sign_hash": sign_hash, "signature": signature }),
+    )
+    .await;
+    assert_eq!((status, body["data"]["verified"].clone()), (StatusCode::OK, json!(true)));
+
+    let request_id = "11".repeat(32);
+    let msg_hash = "22".repeat(32);
+    let id = bls::parse_hash("request id", &request_id).unwrap();
+    let selected = {
+        let snapshot = network.quorum_cache.snapshot();
+        hex::encode(&bls::select_quorum(snapshot.data.list.iter(), 6, &id).unwrap().quorum_hash)
+    };
+    let sign_hash = bls::sign_hash(
+        6,
+        &bls::parse_hash("quorum hash", &selected).unwrap(),
+        &id,
+        &bls::parse_hash("message hash", &msg_hash).unwrap(),
+    );
+    let signature = hex::encode(bls::sign(&secrets[&selected], &sign_hash));
+
+    // The signing quorum is selected from the request id...
+    let (status, body) = post_json(
+        &router,
+        "/verify",
+        json!({ "request_id": request_id, "msg_hash": msg_hash, "signature": signature }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        body["data"],
+        json!({ "verified": true, "llmq_type": 6, "quorum_hash": selected, "sign_hash": hex::encode(sign_hash) })
+    );
+    assert_eq!(body["anchor"]["height"], 1100050);
+
+    // ...or named, with the signed hash given directly
+    let (_, body) = post_json(
+        &router,
+        "/verify",
+        json!({ "llmq_type": 6, "quorum_hash": selected, "sign_hash": hex::encode(sign_hash), "signature": signature }),
+    )
+    .await;
+    assert_eq!(body["data"]["verified"], true);
+
+    // Another quorum or another message doesn't verify
+    let other = if selected == QUORUM_0 { QUORUM_1 } else { QUORUM_0 };
+    for request in [
+        json!({ "quorum_hash": other, "request_id": request_id, "msg_hash": msg_hash, "signature": signature }),
+        json!({ "request_id": request_id, "msg_hash": "33".repeat(32), "signature": signature }),
+    ] {
+        let (status, body) = post_json(&router, "/verify", request).await;
+        assert_eq!((status, body["data"]["verified"].clone()), (StatusCode::OK, json!(false)));
+    }
+
+    let errors = [
+        (json!({ "llmq_type": 4, "request_id": request_id, "msg_hash": msg_hash, "signature": signature }), StatusCode::BAD_REQUEST),
+        (json!({ "quorum_hash": QUORUM_2, "sign_hash": hex::encode(sign_hash), "signature": signature }), StatusCode::NOT_FOUND),
+        (json!({ "quorum_hash": selected, "signature": signature }), StatusCode::BAD_REQUEST),
+        (json!({ "request_id": request_id, "msg_hash": msg_hash, "signature": "00".repeat(96) }), StatusCode::BAD_REQUEST),
+    ];
+    for (request, expected) in errors {
+        let (status, body) = post_json(&router, "/verify", request.clone()).await;
+        assert_eq!(status, expected, "{}", request);
+        assert_eq!(body["success"], false);
+    }
+
+    // Bodies that don't parse are answered in the same envelope
+    for request in [json!({ "request_id": request_id }), json!("not an object")] {
+        let (status, body) = post_json(&router, "/verify", request.clone()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", request);
+        assert_eq!((body["success"].clone(), body["code"].clone()), (json!(false), json!("bad_request")));
+    }
+}
+
+#[tokio::test]
+async fn legacy_bls_signatures_are_refused() {
+    let mut exchanges = fixture_exchanges("testnet");
+    let chain_info = exchanges.iter_mut().find(|exchange| exchange.method == "getblockchaininfo").unwrap();
+    chain_info.result["softforks"] = json!({ "v19": { "type": "bip9", "active": false } });
+    let rpc = MockRpcServer::start(exchanges);
+    let (router, network) = testnet_router(&rpc);
+    network.quorum_cache.refresh().await.unwrap();
+
+    let request = json!({ "quorum_hash": QUORUM_0, "sign_hash": "11".repeat(32), "signature": "00".repeat(96) });
+    let (status, body) = post_json(&router, "/verify", request).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["message"].as_str().unwrap().contains("legacy BLS scheme"), "{}", body);
+}
+
+#[tokio::test]
+async fn quorum_selection_for_request_ids() {
+    let rpc = MockRpcServer::from_fixture("testnet");
+    let (router, _network) = testnet_router(&rpc);
+
+    // QUORUM_0 is mined at 1100026 and QUORUM_1 at 1100002, so both sign at
+    // the tip. Expected signers computed from Dash Core's SelectQuorumForSigning.
+    for (byte, signer) in [(0u8, QUORUM_0), (1, QUORUM_0), (2, QUORUM_1), (3, QUORUM_1), (6, QUORUM_1), (7, QUORUM_0)] {
+        let request_id = hex::encode([byte; 32]);
+        let (status, body) = send_json(&router, "GET", &format!("/quorums/select?request_id={}", request_id)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["quorum_hash"], signer, "{}", request_id);
+        assert_eq!(body["anchor"]["height"], 1100050);
+    }
+
+    // Eight blocks before 1100030 QUORUM_0 isn't mined yet, so QUORUM_1 signs everything
+    let request_id = "00".repeat(32);
+    for query in ["type=6&height=1100030", "type=llmq_25_67&height=1100030", "height=1100020"] {
+        let uri = format!("/quorums/select?{}&request_id={}", query, request_id);
+        let (status, body) = send_json(&router, "GET", &uri).await;
+        assert_eq!((status, body["data"]["quorum_hash"].clone()), (StatusCode::OK, json!(QUORUM_1)), "{}", query);
+    }
+
+    for (query, status) in [
+        // No stored quorum was mined by 1100001
+        (format!("request_id={}&height=1100009", request_id), StatusCode::NOT_FOUND),
+        (format!("request_id={}&height=1100051", request_id), StatusCode::BAD_REQUEST),
+        // Lock quorum types are only served when locks are verified
+        (format!("request_id={}&type=llmq_60_75", request_id), StatusCode::BAD_REQUEST),
+        ("request_id=abcd".to_string(), StatusCode::BAD_REQUEST),
+    ] {
+        let (actual, body) = send_json(&router, "GET", &format!("/quorums/select?{}", query)).await;
+        assert_eq!((actual, body["success"].clone()), (status, json!(false)), "{}", query);
+    }
+}
+
+#[tokio::test]
+async fn rotated_quorum_selection_uses_the_request_id_bits() {
+    let (rpc, _secrets) = testnet_with_lock_quorums();
+    let mut config = test_config(&rpc.url);
+    config.quorum.verify_locks = true;
+    let network = network_state("testnet", config);
+    let router = create_router(std::slice::from_ref(&network), "testnet");
+    network.quorum_cache.refresh().await.unwrap();
+
+    for (request_id, index) in
+        [("0123456789abcdef".repeat(4), 0), ("fedcba9876543210".repeat(4), 31), ("5a".repeat(32), 22)]
+    {
+        for llmq_type in ["5", "llmq_60_75"] {
+            let uri = format!("/quorums/select?type={}&request_id={}", llmq_type, request_id);
+            let (status, body) = send_json(&router, "GET", &uri).await;
+            assert_eq!(status, StatusCode::OK, "{}", body);
+            assert_eq!(body["data"]["quorum_hash"], rotated_quorum(index), "{}", request_id);
+        }
+    }
+    let uri = format!("/quorums/select?type=llmq_50_60&request_id={}", "00".repeat(32));
+    assert_eq!(send_json(&router, "GET", &uri).await.1["data"]["quorum_hash"], QUORUM_50_60);
+}
+
+#[tokio::test]
+async fn selection_below_the_anchor_loads_quorums_that_rotated_out() {
+    // At 1099892 the cycle created at 1099872 has only its first 21 quorums;
+    // the rest of the signing set is the previous cycle's
+    let (mut exchanges, _secrets) = lock_quorum_exchanges();
+    let previous_cycle = |index: u32| format!("{:02x}", 0x90 + index).repeat(32);
+    let mut list = exchanges.iter().find(|exchange| exchange.params == [json!("listextended"), json!(1100050)]).unwrap().result.clone();
+    list["llmq_60_75"] = (0..32)
+        .map(|index| {
+            let (hash, height) = if index < 21 { (rotated_quorum(index), 1099872 + index) } else { (previous_cycle(index), 1099584 + index) };
+            json!({ hash: { "creationHeight": height, "quorumIndex": index, "numValidMembers": 60 } })
+        })
+        .collect();
+    exchanges.push(Exchange {
+        method: "quorum".to_string(),
+        params: vec![json!("listextended"), json!(1099892)],
+        result: list,
+        error: None,
+    });
+    exchanges.push(Exchange {
+        method: "getblockhash".to_string(),
+        params: vec![json!(1099892)],
+        result: json!("92".repeat(32)),
+        error: None,
+    });
+    exchanges.extend((21..32).map(|index| Exchange {
+        method: "quorum".to_string(),
+        params: vec![json!("info"), json!(5), json!(previous_cycle(index))],
+        result: json!({ "height": 1099584 + index, "type": "llmq_60_75", "quorumHash": previous_cycle(index), "quorumPublicKey": KEY_0 }),
+        error: None,
+    }));
+    let rpc = MockRpcServer::start(exchanges);
+    let mut config = test_config(&rpc.url);
+    config.quorum.verify_locks = true;
+    let network = network_state("testnet", config);
+    let router = create_router(std::slice::from_ref(&network), "testnet");
+
+    // Request ids of 0x5a bytes are signed by quorum index 22
+    let request_id = "5a".repeat(32);
+    for (height, signer) in [(1100050, rotated_quorum(22)), (1099900, previous_cycle(22))] {
+        let uri = format!("/quorums/select?type=llmq_60_75&request_id={}&height={}", request_id, height);
+        let (status, body) = send_json(&router, "GET", &uri).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert_eq!(body["data"]["quorum_hash"], signer, "{}", height);
+    }
+    assert!(rpc.calls().iter().any(|(_, params)| *params == [json!("listextended"), json!(1099892)]));
+}
+
+/// Post a lock with a placeholder signature to learn the quorum and hash it
+/// is checked against, then sign it as that quorum.
+async fn sign_lock(
+    router: &Router,
+    uri: &str,
+    secrets: &HashMap<String, blst::min_pk::SecretKey>,
+    unsigned: &[u8],
+) -> (Vec<u8>, Value) {
+    let mut message = unsigned.to_vec();
+    message.extend([0; 96]);
+    let (status, body) = post_json(router, uri, json!({ "message": hex::encode(&message) })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["verified"], false);
+
+    let sign_hash = bls::parse_hash("sign hash", body["data"]["sign_hash"].as_str().unwrap()).unwrap();
+    let secret = &secrets[body["data"]["quorum_hash"].as_str().unwrap()];
+    message.truncate(unsigned.len());
+    message.extend(bls::sign(secret, &sign_hash));
+    (message, body["data"].clone())
+}
+
+#[tokio::test]
+async fn chainlocks_and_instantsend_locks_verify() {
+    let (rpc, secrets) = testnet_with_lock_quorums();
+    let mut config = test_config(&rpc.url);
+    config.quorum.verify_locks = true;
+    let network = network_state("testnet", config);
+    let router = create_router(std::slice::from_ref(&network), "testnet");
+
+    // clsig: height, block hash and signature. The only llmq_50_60 quorum signs it.
+    let mut clsig = 1100000u32.to_le_bytes().to_vec();
+    clsig.extend([0xaa; 32]);
+    let (clsig, unsigned) = sign_lock(&router, "/verify/chainlock", &secrets, &clsig).await;
+    assert_eq!(unsigned["quorum_hash"], QUORUM_50_60);
+    assert_eq!(unsigned["llmq_type"], 1);
+
+    let (status, body) = post_json(&router, "/verify/chainlock", json!({ "message": hex::encode(&clsig) })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        body["data"],
+        json!({
+            "verified": true,
+            "llmq_type": 1,
+            "quorum_hash": QUORUM_50_60,
+            "request_id": unsigned["request_id"],
+            "sign_hash": unsigned["sign_hash"],
+            "reason": null,
+        })
+    );
+    assert_eq!(body["anchor"]["height"], 1100050);
+
+    // isdlock: version, inputs, txid, cycle hash (the cycle's first quorum) and signature
+    let mut isdlock = vec![1, 1];
+    isdlock.extend([0x11; 32]);
+    isdlock.extend(0u32.to_le_bytes());
+    isdlock.extend([0x22; 32]);
+    isdlock.extend(hex::decode(rotated_quorum(0)).unwrap().iter().rev());
+    let (isdlock, unsigned) = sign_lock(&router, "/verify/islock", &secrets, &isdlock).await;
+    let (_, body) = post_json(&router, "/verify/islock", json!({ "message": hex::encode(&isdlock) })).await;
+    assert_eq!(body["data"]["verified"], true, "{}", body);
+    assert_eq!(body["data"]["llmq_type"], 5);
+    assert_eq!(body["data"]["quorum_hash"], unsigned["quorum_hash"]);
+
+    // Well-formed locks that don't verify say why
+    let mut other_block = clsig.clone();
+    other_block[4] ^= 1;
+    let mut unknown_cycle = isdlock.clone();
+    unknown_cycle[74] ^= 1;
+    for (uri, message) in [("/verify/chainlock", other_block), ("/verify/islock", unknown_cycle)] {
+        let (status, body) = post_json(&router, uri, json!({ "message": hex::encode(message) })).await;
+        assert_eq!((status, body["data"]["verified"].clone()), (StatusCode::OK, json!(false)), "{}", uri);
+        assert!(body["data"]["reason"].is_string());
+    }
+
+    for (uri, message) in [("/verify/chainlock", "zz".to_string()), ("/verify/islock", hex::encode(&clsig))] {
+        let (status, body) = post_json(&router, uri, json!({ "message": message })).await;
+        assert_eq!((status, body["code"].clone()), (StatusCode::BAD_REQUEST, json!("bad_request")), "{}", uri);
+    }
+
+    // A lock too far above the tip for its quorums to be listed
+    let mut future = 1100060u32.to_le_bytes().to_vec();
+    future.extend([0xaa; 128]);
+    let (status, body) = post_json(&router, "/verify/chainlock", json!({ "message": hex::encode(&future) })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+
+    // Without lock verification enabled the endpoints don't exist
+    let (router, _) = testnet_router(&rpc);
+    let (status, _) = post_json(&router, "/verify/chainlock", json!({ "message": hex::encode(&clsig) })).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn chainlocks_above_the_anchor_use_the_quorums_at_their_sign_height() {
+    // Anchor the list at 1100042, below the tip at 1100050
+    let (mut exchanges, secrets) = lock_quorum_exchanges();
+    let best = exchanges.iter_mut().find(|exchange| exchange.method == "getbestchainlock").unwrap();
+    best.result["blockhash"] = json!("0000cfe54786a1d98a5c2dbfade51f0af6913c8bbbee7a0cec9968be5b3c7a61");
+    best.result["height"] = json!(1100042);
+    let rpc = MockRpcServer::start(exchanges);
+    let mut config = test_config(&rpc.url);
+    config.quorum.verify_locks = true;
+    let network = network_state("testnet", config);
+    let router = create_router(std::slice::from_ref(&network), "testnet");
+
+    let mut clsig = 1100058u32.to_le_bytes().to_vec();
+    clsig.extend([0xaa; 32]);
+    let (clsig, unsigned) = sign_lock(&router, "/verify/chainlock", &secrets, &clsig).await;
+    assert_eq!(unsigned["quorum_hash"], QUORUM_50_60);
+    let (status, body) = post_json(&router, "/verify/chainlock", json!({ "message": hex::encode(&clsig) })).await;
+    assert_eq!((status, body["data"]["verified"].clone()), (StatusCode::OK, json!(true)), "{}", body);
+    assert_eq!(body["anchor"]["height"], 1100042);
+}
+
+#[tokio::test]
+async fn chainlocks_signed_by_quorums_rotated_out_since_use_the_quorums_at_their_sign_height() {
+    // A full llmq_50_60 set at the tip, the newest created at 1100016. At the
+    // sign height of a ChainLock at 1100020 the quorum it replaced still signed.
+    let (mut exchanges, _) = lock_quorum_exchanges();
+    let tip_list = exchanges.iter().find(|exchange| exchange.params == [json!("listextended"), json!(1100050)]).unwrap();
+    let (mut tip_list, mut sign_height_list) = (tip_list.result.clone(), tip_list.result.clone());
+    let listed = |quorums: &[(String, u32)]| -> Value {
+        quorums.iter().map(|(hash, height)| json!({ hash: { "creationHeight": height, "quorumIndex": 0, "numValidMembers": 50 } })).collect()
+    };
+
+    // Of the set signing at 1100012, the replaced quorum is the one selected
+    let mut request_id = vec![5];
+    request_id.extend(b"clsig");
+    request_id.extend(1100020u32.to_le_bytes());
+    let mut request_id: bls::Hash = Sha256::digest(Sha256::digest(&request_id)).into();
+    request_id.reverse();
+    let signing_set: Vec<QuorumListEntry> =
+        (0..24u8).map(|index| QuorumListEntry::new(vec![0xc0 + index; 32], Vec::new())).collect();
+    let rotated_out = hex::encode(&bls::select_quorum(&signing_set, 1, &request_id).unwrap().quorum_hash);
+    let mut at_sign_height: Vec<(String, u32)> =
+        signing_set.iter().enumerate().map(|(index, quorum)| (hex::encode(&quorum.quorum_hash), 1099440 + 24 * index as u32)).collect();
+    let mut current: Vec<(String, u32)> = at_sign_height.iter().filter(|(hash, _)| *hash != rotated_out).cloned().collect();
+    current.push((hex::encode([0xd8; 32]), 1100016));
+    at_sign_height.sort_by_key(|(_, height)| *height);
+
+    tip_list["llmq_50_60"] = listed(&current);
+    sign_height_list["llmq_50_60"] = listed(&at_sign_height);
+    exchanges.iter_mut().find(|exchange| exchange.params == [json!("listextended"), json!(1100050)]).unwrap().result = tip_list;
+    for (method, params, result) in [
+        ("quorum", vec![json!("listextended"), json!(1100012)], sign_height_list),
+        ("getblockhash", vec![json!(1100012)], json!("12".repeat(32))),
+    ] {
+        exchanges.push(Exchange { method: method.to_string(), params, result, error: None });
+    }
+    for (hash, height) in at_sign_height.iter().chain(current.last()) {
+        exchanges.push(Exchange {
+            method: "quorum".to_string(),
+            params: vec![json!("info"), json!(1), json!(hash)],
+            result: json!({ "height": height, "type": "llmq_50_60", "quorumHash": hash, "quorumPublicKey": "" }),
+            error: None,
+        });
+    }
+    let secrets = key_quorums(&mut exchanges);
+    let rpc = MockRpcServer::start(exchanges);
+    let mut config = test_config(&rpc.url);
+    config.quorum.verify_locks = true;
+    let network = network_state("testnet", config);
+    let router = create_router(std::slice::from_ref(&network), "testnet");
+
+    let mut clsig = 1100020u32.to_le_bytes().to_vec();
+    clsig.extend([0xaa; 32]);
+    let (clsig, unsigned) = sign_lock(&router, "/verify/chainlock", &secrets, &clsig).await;
+    assert_eq!(unsigned["quorum_hash"], json!(rotated_out));
+    let (status, body) = post_json(&router, "/verify/chainlock", json!({ "message": hex::encode(&clsig) })).await;
+    assert_eq!((status, body["data"]["verified"].clone()), (StatusCode::OK, json!(true)), "{}", body);
+    assert_eq!(body["anchor"]["height"], 1100050);
+}
+
+#[tokio::test]
+async fn quorum_lifecycles_outlive_the_published_list() {
+    let rpc = MockRpcServer::from_fixture("testnet");
+    let (router, _) = testnet_router(&rpc);
+
+    let (status, body) = send_json(&router, "GET", &format!("/quorums/{}/lifecycle", QUORUM_0)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        body,
+        anchored(json!({
+            "quorum_hash": QUORUM_0,
+            "llmq_type": "llmq_25_67",
+            "key": KEY_0,
+            "creation_height": 1100016,
+            "mined_height": 1100026,
+            "first_seen_height": 1100050,
+            "last_active_height": 1100050,
+            "retired_height": null,
+        }))
+    );
+
+    // Quorum 1 was mined at 1100002, quorum 0 at 1100026
+    for (query, expected) in [
+        ("", json!([QUORUM_0, QUORUM_1])),
+        ("?type=6&from=1100000&to=1100010", json!([QUORUM_1])),
+        ("?type=llmq_25_67&to=1100001", json!([])),
+    ] {
+        let (status, body) = send_json(&router, "GET", &format!("/quorums/history{}", query)).await;
+        assert_eq!(status, StatusCode::OK, "{}", query);
+        let hashes: Vec<Value> = body["data"].as_array().unwrap().iter().map(|quorum| quorum["quorum_hash"].clone()).collect();
+        assert_eq!(json!(hashes), expected, "{}", query);
+    }
+
+    // Clearing the list doesn't clear the history
+    send(&router, "POST", "/quorums/clear").await;
+    let (status, _) = send_json(&router, "GET", &format!("/quorums/{}/lifecycle", QUORUM_1)).await;
+    assert_eq!(status, StatusCode::OK);
+
+    for (uri, expected) in [
+        (format!("/quorums/{}/lifecycle", QUORUM_2), StatusCode::NOT_FOUND),
+        ("/quorums/abcd/lifecycle".to_string(), StatusCode::BAD_REQUEST),
+        ("/quorums/history?type=llmq_50_60".to_string(), StatusCode::BAD_REQUEST),
+        ("/quorums/history?from=10&to=9".to_string(), StatusCode::BAD_REQUEST),
+    ] {
+        let (status, body) = send_json(&router, "GET", &uri).await;
+        assert_eq!((status, body["success"].clone()), (expected, json!(false)), "{}", uri);
+    }
+}
+
+#[tokio::test]
+async fn quorum_diff_between_heights() {
+    let rpc = MockRpcServer::from_fixture("testnet");
+    let (router, _) = testnet_router(&rpc);
+
+    // Quorum 0 was added after 1100042, when quorum 2 was still active; `to` defaults to the published list's height
+    let expected = anchored(json!({
+        "base_height": 1100042,
+        "height": 1100050,
+        "llmq_types": [{
+            "llmq_type": "llmq_25_67",
+            "added": [quorum_json(QUORUM_0, KEY_0, 1100016, 25)],
+            "removed": [quorum_json(QUORUM_2, KEY_2, 1099968, 23)],
+        }],
+    }));
+    assert_eq!(send_json(&router, "GET", "/quorums/diff?from=1100042").await, (StatusCode::OK, expected.clone()));
+    assert_eq!(send_json(&router, "GET", "/quorums/diff?from=1100042&to=1100050").await, (StatusCode::OK, expected));
+
+    let (_, body) = send_json(&router, "GET", "/quorums/diff?from=1100050&to=1100050").await;
+    assert_eq!(body["data"]["llmq_types"][0], json!({ "llmq_type": "llmq_25_67", "added": [], "removed": [] }));
+
+    // Each block's quorums are loaded once, however often it is diffed
+    let loads = rpc.calls().into_iter().filter(|(_, params)| params == &[json!("listextended"), json!(1100042)]).count();
+    assert_eq!(loads, 1);
+
+    for uri in [
+        "/quorums/diff?from=1100050&to=1100042",
+        "/quorums/diff?from=1100042&to=1100051",
+        // More than diff_window_blocks below the published list
+        "/quorums/diff?from=1099473",
+    ] {
+        let (status, body) = send_json(&router, "GET", uri).await;
+        assert_eq!((status, body["code"].clone()), (StatusCode::BAD_REQUEST, json!("bad_request")), "{}", uri);
+    }
+}
+
+#[tokio::test]
+async fn quorums_filtered_by_height() {
+    let rpc = MockRpcServer::from_fixture("testnet");
+    let (router, _) = testnet_router(&rpc);
+    let q0 = quorum_json(QUORUM_0, KEY_0, 1100016, 25);
+    let q1 = quorum_json(QUORUM_1, KEY_1, 1099992, 24);
+
+    let cases = [
+        ("/quorums?max_height=1100000", json!([q1])),
+        ("/quorums?min_height=1099992", json!([q1, q0])),
+        ("/quorums?min_height=1100000&max_height=1100016", json!([q0])),
+        ("/quorums?latest=1", json!([q0])),
+        ("/quorums?latest=5", json!([q0, q1])),
+    ];
+    for (uri, expected) in cases {
+        assert_eq!(send_json(&router, "GET", uri).await, (StatusCode::OK, anchored(expected)), "{}", uri);
+    }
+
+    for uri in ["/quorums?latest=1&min_height=1", "/quorums?min_height=1100016&max_height=1100000"] {
+        let (status, body) = send_json(&router, "GET", uri).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", uri);
+        assert_eq!(body["code"], "bad_request");
+    }
+}
+
+#[tokio::test]
+async fn quorum_lookup_by_hash() {
+    let rpc = MockRpcServer::from_fixture("testnet");
+    let (router, network) = testnet_router(&rpc);
+    network.quorum_cache.refresh().await.unwrap();
+
+    assert_eq!(
+        send_json(&router, "GET", &format!("/quorums/{}", QUORUM_1)).await,
+        (StatusCode::OK, anchored(quorum_json(QUORUM_1, KEY_1, 1099992, 24)))
+    );
+
+    assert_eq!(
+        send_json(&router, "GET", &format!("/quorums/{}", QUORUM_2)).await,
+        (
+            StatusCode::NOT_FOUND,
+            json!({ "success": false, "data": null, "message": "Quorum not found", "code": "not_found" })
+        )
+    );
+
+    assert_eq!(
+        send_json(&router, "GET", "/quorums/abcd").await,
+        (
+            StatusCode::BAD_REQUEST,
+            json!({
+                "success": false,
+                "data": null,
+                "message": "Invalid hash format. Must be 32 bytes hex encoded.",
+                "code": "bad_request",
+            })
+        )
+    );
+}
+
+#[tokio::test]
+async fn clearing_empties_the_list() {
+    let rpc = MockRpcServer::from_fixture("testnet");
+    let (router, network) = testnet_router(&rpc);
+    network.quorum_cache.refresh().await.unwrap();
+
+    assert_eq!(
+        send_json(&router, "POST", "/quorums/clear").await,
+        (StatusCode::OK, success(json!("All quorums cleared successfully")))
+    );
+    assert_eq!(
+        send_json(&router, "GET", "/quorums/stats").await,
+        (StatusCode::OK, success(json!({ "total_count": 0, "is_empty": true })))
+    );
+}
+
+#[tokio::test]
+async fn previous_quorums_use_the_configured_offset() {
+    let rpc = MockRpcServer::from_fixture("testnet");
+    let (router, _) = testnet_router(&rpc);
+
+    let (status, body) = send_json(&router, "GET", "/previous").await;
+    assert_eq!(status, StatusCode::OK);
+    // The previous block is not the ChainLocked one, so it comes without a signature
+    let mut expected = success(json!({
+        "height": 1100042,
+        "quorums": [
+            quorum_json(QUORUM_1, KEY_1, 1099992, 24),
+            quorum_json(QUORUM_2, KEY_2, 1099968, 23),
+        ],
+    }));
+    expected["anchor"] = json!({ "height": 1100042, "block_hash": PREVIOUS_HASH, "chainlock_signature": null });
+    assert_eq!(body, expected);
+    // Same `quorum info` calls as the current list: no secret key shares requested
+    assert!(rpc
+        .calls()
+        .contains(&("quorum".to_string(), vec![json!("info"), json!(6), json!(QUORUM_2)])));
+}
+
+#[tokio::test]
+async fn masternodes_lists_only_evo_nodes() {
+    let rpc = MockRpcServer::from_fixture("testnet");
+    let (router, _) = testnet_router(&rpc);
+
+    // The list is served before the version checks are done
+    let (status, body) = send_json(&router, "GET", "/masternodes").await;
+    assert_eq!(status, StatusCode::OK);
+    let pending = body["data"].as_array().unwrap().iter().find(|node| node["proTxHash"] == EVO_BAD_ADDRESS).cloned();
+    assert_eq!(pending.unwrap()["versionCheck"], "pending");
+
+    let mut body = checked_masternodes(&router).await;
+    // `masternode list` is a map, so the order of nodes is not stable
+    body["data"]
+        .as_array_mut()
+        .unwrap()
+        .sort_by_key(|node| node["proTxHash"].as_str().unwrap().to_string());
+    assert_eq!(
+        body,
+        success(json!([
+            {
+                "proTxHash": EVO_BAD_ADDRESS,
+                "address": "not-an-address",
+                "status": "ENABLED",
+                "versionCheck": "fail",
+            },
+            {
+                "proTxHash": EVO_BANNED,
+                "address": "35.166.18.166:19999",
+                "status": "POSE_BANNED",
+                "versionCheck": "fail",
+            },
+        ]))
+    );
+
+    let (status, body) = send_json(&router, "GET", &format!("/masternodes/{}/history", EVO_BAD_ADDRESS)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["uptime"], json!({ "last_hour": 0.0, "last_day": 0.0, "last_week": 0.0 }));
+    assert_eq!(body["data"]["probes"][0]["outcome"], "fail");
+    // Banned nodes are never probed
+    let (status, _) = send_json(&router, "GET", &format!("/masternodes/{}/history", EVO_BANNED)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // Neither node passed its version check, so there is nothing to bootstrap from
+    let (status, body) = send_json(&router, "GET", "/dapi/addresses?limit=5&shuffle=true").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, success(json!([])));
+}
+
+#[tokio::test]
+async fn masternode_lists_by_height_and_their_history() {
+    let rpc = MockRpcServer::from_fixture("testnet");
+    let mut config = test_config(&rpc.url);
+    let history_path = std::env::temp_dir().join(format!("qls-test-masternodes-{}.json", std::process::id()));
+    config.dapi.masternode_history_path = Some(history_path.to_string_lossy().to_string());
+    let network = network_state("testnet", config.clone());
+    let router = create_router(std::slice::from_ref(&network), "testnet");
+    let retired = json!({
+        "proTxHash": EVO_RETIRED,
+        "address": "44.240.99.214:19999",
+        "status": "ENABLED",
+        "registeredHeight": 1090002,
+        "posePenalty": 0,
+        "poseBanHeight": null,
+        "poseRevivedHeight": null,
+        "platformNodeID": "0000000000000000000000000000000000000003",
+        "platformHTTPPort": 1443,
+    });
+
+    let (status, body) = send_json(&router, "GET", "/masternodes/at/1100042").await;
+    assert_eq!(status, StatusCode::OK);
+    let listed: Vec<_> = body["data"]["masternodes"].as_array().unwrap().iter().map(|node| node["proTxHash"].clone()).collect();
+    assert_eq!(listed, [EVO_BANNED, EVO_BAD_ADDRESS, EVO_RETIRED]);
+    assert_eq!(body["data"]["masternodes"][2], retired);
+
+    // The Regular node registered in between is left out; `to` defaults to the tip
+    let expected = success(json!({
+        "base_height": 1100042,
+        "height": 1100050,
+        "added": [],
+        "removed": [retired],
+        "updated": [
+            { "proTxHash": EVO_BANNED, "changes": { "PoSePenalty": 4200, "PoSeBanHeight": 1100047 } },
+            { "proTxHash": EVO_BAD_ADDRESS, "changes": { "service": "not-an-address" } },
+        ],
+    }));
+    assert_eq!(send_json(&router, "GET", "/masternodes/diff?from=1100042").await, (StatusCode::OK, expected));
+
+    for uri in ["/masternodes/diff?from=1100050&to=1100042", "/masternodes/diff?from=1100042&to=1100051", "/masternodes/at/1100051"] {
+        let (status, body) = send_json(&router, "GET", uri).await;
+        assert_eq!((status, body["code"].clone()), (StatusCode::BAD_REQUEST, json!("bad_request")), "{}", uri);
+    }
+
+    // Concurrent recordings apply the diff once
+    network.masternode_cache.record_history(1100042).await;
+    tokio::join!(network.masternode_cache.record_history(1100050), network.masternode_cache.record_history(1100050));
+    let (status, body) = send_json(&router, "GET", "/masternodes/history").await;
+    assert_eq!(status, StatusCode::OK);
+    let events: Vec<_> = body["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|event| (event["height"].clone(), event["proTxHash"].clone(), event["kind"].clone()))
+        .collect();
+    assert_eq!(
+        events,
+        [
+            (json!(1100047), json!(EVO_BANNED), json!("pose_banned")),
+            (json!(1100050), json!(EVO_BAD_ADDRESS), json!("updated")),
+            (json!(1100050), json!(EVO_RETIRED), json!("removed")),
+        ]
+    );
+
+    let (_, body) = send_json(&router, "GET", &format!("/masternodes/history?pro_tx_hash={}&to=1100049", EVO_BANNED)).await;
+    assert_eq!(body["data"].as_array().unwrap().len(), 1);
+    let (_, body) = send_json(&router, "GET", "/masternodes/history?from=1100048&to=1100049").await;
+    assert_eq!(body, success(json!([])));
+
+    // The history outlives the server
+    let restarted = network_state("testnet", config);
+    std::fs::remove_file(&history_path).ok();
+    let history = restarted.masternode_cache.history();
+    assert_eq!(history.height(), Some(1100050));
+    assert_eq!(history.events_between(None, 0, u32::MAX).count(), 3);
+}
+
+#[tokio::test]
+async fn networks_are_served_under_their_prefix() {
+    let rpc = MockRpcServer::from_fixture("testnet");
+    let testnet = network_state("testnet", test_config(&rpc.url));
+    // Nothing listens on port 1, so every RPC call to this network fails to connect
+    let devnet = network_state("devnet", test_config("http://127.0.0.1:1"));
+    let router = create_router(&[testnet, devnet], "testnet");
+
+    let (status, body) = send_json(&router, "GET", "/testnet/quorums").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"].as_array().unwrap().len(), 2);
+
+    // Quorums loaded through /testnet are not visible through /devnet
+    assert_eq!(
+        send_json(&router, "GET", "/devnet/quorums/stats").await,
+        (StatusCode::OK, success(json!({ "total_count": 0, "is_empty": true })))
+    );
+
+    let (status, headers, body) = send(&router, "GET", "/devnet/quorums").await;
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(body["code"], json!("rpc_unavailable"));
+    assert!(headers.contains_key("retry-after"));
+
+    let (status, _, _) = send(&router, "GET", "/mainnet/quorums").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn openapi_document_and_docs_are_served() {
+    let rpc = MockRpcServer::from_fixture("testnet");
+    let (router, _) = testnet_router(&rpc);
+
+    let (status, body) = send_json(&router, "GET", "/openapi.json").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body["paths"]["/quorums/{hash}"]["get"].is_object());
+
+    let (status, _, _) = send(&router, "GET", "/docs/").await;
+    assert_eq!(status, StatusCode::OK);
+}
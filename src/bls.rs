@@ -0,0 +1,314 @@
+//! Verification of LLMQ threshold signatures against the served quorum keys.
+//!
+//! Since v19 Dash Core signs with the BLS basic scheme: 48-byte public keys in
+//! G1 and 96-byte signatures in G2, messages hashed to G2 as in RFC 9380. That
+//! is exactly what blst implements, so it is used directly rather than through
+//! a wrapper. Networks that haven't activated v19 still use the legacy scheme,
+//! whose pre-RFC hash-to-curve and point encoding blst doesn't provide; their
+//! signatures are refused instead of being reported as invalid.
+//!
+//! Hashes are hex encoded the way Dash Core displays them, which is the
+//! reverse of the byte order they are hashed and signed in.
+
+use crate::config::LlmqParams;
+use crate::error::AppError;
+use crate::health::CoreSyncStatus;
+use crate::quorum_list::{QuorumList, QuorumListEntry};
+use blst::min_pk::{PublicKey, Signature};
+use blst::BLST_ERROR;
+use sha2::{Digest, Sha256};
+
+/// Domain separation tag of the basic scheme (`BasicSchemeMPL` in Dash Core).
+const DST: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_NUL_";
+
+/// How many blocks before the height a request is signed at the quorums
+/// signing it must exist (`SIGN_HEIGHT_OFFSET` in Dash Core).
+pub const SIGN_HEIGHT_OFFSET: u32 = 8;
+
+/// The BLS scheme quorum signatures are made with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BlsScheme {
+    #[default]
+    Basic,
+    /// Before v19 activation.
+    Legacy,
+}
+
+impl BlsScheme {
+    /// The scheme in use at the tip of a node with `status`.
+    pub fn of(status: &CoreSyncStatus) -> Self {
+        if status.bls_legacy_scheme {
+            BlsScheme::Legacy
+        } else {
+            BlsScheme::Basic
+        }
+    }
+
+    /// Fail unless signatures of this scheme can be verified.
+    pub fn ensure_verifiable(self) -> Result<(), AppError> {
+        match self {
+            BlsScheme::Basic => Ok(()),
+            BlsScheme::Legacy => Err(AppError::BadRequest(
+                "Quorums on this network sign with the legacy BLS scheme (v19 is not active), which can't be verified"
+                    .to_string(),
+            )),
+        }
+    }
+}
+
+/// A 32-byte hash in the byte order Dash Core displays it in.
+pub type Hash = [u8; 32];
+
+/// Parse a hex encoded hash; `name` is used in the error message.
+pub fn parse_hash(name: &str, hex_string: &str) -> Result<Hash, AppError> {
+    hex::decode(hex_string)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| AppError::BadRequest(format!("Invalid {}. Must be 32 bytes hex encoded.", name)))
+}
+
+/// Double SHA-256 over the internal byte order of `hashes`, prefixed by the
+/// LLMQ type, as Dash Core's `CHashWriter` computes it.
+fn llmq_hash(llmq_type: u8, hashes: &[&Hash]) -> [u8; 32] {
+    let mut data = vec![llmq_type];
+    for hash in hashes {
+        data.extend(hash.iter().rev());
+    }
+    Sha256::digest(Sha256::digest(&data)).into()
+}
+
+/// The hash quorum members sign for a request:
+/// `SHA256d(llmqType, quorumHash, requestId, msgHash)`.
+pub fn sign_hash(llmq_type: u8, quorum_hash: &Hash, request_id: &Hash, msg_hash: &Hash) -> Hash {
+    let mut hash = llmq_hash(llmq_type, &[quorum_hash, request_id, msg_hash]);
+    hash.reverse();
+    hash
+}
+
+/// The quorum Dash Core picks to sign `request_id` among `quorums`: the one
+/// with the lowest `SHA256d(llmqType, quorumHash, requestId)`. Only applies
+/// to LLMQ types without quorum rotation.
+pub fn select_quorum<'a>(
+    quorums: impl IntoIterator<Item = &'a QuorumListEntry>,
+    llmq_type: u8,
+    request_id: &Hash,
+) -> Option<&'a QuorumListEntry> {
+    quorums
+        .into_iter()
+        .filter_map(|entry| {
+            let quorum_hash: &Hash = entry.quorum_hash.as_slice().try_into().ok()?;
+            Some((llmq_hash(llmq_type, &[quorum_hash, request_id]), entry))
+        })
+        .min_by_key(|(score, _)| *score)
+        .map(|(_, entry)| entry)
+}
+
+/// The quorum Dash Core picks to sign `request_id` among the `active_count`
+/// quorums of a rotated LLMQ type: the one whose quorum index is given by
+/// the top bits of the request id.
+pub fn select_rotated_quorum<'a>(
+    quorums: impl IntoIterator<Item = &'a QuorumListEntry>,
+    active_count: u32,
+    request_id: &Hash,
+) -> Option<&'a QuorumListEntry> {
+    let bits = active_count.checked_ilog2()?;
+    // The last 64 bits of the request id, which are its first displayed bytes
+    let b = u64::from_be_bytes(request_id[..8].try_into().unwrap());
+    let index = (b >> (64 - bits - 1)) & ((1 << bits) - 1);
+    quorums.into_iter().find(|entry| u64::from(entry.quorum_index) == index)
+}
+
+/// The quorums Dash Core picks a signer of `llmq` from at `height`: the
+/// newest ones whose commitment was mined `SIGN_HEIGHT_OFFSET` or more blocks
+/// earlier, newest first. `None` when `list` no longer holds all of them:
+/// once it is full, every quorum mined since pushed one of the set out.
+pub fn signing_quorums(list: &QuorumList, llmq: LlmqParams, height: u32) -> Option<Vec<&QuorumListEntry>> {
+    let count = llmq.signing_active_quorum_count as usize;
+    let start = height.saturating_sub(SIGN_HEIGHT_OFFSET);
+    let mut quorums: Vec<_> = list.iter().filter(|quorum| quorum.active_height() <= start).collect();
+    if quorums.len() < count && list.len() >= count {
+        return None;
+    }
+    quorums.sort_by_key(|quorum| std::cmp::Reverse(quorum.active_height()));
+    quorums.truncate(count);
+    Some(quorums)
+}
+
+/// The quorum Dash Core picks among `quorums` to sign `request_id`: by
+/// quorum index for rotated types, by score otherwise.
+pub fn select_signer<'a>(
+    quorums: Vec<&'a QuorumListEntry>,
+    llmq: LlmqParams,
+    request_id: &Hash,
+) -> Option<&'a QuorumListEntry> {
+    if llmq.rotated {
+        select_rotated_quorum(quorums, llmq.signing_active_quorum_count, request_id)
+    } else {
+        select_quorum(quorums, llmq.id as u8, request_id)
+    }
+}
+
+/// Whether `signature` is a quorum's threshold signature of `sign_hash`.
+/// A signature that isn't a valid G2 point at all is a bad request.
+pub fn verify_signature(public_key: &[u8], sign_hash: &Hash, signature: &[u8]) -> Result<bool, AppError> {
+    let public_key = PublicKey::key_validate(public_key)
+        .map_err(|e| AppError::VerificationFailed(format!("Quorum public key is not a valid BLS key: {:?}", e)))?;
+    let signature = Signature::sig_validate(signature, true)
+        .map_err(|e| AppError::BadRequest(format!("Signature is not a valid BLS signature: {:?}", e)))?;
+
+    let message: Vec<u8> = sign_hash.iter().rev().copied().collect();
+    Ok(signature.verify(false, &message, DST, &[], &public_key, false) == BLST_ERROR::BLST_SUCCESS)
+}
+
+/// Sign `sign_hash` the way a quorum does, for tests standing in for one.
+#[cfg(test)]
+pub fn sign(secret: &blst::min_pk::SecretKey, sign_hash: &Hash) -> Vec<u8> {
+    let message: Vec<u8> = sign_hash.iter().rev().copied().collect();
+    secret.sign(&message, DST, &[]).to_bytes().to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use blst::min_pk::SecretKey;
+
+    fn entry(id: u8, height: u32) -> QuorumListEntry {
+        QuorumListEntry::new_extended(vec![id; 32], vec![id; 48], height, Vec::new(), String::new(), 0, 25)
+    }
+
+    #[test]
+    fn signatures_verify_over_the_sign_hash() {
+        let secret = SecretKey::key_gen(&[7; 32], &[]).unwrap();
+        let public_key = secret.sk_to_pk().to_bytes();
+        let hash = sign_hash(6, &[1; 32], &[2; 32], &[3; 32]);
+        let signature = sign(&secret, &hash);
+
+        assert!(verify_signature(&public_key, &hash, &signature).unwrap());
+        // Any other type, quorum, request or message changes the signed hash
+        assert_ne!(sign_hash(4, &[1; 32], &[2; 32], &[3; 32]), hash);
+        assert_ne!(sign_hash(6, &[1; 32], &[2; 32], &[4; 32]), hash);
+        assert!(!verify_signature(&public_key, &sign_hash(6, &[1; 32], &[9; 32], &[3; 32]), &signature).unwrap());
+
+        assert!(matches!(verify_signature(&public_key, &hash, &[0; 96]), Err(AppError::BadRequest(_))));
+        assert!(matches!(verify_signature(&[0; 48], &hash, &signature), Err(AppError::VerificationFailed(_))));
+    }
+
+    #[test]
+    fn legacy_scheme_is_refused() {
+        let status = |chain_info| CoreSyncStatus::from_rpc(&chain_info, &serde_json::json!({}));
+
+        let pre_v19 = status(serde_json::json!({ "softforks": { "v19": { "type": "bip9", "active": false } } }));
+        assert_eq!(BlsScheme::of(&pre_v19), BlsScheme::Legacy);
+        assert!(matches!(BlsScheme::Legacy.ensure_verifiable(), Err(AppError::BadRequest(_))));
+
+        let v19 = status(serde_json::json!({ "softforks": { "v19": { "type": "buried", "active": true } } }));
+        assert_eq!(BlsScheme::of(&v19), BlsScheme::Basic);
+        assert_eq!(BlsScheme::of(&status(serde_json::json!({}))), BlsScheme::Basic);
+        assert!(BlsScheme::Basic.ensure_verifiable().is_ok());
+    }
+
+    #[test]
+    fn selection_picks_the_lowest_score() {
+        let quorums: Vec<QuorumListEntry> = (1..=5).map(|id| entry(id, 100)).collect();
+        let request_id = [42; 32];
+        let selected = select_quorum(&quorums, 6, &request_id).unwrap();
+
+        let score = |quorum: &QuorumListEntry| llmq_hash(6, &[&quorum.quorum_hash[..].try_into().unwrap(), &request_id]);
+        assert!(quorums.iter().all(|quorum| score(selected) <= score(quorum)));
+        // The order quorums are listed in doesn't matter
+        assert_eq!(select_quorum(quorums.iter().rev(), 6, &request_id), Some(selected));
+        assert_eq!(select_quorum(&[], 6, &request_id), None);
+    }
+
+    #[test]
+    fn rotated_selection_uses_the_request_id_bits() {
+        let quorums: Vec<QuorumListEntry> = (0..4)
+            .map(|index| {
+                let mut quorum = entry(index as u8, 100 + index);
+                quorum.quorum_index = index;
+                quorum
+            })
+            .collect();
+        let index = |top_byte: u8| select_rotated_quorum(&quorums, 4, &[top_byte; 32]).map(|q| q.quorum_index);
+
+        assert_eq!(index(0b0000_0000), Some(0));
+        assert_eq!(index(0b0010_0000), Some(1));
+        assert_eq!(index(0b1100_0000), Some(2));
+        assert_eq!(index(0b0111_1111), Some(3));
+        assert_eq!(select_rotated_quorum(&quorums[..2], 4, &[0b0111_1111; 32]), None);
+    }
+
+    const PLATFORM: LlmqParams =
+        LlmqParams { name: "llmq_test", id: 100, rotated: false, signing_active_quorum_count: 2, dkg_interval: 24 };
+
+    #[test]
+    fn signing_quorums_count_from_the_mined_height() {
+        let mined = |id, height, mined_height| {
+            let mut quorum = entry(id, height);
+            quorum.mined_height = mined_height;
+            quorum
+        };
+        let list: QuorumList = vec![mined(1, 100, Some(110)), mined(2, 124, Some(134)), mined(3, 148, Some(158))].into();
+        let ids = |list: &QuorumList, height| {
+            signing_quorums(list, PLATFORM, height).map(|quorums| quorums.iter().map(|q| q.quorum_hash[0]).collect::<Vec<_>>())
+        };
+
+        // Only the newest signing_active_quorum_count quorums take part
+        assert_eq!(ids(&list, 200).unwrap(), [3, 2]);
+        assert_eq!(ids(&list, 166).unwrap(), [3, 2]);
+        // Quorum 3 was created 17 blocks before 165, but mined only 7 before it
+        assert_eq!(ids(&list, 165).unwrap(), [2, 1]);
+        // At 118 quorum 1 signed with one that quorums 2 and 3 pushed out of the list
+        assert_eq!(ids(&list, 118), None);
+        assert_eq!(ids(&list, 117), None);
+
+        // A list that was never full still holds every quorum it ever had.
+        // Until its mined height is known a quorum counts from its creation
+        let list: QuorumList = vec![mined(1, 100, None)].into();
+        assert_eq!(ids(&list, 108).unwrap(), [1]);
+        assert!(ids(&list, 107).unwrap().is_empty());
+    }
+
+    #[test]
+    fn signers_match_dash_core() {
+        // Expected signers computed from Dash Core's SelectQuorumForSigning
+        // with a separate SHA256d implementation, not with this module
+        let quorums: Vec<QuorumListEntry> = [
+            "0000003e67c3146720fa1b9504927ece5f5daefadaad467dddfd2ff04ba039be",
+            "000000c1b7c3ae5252091267a736a9bf57001d80e82668b3cb3cd09e2f6a43cb",
+        ]
+        .iter()
+        .map(|hash| QuorumListEntry::new(hex::decode(hash).unwrap(), vec![0; 48]))
+        .collect();
+        let llmq_25_67 = LlmqParams { id: 6, ..PLATFORM };
+        for (request_id, signer) in [
+            ("00".repeat(32), 0),
+            ("01".repeat(32), 0),
+            ("02".repeat(32), 1),
+            ("07".repeat(32), 0),
+            ("0123456789abcdef".repeat(4), 1),
+            ("fedcba9876543210".repeat(4), 0),
+        ] {
+            let request_id = parse_hash("request id", &request_id).unwrap();
+            let selected = select_signer(quorums.iter().collect(), llmq_25_67, &request_id).unwrap();
+            assert_eq!(selected, &quorums[signer], "{}", hex::encode(request_id));
+        }
+
+        // Rotated: bits 58..62 of the request id's last 64 bits give the quorum index
+        let rotated: Vec<QuorumListEntry> = (0..32)
+            .map(|index| {
+                let mut quorum = entry(index as u8, 100);
+                quorum.quorum_index = index;
+                quorum
+            })
+            .collect();
+        let llmq_60_75 = LlmqParams { id: 5, rotated: true, signing_active_quorum_count: 32, ..PLATFORM };
+        for (request_id, index) in
+            [("0123456789abcdef".repeat(4), 0), ("fedcba9876543210".repeat(4), 31), ("5a".repeat(32), 22)]
+        {
+            let request_id = parse_hash("request id", &request_id).unwrap();
+            let selected = select_signer(rotated.iter().collect(), llmq_60_75, &request_id).unwrap();
+            assert_eq!(selected.quorum_index, index);
+        }
+    }
+}
@@ -0,0 +1,284 @@
+//! Response bodies that are serialized and compressed once per cache snapshot,
+//! with the HTTP caching headers (`ETag`, `Last-Modified`, `Cache-Control`)
+//! that let clients skip downloading an unchanged list.
+
+use axum::body::Bytes;
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use sha2::{Digest, Sha256};
+use std::io::Write;
+use std::time::{Duration, SystemTime};
+use tokio::sync::OnceCell;
+
+/// A `Content-Encoding` the server can produce.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Identity,
+    Gzip,
+    Brotli,
+    Zstd,
+}
+
+impl Encoding {
+    /// Preferred first when a client accepts several equally.
+    const PREFERENCE: [Encoding; 4] = [Encoding::Brotli, Encoding::Zstd, Encoding::Gzip, Encoding::Identity];
+
+    fn token(self) -> &'static str {
+        match self {
+            Encoding::Identity => "identity",
+            Encoding::Gzip => "gzip",
+            Encoding::Brotli => "br",
+            Encoding::Zstd => "zstd",
+        }
+    }
+
+    /// Pick the encoding for a request from its `Accept-Encoding` header,
+    /// honouring q-values. Falls back to identity.
+    pub fn negotiate(accept_encoding: Option<&HeaderValue>) -> Self {
+        let Some(accept) = accept_encoding.and_then(|value| value.to_str().ok()) else {
+            return Encoding::Identity;
+        };
+
+        let mut wildcard = None;
+        let mut qualities = Vec::new();
+        for (coding, quality) in parse_quality_list(accept) {
+            if coding == "*" {
+                wildcard = Some(quality);
+            } else {
+                qualities.push((coding, quality));
+            }
+        }
+
+        let quality_of = |encoding: Encoding| {
+            qualities
+                .iter()
+                .find(|(coding, _)| coding == encoding.token())
+                .map(|(_, quality)| *quality)
+                .or(wildcard)
+                // identity is acceptable unless explicitly refused
+                .unwrap_or(if encoding == Encoding::Identity { 0.001 } else { 0.0 })
+        };
+
+        let mut best = (Encoding::Identity, 0.0);
+        for encoding in Self::PREFERENCE {
+            let quality = quality_of(encoding);
+            if quality > best.1 {
+                best = (encoding, quality);
+            }
+        }
+        best.0
+    }
+}
+
+/// A serialized response body and its compressed variants. Each variant is
+/// compressed on the blocking pool for the first request that asks for it,
+/// then reused.
+#[derive(Debug)]
+pub struct CachedBody {
+    body: Bytes,
+    content_type: &'static str,
+    /// Hex digest of `body`; the ETag of each variant is derived from it.
+    digest: String,
+    last_modified: SystemTime,
+    gzip: OnceCell<Bytes>,
+    brotli: OnceCell<Bytes>,
+    zstd: OnceCell<Bytes>,
+}
+
+impl CachedBody {
+    pub fn new(body: Bytes, content_type: &'static str, last_modified: SystemTime) -> Self {
+        let digest = hex::encode(&Sha256::digest(&body)[..16]);
+        Self {
+            body,
+            content_type,
+            digest,
+            last_modified,
+            gzip: OnceCell::new(),
+            brotli: OnceCell::new(),
+            zstd: OnceCell::new(),
+        }
+    }
+
+    /// The same body, last modified at `last_modified` instead.
+    pub fn with_last_modified(self, last_modified: SystemTime) -> Self {
+        Self { last_modified, ..self }
+    }
+
+    pub fn digest(&self) -> &str {
+        &self.digest
+    }
+
+    pub fn last_modified(&self) -> SystemTime {
+        self.last_modified
+    }
+
+    /// Strong ETag of the body as sent with `encoding`. Variants get distinct
+    /// tags because their bytes differ.
+    pub fn etag(&self, encoding: Encoding) -> String {
+        match encoding {
+            Encoding::Identity => format!("\"{}\"", self.digest),
+            encoding => format!("\"{}-{}\"", self.digest, encoding.token()),
+        }
+    }
+
+    /// The body as sent with `encoding`.
+    pub async fn encoded(&self, encoding: Encoding) -> Bytes {
+        let variant = match encoding {
+            Encoding::Identity => return self.body.clone(),
+            Encoding::Gzip => &self.gzip,
+            Encoding::Brotli => &self.brotli,
+            Encoding::Zstd => &self.zstd,
+        };
+        variant
+            .get_or_init(|| {
+                let body = self.body.clone();
+                async move {
+                    tokio::task::spawn_blocking(move || compress(&body, encoding))
+                        .await
+                        .expect("compression does not panic")
+                }
+            })
+            .await
+            .clone()
+    }
+
+    /// Build the response for a request: `304 Not Modified` if its
+    /// `If-None-Match` already names this body, otherwise the body in the
+    /// best encoding the client accepts. `max_age` goes into `Cache-Control`.
+    pub async fn respond(&self, request_headers: &HeaderMap, max_age: Duration) -> Response {
+        let encoding = Encoding::negotiate(request_headers.get(header::ACCEPT_ENCODING));
+        let etag = self.etag(encoding);
+
+        let mut headers = HeaderMap::new();
+        headers.insert(header::VARY, HeaderValue::from_static("accept, accept-encoding"));
+        headers.insert(header::ETAG, header_value(&etag));
+        headers.insert(header::LAST_MODIFIED, header_value(&httpdate::fmt_http_date(self.last_modified)));
+        headers.insert(
+            header::CACHE_CONTROL,
+            header_value(&format!("public, max-age={}", max_age.as_secs())),
+        );
+
+        if if_none_match(request_headers, &etag) {
+            return (StatusCode::NOT_MODIFIED, headers).into_response();
+        }
+
+        headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(self.content_type));
+        if encoding != Encoding::Identity {
+            headers.insert(header::CONTENT_ENCODING, HeaderValue::from_static(encoding.token()));
+        }
+        (headers, self.encoded(encoding).await).into_response()
+    }
+}
+
+/// Split an `Accept`-style header into lowercased values and their q-values.
+pub fn parse_quality_list(header: &str) -> Vec<(String, f32)> {
+    header
+        .split(',')
+        .filter(|item| !item.trim().is_empty())
+        .map(|item| {
+            let mut parts = item.split(';');
+            let value = parts.next().unwrap_or_default().trim().to_ascii_lowercase();
+            let quality = parts
+                .filter_map(|param| param.trim().strip_prefix("q="))
+                .find_map(|q| q.trim().parse::<f32>().ok())
+                .unwrap_or(1.0);
+            (value, quality)
+        })
+        .collect()
+}
+
+fn header_value(value: &str) -> HeaderValue {
+    HeaderValue::from_str(value).expect("generated header values are ASCII")
+}
+
+/// Whether the request's `If-None-Match` matches `etag`, using the weak
+/// comparison RFC 9110 prescribes for it.
+fn if_none_match(request_headers: &HeaderMap, etag: &str) -> bool {
+    request_headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .any(|candidate| candidate == "*" || candidate.trim_start_matches("W/") == etag)
+}
+
+fn compress(data: &[u8], encoding: Encoding) -> Bytes {
+    let compressed = match encoding {
+        Encoding::Identity => Ok(data.to_vec()),
+        Encoding::Gzip => {
+            let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(data).and_then(|_| encoder.finish())
+        }
+        Encoding::Brotli => {
+            let mut compressed = Vec::new();
+            let params = brotli::enc::BrotliEncoderParams { quality: 9, ..Default::default() };
+            brotli::BrotliCompress(&mut &data[..], &mut compressed, &params).map(|_| compressed)
+        }
+        Encoding::Zstd => zstd::encode_all(data, 3),
+    };
+    compressed.expect("compressing into memory cannot fail").into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    fn negotiate(accept: &str) -> Encoding {
+        Encoding::negotiate(Some(&HeaderValue::from_str(accept).unwrap()))
+    }
+
+    #[test]
+    fn negotiation_follows_q_values_and_preference() {
+        assert_eq!(Encoding::negotiate(None), Encoding::Identity);
+        assert_eq!(negotiate("gzip"), Encoding::Gzip);
+        assert_eq!(negotiate("gzip, deflate, br, zstd"), Encoding::Brotli);
+        assert_eq!(negotiate("gzip;q=1.0, br;q=0.5"), Encoding::Gzip);
+        assert_eq!(negotiate("zstd, gzip;q=0.8"), Encoding::Zstd);
+        assert_eq!(negotiate("deflate"), Encoding::Identity);
+        assert_eq!(negotiate("*"), Encoding::Brotli);
+        assert_eq!(negotiate("*;q=0.5, br;q=0"), Encoding::Zstd);
+    }
+
+    #[tokio::test]
+    async fn compressed_variants_decode_to_the_json() {
+        let json = Bytes::from(r#"{"success":true,"data":[1,2,3]}"#.repeat(50));
+        let body = CachedBody::new(json.clone(), "application/json", SystemTime::UNIX_EPOCH);
+
+        let mut gunzipped = Vec::new();
+        flate2::read::GzDecoder::new(&body.encoded(Encoding::Gzip).await[..]).read_to_end(&mut gunzipped).unwrap();
+        assert_eq!(gunzipped, json);
+
+        let mut unbrotlied = Vec::new();
+        brotli::BrotliDecompress(&mut &body.encoded(Encoding::Brotli).await[..], &mut unbrotlied).unwrap();
+        assert_eq!(unbrotlied, json);
+
+        assert_eq!(zstd::decode_all(&body.encoded(Encoding::Zstd).await[..]).unwrap(), json);
+        assert!(body.encoded(Encoding::Zstd).await.len() < json.len());
+    }
+
+    #[test]
+    fn etags_depend_on_content_and_encoding() {
+        let body = CachedBody::new(Bytes::from_static(b"[1]"), "application/json", SystemTime::UNIX_EPOCH);
+        let same = CachedBody::new(Bytes::from_static(b"[1]"), "application/json", SystemTime::now());
+        let other = CachedBody::new(Bytes::from_static(b"[2]"), "application/json", SystemTime::UNIX_EPOCH);
+
+        assert_eq!(body.etag(Encoding::Identity), same.etag(Encoding::Identity));
+        assert_ne!(body.etag(Encoding::Identity), other.etag(Encoding::Identity));
+        assert_ne!(body.etag(Encoding::Identity), body.etag(Encoding::Gzip));
+    }
+
+    #[test]
+    fn if_none_match_uses_weak_comparison_and_lists() {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::IF_NONE_MATCH, HeaderValue::from_static("\"a\", W/\"b\""));
+        assert!(if_none_match(&headers, "\"a\""));
+        assert!(if_none_match(&headers, "\"b\""));
+        assert!(!if_none_match(&headers, "\"c\""));
+
+        headers.insert(header::IF_NONE_MATCH, HeaderValue::from_static("*"));
+        assert!(if_none_match(&headers, "\"c\""));
+        assert!(!if_none_match(&HeaderMap::new(), "\"c\""));
+    }
+}
@@ -1,3 +1,4 @@
+use quorum_list_client::SignatureAlgorithm;
 use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::fs;
@@ -9,6 +10,7 @@ pub enum Network {
     Mainnet,
     #[default]
     Testnet,
+    Devnet,
     Regtest,
 }
 
@@ -17,6 +19,7 @@ impl fmt::Display for Network {
         match self {
             Network::Mainnet => write!(f, "mainnet"),
             Network::Testnet => write!(f, "testnet"),
+            Network::Devnet => write!(f, "devnet"),
             Network::Regtest => write!(f, "regtest"),
         }
     }
@@ -29,29 +32,86 @@ impl TryFrom<&str> for Network {
         match s.to_lowercase().as_str() {
             "mainnet" => Ok(Network::Mainnet),
             "testnet" => Ok(Network::Testnet),
+            "devnet" => Ok(Network::Devnet),
             "regtest" => Ok(Network::Regtest),
             _ => Err(format!(
-                "Invalid network '{}'. Must be one of: mainnet, testnet, regtest",
+                "Invalid network '{}'. Must be one of: mainnet, testnet, devnet, regtest",
                 s
             )),
         }
     }
 }
 
+/// An LLMQ type with the consensus parameters quorum selection depends on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LlmqParams {
+    pub name: &'static str,
+    pub id: u32,
+    /// Rotated (DIP-24) quorums are selected by quorum index instead of by hash.
+    pub rotated: bool,
+    pub signing_active_quorum_count: u32,
+    /// Blocks between two DKG rounds; the length of a rotation cycle.
+    pub dkg_interval: u32,
+}
+
+const LLMQ_100_67: LlmqParams =
+    LlmqParams { name: "llmq_100_67", id: 4, rotated: false, signing_active_quorum_count: 24, dkg_interval: 24 };
+const LLMQ_25_67: LlmqParams =
+    LlmqParams { name: "llmq_25_67", id: 6, rotated: false, signing_active_quorum_count: 24, dkg_interval: 24 };
+const LLMQ_TEST_PLATFORM: LlmqParams =
+    LlmqParams { name: "llmq_test_platform", id: 106, rotated: false, signing_active_quorum_count: 2, dkg_interval: 24 };
+const LLMQ_DEVNET_PLATFORM: LlmqParams =
+    LlmqParams { name: "llmq_devnet_platform", id: 107, rotated: false, signing_active_quorum_count: 4, dkg_interval: 24 };
+const LLMQ_50_60: LlmqParams =
+    LlmqParams { name: "llmq_50_60", id: 1, rotated: false, signing_active_quorum_count: 24, dkg_interval: 24 };
+const LLMQ_400_60: LlmqParams =
+    LlmqParams { name: "llmq_400_60", id: 2, rotated: false, signing_active_quorum_count: 4, dkg_interval: 288 };
+const LLMQ_60_75: LlmqParams =
+    LlmqParams { name: "llmq_60_75", id: 5, rotated: true, signing_active_quorum_count: 32, dkg_interval: 288 };
+const LLMQ_TEST: LlmqParams =
+    LlmqParams { name: "llmq_test", id: 100, rotated: false, signing_active_quorum_count: 2, dkg_interval: 24 };
+const LLMQ_DEVNET: LlmqParams =
+    LlmqParams { name: "llmq_devnet", id: 101, rotated: false, signing_active_quorum_count: 4, dkg_interval: 24 };
+const LLMQ_TEST_DIP0024: LlmqParams =
+    LlmqParams { name: "llmq_test_dip0024", id: 103, rotated: true, signing_active_quorum_count: 2, dkg_interval: 24 };
+const LLMQ_DEVNET_DIP0024: LlmqParams =
+    LlmqParams { name: "llmq_devnet_dip0024", id: 105, rotated: true, signing_active_quorum_count: 2, dkg_interval: 48 };
+
 impl Network {
-    pub fn llmq_type(&self) -> &'static str {
+    /// The LLMQ type signing for Platform, whose quorums the server lists.
+    pub fn platform_llmq(&self) -> LlmqParams {
         match self {
-            Network::Mainnet => "llmq_100_67",
-            Network::Testnet => "llmq_25_67",
-            Network::Regtest => "llmq_test_platform",
+            Network::Mainnet => LLMQ_100_67,
+            Network::Testnet => LLMQ_25_67,
+            Network::Devnet => LLMQ_DEVNET_PLATFORM,
+            Network::Regtest => LLMQ_TEST_PLATFORM,
         }
     }
 
+    pub fn llmq_type(&self) -> &'static str {
+        self.platform_llmq().name
+    }
+
     pub fn llmq_type_id(&self) -> u32 {
+        self.platform_llmq().id
+    }
+
+    /// The LLMQ type signing ChainLocks.
+    pub fn chainlock_llmq(&self) -> LlmqParams {
+        match self {
+            Network::Mainnet => LLMQ_400_60,
+            Network::Testnet => LLMQ_50_60,
+            Network::Devnet => LLMQ_DEVNET,
+            Network::Regtest => LLMQ_TEST,
+        }
+    }
+
+    /// The LLMQ type signing deterministic InstantSend locks.
+    pub fn instantsend_llmq(&self) -> LlmqParams {
         match self {
-            Network::Mainnet => 4,   // llmq_100_67 = type 4
-            Network::Testnet => 6,   // llmq_25_67 = type 6
-            Network::Regtest => 106, // llmq_test_platform = type 106
+            Network::Mainnet | Network::Testnet => LLMQ_60_75,
+            Network::Devnet => LLMQ_DEVNET_DIP0024,
+            Network::Regtest => LLMQ_TEST_DIP0024,
         }
     }
 
@@ -59,6 +119,7 @@ impl Network {
         match self {
             Network::Mainnet => 443,
             Network::Testnet => 1443,
+            Network::Devnet => 443,
             Network::Regtest => 2443,
         }
     }
@@ -67,12 +128,89 @@ impl Network {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
     pub server: ServerConfig,
+    #[serde(default)]
     pub rpc: RpcConfig,
+    /// Where quorum, block and masternode data is read from. Defaults to `rpc`.
+    #[serde(default)]
+    pub source: DataSourceConfig,
+    #[serde(default)]
     pub quorum: QuorumConfig,
     #[serde(default)]
     pub network: Network,
     #[serde(default)]
     pub docker: DockerConfig,
+    #[serde(default)]
+    pub dapi: DapiConfig,
+    #[serde(default)]
+    pub health: HealthConfig,
+    /// Key used to sign published quorum lists. Lists are unsigned when omitted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signing: Option<SigningConfig>,
+    /// Additional networks served by this instance. When empty, the
+    /// top-level `rpc`/`quorum`/`network` settings describe the only network.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub networks: Vec<NetworkSection>,
+    /// Name of the network served by the un-prefixed routes.
+    /// Defaults to the first entry of `networks`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_network: Option<String>,
+}
+
+/// First path segments of the routes every network serves, and of the
+/// documentation. A network with one of these names would shadow or collide
+/// with them when served under `/<name>/...`.
+pub const RESERVED_NETWORK_NAMES: &[&str] = &[
+    "health",
+    "quorums",
+    "previous",
+    "signing-key",
+    "verify",
+    "masternodes",
+    "dapi",
+    "core",
+    "docs",
+    "openapi.json",
+];
+
+/// A `[[networks]]` section. Each one gets its own quorum list and
+/// masternode cache and is served under `/<name>/...`.
+/// `source`, `quorum`, `docker` and `dapi` fall back to the top-level values when omitted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NetworkSection {
+    pub name: String,
+    pub network: Network,
+    pub rpc: RpcConfig,
+    #[serde(default)]
+    pub source: Option<DataSourceConfig>,
+    #[serde(default)]
+    pub quorum: Option<QuorumConfig>,
+    #[serde(default)]
+    pub docker: Option<DockerConfig>,
+    #[serde(default)]
+    pub dapi: Option<DapiConfig>,
+}
+
+/// A `[source]` section selecting the `CoreDataSource` implementation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum DataSourceConfig {
+    /// Dash Core over JSON-RPC, using the `[rpc]` settings.
+    #[default]
+    Rpc,
+    /// A JSON file of recorded RPC exchanges (the cassette format).
+    Fixture { path: String },
+    /// Another quorum-list-server, e.g. `http://upstream:8080/testnet`.
+    Http { url: String },
+}
+
+impl fmt::Display for DataSourceConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DataSourceConfig::Rpc => write!(f, "Dash Core RPC"),
+            DataSourceConfig::Fixture { path } => write!(f, "Fixture {}", path),
+            DataSourceConfig::Http { url } => write!(f, "Upstream server {}", url),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -84,10 +222,92 @@ pub struct DockerConfig {
     pub localhost_replacement: Option<String>,
 }
 
+/// How Evo masternodes' DAPI endpoints are reached for version checks.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DapiConfig {
+    /// Overrides the network's default DAPI port.
+    #[serde(default)]
+    pub port: Option<u16>,
+    /// PEM file with an extra root certificate to trust, for networks whose
+    /// nodes use self-signed certificates (e.g. local devnets).
+    #[serde(default)]
+    pub ca_cert_path: Option<String>,
+    /// File the per-node probe history is saved to after every refresh, so
+    /// uptime survives restarts. Kept in memory only when omitted.
+    #[serde(default)]
+    pub probe_history_path: Option<String>,
+    /// File the Evo masternode history is saved to whenever it changes, so
+    /// events survive restarts. Kept in memory only when omitted.
+    #[serde(default)]
+    pub masternode_history_path: Option<String>,
+    /// Most version checks in flight at once.
+    #[serde(default = "default_probe_concurrency")]
+    pub probe_concurrency: usize,
+    /// How long a node has to answer its version check before it fails.
+    #[serde(default = "default_probe_timeout_ms")]
+    pub probe_timeout_ms: u64,
+    /// Background refreshes start their version checks spread over this
+    /// many seconds, in random order, rather than all at once.
+    #[serde(default = "default_probe_spread_secs")]
+    pub probe_spread_secs: u64,
+}
+
+fn default_probe_concurrency() -> usize {
+    32
+}
+
+fn default_probe_timeout_ms() -> u64 {
+    2000
+}
+
+fn default_probe_spread_secs() -> u64 {
+    300
+}
+
+impl Default for DapiConfig {
+    fn default() -> Self {
+        Self {
+            port: None,
+            ca_cert_path: None,
+            probe_history_path: None,
+            masternode_history_path: None,
+            probe_concurrency: default_probe_concurrency(),
+            probe_timeout_ms: default_probe_timeout_ms(),
+            probe_spread_secs: default_probe_spread_secs(),
+        }
+    }
+}
+
+/// A `[signing]` section: the key quorum list snapshots are signed with.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SigningConfig {
+    #[serde(default = "default_signing_algorithm")]
+    pub algorithm: SignatureAlgorithm,
+    /// File holding the hex encoded 32-byte secret key.
+    pub key_path: String,
+}
+
+fn default_signing_algorithm() -> SignatureAlgorithm {
+    SignatureAlgorithm::Ed25519
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServerConfig {
     pub port: u16,
     pub host: String,
+    /// How long to wait for in-flight requests and background tasks to
+    /// finish after a shutdown signal before exiting anyway.
+    #[serde(default = "default_shutdown_timeout_secs")]
+    pub shutdown_timeout_secs: u64,
+    /// Serve the raw `/core/*` data source passthrough. Off by default: it
+    /// relays arbitrary lookups to Dash Core, so enable it only for
+    /// downstream servers using an `http` source.
+    #[serde(default)]
+    pub expose_core: bool,
+}
+
+fn default_shutdown_timeout_secs() -> u64 {
+    30
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -95,11 +315,133 @@ pub struct RpcConfig {
     pub url: String,
     pub username: String,
     pub password: String,
+    /// Whether RPC traffic goes to Dash Core, is recorded, or is replayed.
+    #[serde(default)]
+    pub mode: RpcMode,
+    /// Cassette file written in `record` mode and read in `replay` mode.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cassette: Option<String>,
+    /// Recording stops once the cassette holds this many exchanges, so
+    /// background refreshes can't grow it without bound.
+    #[serde(default = "default_cassette_max_exchanges")]
+    pub cassette_max_exchanges: usize,
+    /// Start `record` mode with an empty cassette, discarding an existing
+    /// one, rather than appending to it.
+    #[serde(default)]
+    pub cassette_overwrite: bool,
+}
+
+fn default_cassette_max_exchanges() -> usize {
+    10_000
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum RpcMode {
+    /// Talk to Dash Core.
+    #[default]
+    Live,
+    /// Talk to Dash Core and write every call and its response to the cassette.
+    Record,
+    /// Answer every call from the cassette; Dash Core is never contacted.
+    Replay,
+}
+
+impl TryFrom<&str> for RpcMode {
+    type Error = String;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        match s.to_lowercase().as_str() {
+            "live" => Ok(RpcMode::Live),
+            "record" => Ok(RpcMode::Record),
+            "replay" => Ok(RpcMode::Replay),
+            _ => Err(format!(
+                "Invalid RPC mode '{}'. Must be one of: live, record, replay",
+                s
+            )),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct QuorumConfig {
     pub previous_blocks_offset: u32,
+    /// How often the quorum list is reloaded from Dash Core in the background.
+    #[serde(default = "default_quorum_refresh_interval_secs")]
+    pub refresh_interval_secs: u64,
+    /// Also load the ChainLock and InstantSend quorums on every refresh, for
+    /// `/verify/chainlock` and `/verify/islock`.
+    #[serde(default)]
+    pub verify_locks: bool,
+    /// File the quorum history is saved to after every refresh, so retired
+    /// quorums can still be looked up after a restart. Kept in memory only when omitted.
+    #[serde(default)]
+    pub history_path: Option<String>,
+    /// How far below the published list's height `/quorums/diff` reaches.
+    #[serde(default = "default_diff_window_blocks")]
+    pub diff_window_blocks: u32,
+}
+
+fn default_quorum_refresh_interval_secs() -> u64 {
+    60
+}
+
+fn default_diff_window_blocks() -> u32 {
+    576
+}
+
+/// Thresholds used by `/health/ready` to decide whether served data is too old.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthConfig {
+    /// Maximum age of the last successful quorum refresh.
+    #[serde(default = "default_max_quorum_age_secs")]
+    pub max_quorum_age_secs: u64,
+    /// Maximum age of the last successful masternode cache refresh.
+    #[serde(default = "default_max_masternode_age_secs")]
+    pub max_masternode_age_secs: u64,
+}
+
+fn default_max_quorum_age_secs() -> u64 {
+    300
+}
+
+fn default_max_masternode_age_secs() -> u64 {
+    1800
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        Self {
+            max_quorum_age_secs: default_max_quorum_age_secs(),
+            max_masternode_age_secs: default_max_masternode_age_secs(),
+        }
+    }
+}
+
+impl Default for RpcConfig {
+    fn default() -> Self {
+        Self {
+            url: "http://127.0.0.1:19998".to_string(),
+            username: "dashrpc".to_string(),
+            password: "password".to_string(),
+            mode: RpcMode::default(),
+            cassette: None,
+            cassette_max_exchanges: default_cassette_max_exchanges(),
+            cassette_overwrite: false,
+        }
+    }
+}
+
+impl Default for QuorumConfig {
+    fn default() -> Self {
+        Self {
+            previous_blocks_offset: 8,
+            refresh_interval_secs: default_quorum_refresh_interval_secs(),
+            verify_locks: false,
+            history_path: None,
+            diff_window_blocks: default_diff_window_blocks(),
+        }
+    }
 }
 
 impl Default for Config {
@@ -108,17 +450,19 @@ impl Default for Config {
             server: ServerConfig {
                 port: 3000,
                 host: "0.0.0.0".to_string(),
+                shutdown_timeout_secs: default_shutdown_timeout_secs(),
+                expose_core: false,
             },
-            rpc: RpcConfig {
-                url: "http://127.0.0.1:19998".to_string(),
-                username: "dashrpc".to_string(),
-                password: "password".to_string(),
-            },
-            quorum: QuorumConfig {
-                previous_blocks_offset: 8,
-            },
+            rpc: RpcConfig::default(),
+            source: DataSourceConfig::default(),
+            quorum: QuorumConfig::default(),
             network: Network::default(),
             docker: DockerConfig::default(),
+            dapi: DapiConfig::default(),
+            health: HealthConfig::default(),
+            signing: None,
+            networks: Vec::new(),
+            default_network: None,
         }
     }
 }
@@ -151,6 +495,18 @@ impl Config {
             config.server.host = host;
         }
 
+        if let Ok(timeout) = std::env::var("SHUTDOWN_TIMEOUT_SECS") {
+            if let Ok(timeout_secs) = timeout.parse::<u64>() {
+                config.server.shutdown_timeout_secs = timeout_secs;
+            }
+        }
+
+        if let Ok(expose) = std::env::var("API_EXPOSE_CORE") {
+            if let Ok(expose_core) = expose.parse::<bool>() {
+                config.server.expose_core = expose_core;
+            }
+        }
+
         if let Ok(url) = std::env::var("DASH_RPC_URL") {
             config.rpc.url = url;
         }
@@ -163,12 +519,74 @@ impl Config {
             config.rpc.password = password;
         }
 
+        if let Ok(mode) = std::env::var("DASH_RPC_MODE") {
+            config.rpc.mode = RpcMode::try_from(mode.as_str()).unwrap_or_else(|e| panic!("{}", e));
+        }
+
+        if let Ok(cassette) = std::env::var("DASH_RPC_CASSETTE") {
+            config.rpc.cassette = Some(cassette);
+        }
+
+        if let Ok(max_exchanges) = std::env::var("DASH_RPC_CASSETTE_MAX_EXCHANGES") {
+            if let Ok(max_exchanges) = max_exchanges.parse::<usize>() {
+                config.rpc.cassette_max_exchanges = max_exchanges;
+            }
+        }
+
+        if let Ok(overwrite) = std::env::var("DASH_RPC_CASSETTE_OVERWRITE") {
+            if let Ok(overwrite) = overwrite.parse::<bool>() {
+                config.rpc.cassette_overwrite = overwrite;
+            }
+        }
+
+        if let Ok(path) = std::env::var("DATA_SOURCE_FIXTURE") {
+            config.source = DataSourceConfig::Fixture { path };
+        }
+
+        if let Ok(url) = std::env::var("DATA_SOURCE_URL") {
+            config.source = DataSourceConfig::Http { url };
+        }
+
         if let Ok(offset) = std::env::var("QUORUM_PREVIOUS_BLOCKS_OFFSET") {
             if let Ok(offset_num) = offset.parse::<u32>() {
                 config.quorum.previous_blocks_offset = offset_num;
             }
         }
 
+        if let Ok(interval) = std::env::var("QUORUM_REFRESH_INTERVAL_SECS") {
+            if let Ok(interval_secs) = interval.parse::<u64>() {
+                config.quorum.refresh_interval_secs = interval_secs;
+            }
+        }
+
+        if let Ok(verify_locks) = std::env::var("QUORUM_VERIFY_LOCKS") {
+            if let Ok(verify_locks) = verify_locks.parse::<bool>() {
+                config.quorum.verify_locks = verify_locks;
+            }
+        }
+
+        if let Ok(history_path) = std::env::var("QUORUM_HISTORY_PATH") {
+            config.quorum.history_path = Some(history_path);
+        }
+
+        if let Ok(window) = std::env::var("QUORUM_DIFF_WINDOW_BLOCKS") {
+            if let Ok(window_blocks) = window.parse::<u32>() {
+                config.quorum.diff_window_blocks = window_blocks;
+            }
+        }
+
+        if let Ok(max_age) = std::env::var("HEALTH_MAX_QUORUM_AGE_SECS") {
+            if let Ok(max_age_secs) = max_age.parse::<u64>() {
+                config.health.max_quorum_age_secs = max_age_secs;
+            }
+        }
+
+        if let Ok(max_age) = std::env::var("HEALTH_MAX_MASTERNODE_AGE_SECS") {
+            if let Ok(max_age_secs) = max_age.parse::<u64>() {
+                config.health.max_masternode_age_secs = max_age_secs;
+            }
+        }
+
         if let Ok(network_str) = std::env::var("DASH_NETWORK") {
             config.network =
                 Network::try_from(network_str.as_str()).unwrap_or_else(|e| panic!("{}", e));
@@ -178,6 +596,51 @@ impl Config {
             config.docker.localhost_replacement = Some(localhost_replacement);
         }
 
+        if let Ok(port) = std::env::var("DAPI_PORT") {
+            if let Ok(port_num) = port.parse::<u16>() {
+                config.dapi.port = Some(port_num);
+            }
+        }
+
+        if let Ok(ca_cert_path) = std::env::var("DAPI_CA_CERT_PATH") {
+            config.dapi.ca_cert_path = Some(ca_cert_path);
+        }
+
+        if let Ok(probe_history_path) = std::env::var("DAPI_PROBE_HISTORY_PATH") {
+            config.dapi.probe_history_path = Some(probe_history_path);
+        }
+
+        if let Ok(masternode_history_path) = std::env::var("DAPI_MASTERNODE_HISTORY_PATH") {
+            config.dapi.masternode_history_path = Some(masternode_history_path);
+        }
+
+        if let Ok(concurrency) = std::env::var("DAPI_PROBE_CONCURRENCY") {
+            if let Ok(concurrency) = concurrency.parse::<usize>() {
+                config.dapi.probe_concurrency = concurrency;
+            }
+        }
+
+        if let Ok(timeout) = std::env::var("DAPI_PROBE_TIMEOUT_MS") {
+            if let Ok(timeout_ms) = timeout.parse::<u64>() {
+                config.dapi.probe_timeout_ms = timeout_ms;
+            }
+        }
+
+        if let Ok(spread) = std::env::var("DAPI_PROBE_SPREAD_SECS") {
+            if let Ok(spread_secs) = spread.parse::<u64>() {
+                config.dapi.probe_spread_secs = spread_secs;
+            }
+        }
+
+        if let Ok(key_path) = std::env::var("SIGNING_KEY_PATH") {
+            let algorithm = match std::env::var("SIGNING_ALGORITHM").as_deref() {
+                Ok("secp256k1") => SignatureAlgorithm::Secp256k1,
+                Ok("ed25519") | Err(_) => SignatureAlgorithm::Ed25519,
+                Ok(other) => panic!("Invalid signing algorithm '{}'. Must be one of: ed25519, secp256k1", other),
+            };
+            config.signing = Some(SigningConfig { algorithm, key_path });
+        }
+
         config
     }
 
@@ -190,6 +653,85 @@ impl Config {
         Ok(())
     }
 
+    /// Resolve the configured networks into one standalone `Config` per network,
+    /// keyed by the name used as its route prefix.
+    pub fn network_configs(&self) -> Result<Vec<(String, Config)>, String> {
+        if self.networks.is_empty() {
+            return Ok(vec![(self.network.to_string(), self.clone())]);
+        }
+
+        let mut configs: Vec<(String, Config)> = Vec::with_capacity(self.networks.len());
+        for section in &self.networks {
+            let valid_name = !section.name.is_empty()
+                && section
+                    .name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+            if !valid_name {
+                return Err(format!(
+                    "Invalid network name '{}'. Use letters, digits, '-' or '_'",
+                    section.name
+                ));
+            }
+            if RESERVED_NETWORK_NAMES.contains(&section.name.as_str()) {
+                return Err(format!("Network name '{}' is reserved for a route", section.name));
+            }
+            if configs.iter().any(|(name, _)| *name == section.name) {
+                return Err(format!("Duplicate network name '{}'", section.name));
+            }
+
+            let config = Config {
+                server: self.server.clone(),
+                rpc: section.rpc.clone(),
+                source: section.source.clone().unwrap_or_else(|| self.source.clone()),
+                quorum: section.quorum.clone().unwrap_or_else(|| self.quorum.clone()),
+                network: section.network,
+                docker: section.docker.clone().unwrap_or_else(|| self.docker.clone()),
+                dapi: section.dapi.clone().unwrap_or_else(|| self.dapi.clone()),
+                health: self.health.clone(),
+                signing: self.signing.clone(),
+                networks: Vec::new(),
+                default_network: None,
+            };
+            if let Some(path) = &config.dapi.probe_history_path {
+                if configs.iter().any(|(_, other)| other.dapi.probe_history_path.as_ref() == Some(path)) {
+                    return Err(format!(
+                        "Network '{}' shares probe_history_path {} with another network",
+                        section.name, path
+                    ));
+                }
+            }
+            if let Some(path) = &config.dapi.masternode_history_path {
+                if configs.iter().any(|(_, other)| other.dapi.masternode_history_path.as_ref() == Some(path)) {
+                    return Err(format!(
+                        "Network '{}' shares masternode_history_path {} with another network",
+                        section.name, path
+                    ));
+                }
+            }
+            if let Some(path) = &config.quorum.history_path {
+                if configs.iter().any(|(_, other)| other.quorum.history_path.as_ref() == Some(path)) {
+                    return Err(format!(
+                        "Network '{}' shares quorum history_path {} with another network",
+                        section.name, path
+                    ));
+                }
+            }
+            configs.push((section.name.clone(), config));
+        }
+
+        Ok(configs)
+    }
+
+    /// Name of the network served by the un-prefixed routes.
+    pub fn default_network_name(&self) -> String {
+        match (&self.default_network, self.networks.first()) {
+            (Some(name), _) => name.clone(),
+            (None, Some(section)) => section.name.clone(),
+            (None, None) => self.network.to_string(),
+        }
+    }
+
     pub fn get_llmq_type(&self) -> &'static str {
         self.network.llmq_type()
     }
@@ -198,8 +740,10 @@ impl Config {
         self.network.llmq_type_id()
     }
 
-    pub fn get_dapi_port(&self) -> u16 {
-        self.network.dapi_port()
+    /// The DAPI port of a node announcing `platform_http_port`: the configured
+    /// override, else the node's own port, else the network's default.
+    pub fn dapi_port_for(&self, platform_http_port: Option<u16>) -> u16 {
+        self.dapi.port.or(platform_http_port).unwrap_or_else(|| self.network.dapi_port())
     }
 
     /// Replace 127.0.0.1 in an address with the configured replacement host.
@@ -0,0 +1,135 @@
+//! DAPI endpoints SDK clients can bootstrap from: Evo nodes whose latest
+//! version check succeeded, best first.
+
+use crate::config::Config;
+use crate::masternode::EvoMasternodeInfo;
+use crate::probe_history::{ProbeHistory, Uptime};
+use rand::seq::SliceRandom;
+use serde::Serialize;
+use std::cmp::Ordering;
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct DapiAddress {
+    #[serde(rename = "proTxHash")]
+    pub pro_tx_hash: String,
+    /// Always `https`: version checks only succeed over TLS, so every node
+    /// listed has been reached with it.
+    #[schema(example = "https://35.166.18.166:1443")]
+    pub uri: String,
+    pub host: String,
+    pub port: u16,
+    /// Latency of the latest version check.
+    pub latency_ms: u64,
+    pub uptime: Uptime,
+}
+
+/// The nodes whose latest version check succeeded, ranked by uptime over the
+/// last day and then by latency.
+pub fn healthy_addresses(
+    masternodes: &[EvoMasternodeInfo],
+    probes: &ProbeHistory,
+    config: &Config,
+    now: u64,
+) -> Vec<DapiAddress> {
+    let mut addresses: Vec<DapiAddress> = masternodes
+        .iter()
+        .filter(|node| node.version_check == "success")
+        .filter_map(|node| {
+            let (host, _) = node.address.rsplit_once(':')?;
+            let port = config.dapi_port_for(node.platform_http_port);
+            Some(DapiAddress {
+                pro_tx_hash: node.pro_tx_hash.clone(),
+                uri: format!("https://{}:{}", host, port),
+                host: host.to_string(),
+                port,
+                latency_ms: probes.latest(&node.pro_tx_hash)?.latency_ms,
+                uptime: probes.uptime(&node.pro_tx_hash, now)?,
+            })
+        })
+        .collect();
+
+    addresses.sort_by(|a, b| {
+        let uptime = |address: &DapiAddress| address.uptime.last_day.unwrap_or(0.0);
+        uptime(b)
+            .partial_cmp(&uptime(a))
+            .unwrap_or(Ordering::Equal)
+            .then(a.latency_ms.cmp(&b.latency_ms))
+            .then_with(|| a.pro_tx_hash.cmp(&b.pro_tx_hash))
+    });
+    addresses
+}
+
+/// Shuffle `addresses`, so clients taking the first few spread their load.
+pub fn shuffle(addresses: &mut [DapiAddress]) {
+    addresses.shuffle(&mut rand::thread_rng());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::probe_history::{ProbeOutcome, ProbeResult};
+    use crate::test_support::test_config;
+
+    const NOW: u64 = 1_700_000_000;
+
+    fn node(pro_tx_hash: &str, address: &str, version_check: &str, platform_http_port: Option<u16>) -> EvoMasternodeInfo {
+        EvoMasternodeInfo {
+            pro_tx_hash: pro_tx_hash.to_string(),
+            address: address.to_string(),
+            status: "ENABLED".to_string(),
+            version_check: version_check.to_string(),
+            dapi_version: None,
+            drive_version: None,
+            platform_http_port,
+        }
+    }
+
+    fn record(probes: &mut ProbeHistory, pro_tx_hash: &str, outcomes: &[bool], latency_ms: u64) {
+        for (age, success) in outcomes.iter().rev().enumerate() {
+            probes.record(pro_tx_hash, ProbeResult {
+                timestamp: NOW - age as u64 * 600,
+                outcome: if *success { ProbeOutcome::Success } else { ProbeOutcome::Fail },
+                latency_ms,
+                dapi_version: None,
+                drive_version: None,
+            });
+        }
+    }
+
+    #[test]
+    fn healthy_nodes_are_ranked_by_uptime_then_latency() {
+        let masternodes = [
+            node("flapping", "10.0.0.1:19999", "success", Some(1443)),
+            node("slow", "10.0.0.2:19999", "success", Some(2443)),
+            node("fast", "10.0.0.3:19999", "success", None),
+            node("failing", "10.0.0.4:19999", "fail", Some(1443)),
+            node("unprobed", "10.0.0.5:19999", "success", Some(1443)),
+            node("bad-address", "not-an-address", "success", Some(1443)),
+        ];
+        let mut probes = ProbeHistory::new();
+        record(&mut probes, "flapping", &[false, true, false, true], 10);
+        record(&mut probes, "slow", &[true, true, true, true], 300);
+        record(&mut probes, "fast", &[true, true, true, true], 20);
+        record(&mut probes, "failing", &[true, true, true, false], 20);
+        record(&mut probes, "bad-address", &[true], 20);
+
+        let mut config = test_config("http://127.0.0.1:1");
+        let addresses = healthy_addresses(&masternodes, &probes, &config, NOW);
+        let ranked: Vec<_> = addresses.iter().map(|address| (address.pro_tx_hash.as_str(), address.uri.as_str())).collect();
+        assert_eq!(
+            ranked,
+            [
+                ("fast", "https://10.0.0.3:1443"),
+                ("slow", "https://10.0.0.2:2443"),
+                ("flapping", "https://10.0.0.1:1443"),
+            ]
+        );
+        assert_eq!(addresses[2].uptime.last_hour, Some(50.0));
+
+        // A configured DAPI port overrides the announced one
+        config.dapi.port = Some(443);
+        let addresses = healthy_addresses(&masternodes, &probes, &config, NOW);
+        assert!(addresses.iter().all(|address| address.port == 443));
+    }
+}
@@ -0,0 +1,365 @@
+//! Where quorum, block and masternode data comes from: Dash Core over RPC,
+//! a fixture file of recorded RPC exchanges, or another quorum-list-server.
+
+use crate::config::{Config, DataSourceConfig};
+use crate::error::AppError;
+use crate::health::CoreSyncStatus;
+use crate::rpc::{Cassette, RpcClient};
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// The Dash Core queries the server depends on. Results keep the shape of
+/// the corresponding RPC responses so every source is interchangeable.
+#[async_trait]
+pub trait CoreDataSource: Send + Sync {
+    /// `quorum listextended`, at the chain tip or at `height`.
+    async fn list_quorums(&self, height: Option<u32>) -> Result<Value, AppError>;
+
+    /// `quorum info` for one quorum. Never includes the node's secret key share.
+    async fn quorum_info(&self, llmq_type: u32, quorum_hash: &str) -> Result<Value, AppError>;
+
+    /// `getblockcount`
+    async fn block_count(&self) -> Result<u32, AppError>;
+
+    /// `masternode list`, keyed by collateral outpoint.
+    async fn masternode_list(&self) -> Result<Value, AppError>;
+
+    /// `protx list evo true <height>`: the detailed Evo masternode list at `height`.
+    async fn masternode_list_at(&self, height: u32) -> Result<Value, AppError>;
+
+    /// `protx listdiff`: masternode list changes from `base_height` to `height`.
+    async fn masternode_list_diff(&self, base_height: u32, height: u32) -> Result<Value, AppError>;
+
+    /// `getblockhash`
+    async fn block_hash(&self, height: u32) -> Result<String, AppError>;
+
+    /// `getblockheader`, as JSON.
+    async fn block_header(&self, block_hash: &str) -> Result<Value, AppError>;
+
+    /// `getbestchainlock`: the most recent ChainLock the node knows about.
+    async fn best_chainlock(&self) -> Result<Value, AppError>;
+
+    /// Sync state of the node behind this source.
+    async fn sync_status(&self) -> Result<CoreSyncStatus, AppError>;
+}
+
+pub type SharedDataSource = Arc<dyn CoreDataSource>;
+
+/// Build the data source selected by `config.source`.
+pub fn from_config(config: &Config) -> Result<SharedDataSource, AppError> {
+    Ok(match &config.source {
+        DataSourceConfig::Rpc => Arc::new(RpcDataSource::new(config.clone())),
+        DataSourceConfig::Fixture { path } => Arc::new(FixtureDataSource::load(path)?),
+        DataSourceConfig::Http { url } => Arc::new(HttpDataSource::new(url)?),
+    })
+}
+
+/// Something that answers raw Dash Core RPC calls. Every implementation
+/// gets `CoreDataSource` for free by mapping each query onto its RPC.
+#[async_trait]
+pub trait RpcTransport: Send + Sync {
+    async fn call(&self, method: &str, params: Vec<Value>) -> Result<Value, AppError>;
+}
+
+#[async_trait]
+impl<T: RpcTransport> CoreDataSource for T {
+    async fn list_quorums(&self, height: Option<u32>) -> Result<Value, AppError> {
+        let mut params = vec![json!("listextended")];
+        if let Some(height) = height {
+            params.push(json!(height));
+        }
+        self.call("quorum", params).await
+    }
+
+    async fn quorum_info(&self, llmq_type: u32, quorum_hash: &str) -> Result<Value, AppError> {
+        self.call("quorum", vec![json!("info"), json!(llmq_type), json!(quorum_hash)]).await
+    }
+
+    async fn block_count(&self) -> Result<u32, AppError> {
+        let result = self.call("getblockcount", Vec::new()).await?;
+        result
+            .as_u64()
+            .map(|height| height as u32)
+            .ok_or_else(|| AppError::Upstream("Invalid block count response".to_string()))
+    }
+
+    async fn masternode_list(&self) -> Result<Value, AppError> {
+        self.call("masternode", vec![json!("list")]).await
+    }
+
+    async fn masternode_list_at(&self, height: u32) -> Result<Value, AppError> {
+        self.call("protx", vec![json!("list"), json!("evo"), json!(true), json!(height)]).await
+    }
+
+    async fn masternode_list_diff(&self, base_height: u32, height: u32) -> Result<Value, AppError> {
+        self.call("protx", vec![json!("listdiff"), json!(base_height), json!(height)]).await
+    }
+
+    async fn block_hash(&self, height: u32) -> Result<String, AppError> {
+        let result = self.call("getblockhash", vec![json!(height)]).await?;
+        result
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| AppError::Upstream("Invalid block hash response".to_string()))
+    }
+
+    async fn block_header(&self, block_hash: &str) -> Result<Value, AppError> {
+        self.call("getblockheader", vec![json!(block_hash)]).await
+    }
+
+    async fn best_chainlock(&self) -> Result<Value, AppError> {
+        self.call("getbestchainlock", Vec::new()).await
+    }
+
+    async fn sync_status(&self) -> Result<CoreSyncStatus, AppError> {
+        let chain_info = self.call("getblockchaininfo", Vec::new()).await?;
+        let mnsync = self.call("mnsync", vec![json!("status")]).await?;
+        Ok(CoreSyncStatus::from_rpc(&chain_info, &mnsync))
+    }
+}
+
+/// Dash Core over JSON-RPC, honouring `rpc.mode` (live, record or replay).
+pub struct RpcDataSource {
+    config: Arc<Config>,
+}
+
+impl RpcDataSource {
+    pub fn new(config: Config) -> Self {
+        Self { config: Arc::new(config) }
+    }
+}
+
+#[async_trait]
+impl RpcTransport for RpcDataSource {
+    async fn call(&self, method: &str, params: Vec<Value>) -> Result<Value, AppError> {
+        let config = self.config.clone();
+        let method = method.to_string();
+        // The RPC client is blocking; keep it off the async workers
+        tokio::task::spawn_blocking(move || RpcClient::new(&config)?.call(&method, &params)).await?
+    }
+}
+
+/// Answers from a JSON file of recorded RPC exchanges, such as an RPC
+/// cassette or one of the fixtures in `tests/fixtures/rpc/`.
+pub struct FixtureDataSource {
+    cassette: Cassette,
+}
+
+impl FixtureDataSource {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, AppError> {
+        Ok(Self { cassette: Cassette::load(path.as_ref())? })
+    }
+}
+
+#[async_trait]
+impl RpcTransport for FixtureDataSource {
+    async fn call(&self, method: &str, params: Vec<Value>) -> Result<Value, AppError> {
+        self.cassette.replay(method, &params)?.into_result()
+    }
+}
+
+/// Another quorum-list-server's `/core` routes, for chaining servers so
+/// that only one of them talks to Dash Core.
+pub struct HttpDataSource {
+    base_url: String,
+    client: reqwest::Client,
+}
+
+/// The `ApiResponse` envelope as seen from the client side.
+#[derive(Deserialize)]
+struct UpstreamResponse<T> {
+    data: Option<T>,
+    message: Option<String>,
+}
+
+impl HttpDataSource {
+    /// `base_url` is the upstream's network root, e.g. `http://upstream:8080/testnet`.
+    pub fn new(base_url: &str) -> Result<Self, AppError> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(30))
+            .build()
+            .map_err(|e| AppError::Internal(format!("Failed to build HTTP client: {}", e)))?;
+        Ok(Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            client,
+        })
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T, AppError> {
+        let url = format!("{}{}", self.base_url, path);
+        let response = self.client.get(&url).send().await.map_err(|e| {
+            if e.is_timeout() {
+                AppError::UpstreamTimeout(format!("{}: {}", url, e))
+            } else {
+                AppError::RpcUnavailable(format!("{}: {}", url, e))
+            }
+        })?;
+
+        let status = response.status();
+        let body: UpstreamResponse<T> = response
+            .json()
+            .await
+            .map_err(|e| AppError::Upstream(format!("Unexpected response from {}: {}", url, e)))?;
+
+        match body.data {
+            Some(data) if status.is_success() => Ok(data),
+            _ => {
+                let message = body.message.unwrap_or_else(|| format!("{} returned {}", url, status));
+                Err(match status.as_u16() {
+                    404 => AppError::NotFound(message),
+                    503 => AppError::RpcUnavailable(message),
+                    504 => AppError::UpstreamTimeout(message),
+                    _ => AppError::Upstream(message),
+                })
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl CoreDataSource for HttpDataSource {
+    async fn list_quorums(&self, height: Option<u32>) -> Result<Value, AppError> {
+        match height {
+            Some(height) => self.get(&format!("/core/quorums?height={}", height)).await,
+            None => self.get("/core/quorums").await,
+        }
+    }
+
+    async fn quorum_info(&self, llmq_type: u32, quorum_hash: &str) -> Result<Value, AppError> {
+        self.get(&format!("/core/quorums/{}/{}", llmq_type, quorum_hash)).await
+    }
+
+    async fn block_count(&self) -> Result<u32, AppError> {
+        self.get("/core/blockcount").await
+    }
+
+    async fn masternode_list(&self) -> Result<Value, AppError> {
+        self.get("/core/masternodes").await
+    }
+
+    async fn masternode_list_at(&self, height: u32) -> Result<Value, AppError> {
+        self.get(&format!("/core/masternodes/at/{}", height)).await
+    }
+
+    async fn masternode_list_diff(&self, base_height: u32, height: u32) -> Result<Value, AppError> {
+        self.get(&format!("/core/masternodes/diff?from={}&to={}", base_height, height)).await
+    }
+
+    async fn block_hash(&self, height: u32) -> Result<String, AppError> {
+        self.get(&format!("/core/blockhash/{}", height)).await
+    }
+
+    async fn block_header(&self, block_hash: &str) -> Result<Value, AppError> {
+        self.get(&format!("/core/blockheader/{}", block_hash)).await
+    }
+
+    async fn best_chainlock(&self) -> Result<Value, AppError> {
+        self.get("/core/chainlock").await
+    }
+
+    async fn sync_status(&self) -> Result<CoreSyncStatus, AppError> {
+        self.get("/core/status").await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::{create_router, NetworkState};
+    use crate::masternode_cache::MasternodeCache;
+    use crate::quorum_cache::QuorumCache;
+    use crate::test_support::test_config;
+
+    const QUORUM_1: &str = "000000c1b7c3ae5252091267a736a9bf57001d80e82668b3cb3cd09e2f6a43cb";
+    const MINED_BLOCK_1: &str = "000000c1b7c3ae52520900000000000000000000000000000000000000000000";
+
+    fn testnet_fixture() -> FixtureDataSource {
+        FixtureDataSource::load(
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/rpc/testnet.json"),
+        )
+        .unwrap()
+    }
+
+    /// Serve a network backed by the testnet fixture, and return its base URL.
+    async fn serve_upstream() -> String {
+        let mut config = test_config("http://127.0.0.1:1");
+        config.server.expose_core = true;
+        let source: SharedDataSource = Arc::new(testnet_fixture());
+        let network = Arc::new(NetworkState {
+            name: "testnet".to_string(),
+            quorum_cache: Arc::new(QuorumCache::new(config.clone(), source.clone())),
+            masternode_cache: Arc::new(MasternodeCache::new(config.clone(), source.clone())),
+            config: Arc::new(config),
+            source,
+        });
+        let router = create_router(&[network], "testnet");
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+        format!("http://{}/testnet", addr)
+    }
+
+    #[tokio::test]
+    async fn fixture_source_answers_from_recorded_exchanges() {
+        let source = testnet_fixture();
+
+        assert_eq!(source.block_count().await.unwrap(), 1100050);
+        assert_eq!(source.list_quorums(None).await.unwrap()["llmq_25_67"].as_array().unwrap().len(), 2);
+        let info = source.quorum_info(6, QUORUM_1).await.unwrap();
+        assert_eq!(info["quorumHash"], QUORUM_1);
+        assert!(source.sync_status().await.unwrap().is_synced());
+        assert_eq!(source.best_chainlock().await.unwrap()["height"], 1100050);
+
+        let missing = source.block_hash(1).await.unwrap_err();
+        assert_eq!(missing.code(), "upstream_error");
+    }
+
+    #[tokio::test]
+    async fn http_source_matches_its_upstream() {
+        let upstream = testnet_fixture();
+        let chained = HttpDataSource::new(&serve_upstream().await).unwrap();
+
+        assert_eq!(chained.block_count().await.unwrap(), upstream.block_count().await.unwrap());
+        assert_eq!(chained.list_quorums(None).await.unwrap(), upstream.list_quorums(None).await.unwrap());
+        assert_eq!(
+            chained.list_quorums(Some(1100042)).await.unwrap(),
+            upstream.list_quorums(Some(1100042)).await.unwrap()
+        );
+        assert_eq!(
+            chained.quorum_info(6, QUORUM_1).await.unwrap(),
+            upstream.quorum_info(6, QUORUM_1).await.unwrap()
+        );
+        assert_eq!(chained.masternode_list().await.unwrap(), upstream.masternode_list().await.unwrap());
+        assert_eq!(
+            chained.masternode_list_at(1100042).await.unwrap(),
+            upstream.masternode_list_at(1100042).await.unwrap()
+        );
+        assert_eq!(
+            chained.masternode_list_diff(1100042, 1100050).await.unwrap(),
+            upstream.masternode_list_diff(1100042, 1100050).await.unwrap()
+        );
+        assert_eq!(chained.block_hash(1100042).await.unwrap(), upstream.block_hash(1100042).await.unwrap());
+        assert_eq!(
+            chained.block_header(MINED_BLOCK_1).await.unwrap(),
+            upstream.block_header(MINED_BLOCK_1).await.unwrap()
+        );
+        assert_eq!(chained.best_chainlock().await.unwrap(), upstream.best_chainlock().await.unwrap());
+        assert_eq!(chained.sync_status().await.unwrap().blocks, 1100050);
+
+        // Upstream errors keep their meaning across the hop
+        let error = chained.block_hash(1).await.unwrap_err();
+        assert_eq!(error.code(), "upstream_error");
+    }
+
+    #[tokio::test]
+    async fn http_source_reports_unreachable_upstream() {
+        let chained = HttpDataSource::new("http://127.0.0.1:1/testnet").unwrap();
+        let error = chained.block_count().await.unwrap_err();
+        assert_eq!(error.code(), "rpc_unavailable");
+    }
+}
@@ -0,0 +1,130 @@
+use crate::api::{ApiResponse, NoData};
+use axum::{
+    extract::rejection::JsonRejection,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+
+/// Seconds clients are asked to wait before retrying after an upstream failure.
+const UPSTREAM_RETRY_AFTER_SECS: u64 = 5;
+
+/// Errors surfaced by loaders and handlers. Each variant maps to one HTTP
+/// status and one machine-readable `code` in the response body.
+#[derive(Debug, thiserror::Error)]
+pub enum AppError {
+    /// Dash Core could not be reached at all.
+    #[error("Dash Core RPC unavailable: {0}")]
+    RpcUnavailable(String),
+    /// Dash Core (or another upstream) did not answer in time.
+    #[error("Upstream request timed out: {0}")]
+    UpstreamTimeout(String),
+    /// Dash Core answered, but with an error or a response we can't use.
+    #[error("Upstream error: {0}")]
+    Upstream(String),
+    #[error("{0}")]
+    NotFound(String),
+    #[error("{0}")]
+    BadRequest(String),
+    /// Data failed a consistency or cryptographic check.
+    #[error("Verification failed: {0}")]
+    VerificationFailed(String),
+    /// Cached data is too old to be served and could not be refreshed.
+    #[error("Stale data: {0}")]
+    StaleData(String),
+    #[error("Internal error: {0}")]
+    Internal(String),
+}
+
+impl AppError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            AppError::RpcUnavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            AppError::UpstreamTimeout(_) => StatusCode::GATEWAY_TIMEOUT,
+            AppError::Upstream(_) => StatusCode::BAD_GATEWAY,
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            AppError::VerificationFailed(_) => StatusCode::BAD_GATEWAY,
+            AppError::StaleData(_) => StatusCode::SERVICE_UNAVAILABLE,
+            AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::RpcUnavailable(_) => "rpc_unavailable",
+            AppError::UpstreamTimeout(_) => "upstream_timeout",
+            AppError::Upstream(_) => "upstream_error",
+            AppError::NotFound(_) => "not_found",
+            AppError::BadRequest(_) => "bad_request",
+            AppError::VerificationFailed(_) => "verification_failed",
+            AppError::StaleData(_) => "stale_data",
+            AppError::Internal(_) => "internal_error",
+        }
+    }
+
+    fn retry_after_secs(&self) -> Option<u64> {
+        match self {
+            AppError::RpcUnavailable(_)
+            | AppError::UpstreamTimeout(_)
+            | AppError::Upstream(_)
+            | AppError::StaleData(_) => Some(UPSTREAM_RETRY_AFTER_SECS),
+            _ => None,
+        }
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let body = ApiResponse::<NoData>::error(self.code(), self.to_string());
+        let mut response = (self.status(), body).into_response();
+        if let Some(secs) = self.retry_after_secs() {
+            response.headers_mut().insert(header::RETRY_AFTER, secs.into());
+        }
+        response
+    }
+}
+
+impl From<dashcore_rpc::Error> for AppError {
+    fn from(e: dashcore_rpc::Error) -> Self {
+        use dashcore_rpc::jsonrpc::error::Error as JsonRpcError;
+
+        match e {
+            dashcore_rpc::Error::JsonRpc(JsonRpcError::Transport(transport)) => {
+                let message = transport.to_string();
+                if message.to_lowercase().contains("timed out") {
+                    AppError::UpstreamTimeout(message)
+                } else {
+                    AppError::RpcUnavailable(message)
+                }
+            }
+            dashcore_rpc::Error::JsonRpc(JsonRpcError::Rpc(rpc_error)) => {
+                AppError::Upstream(format!("RPC error {}: {}", rpc_error.code, rpc_error.message))
+            }
+            other => AppError::Upstream(other.to_string()),
+        }
+    }
+}
+
+impl From<hex::FromHexError> for AppError {
+    fn from(e: hex::FromHexError) -> Self {
+        AppError::Upstream(format!("Invalid hex in upstream response: {}", e))
+    }
+}
+
+impl From<serde_json::Error> for AppError {
+    fn from(e: serde_json::Error) -> Self {
+        AppError::Upstream(format!("Unexpected upstream response: {}", e))
+    }
+}
+
+impl From<JsonRejection> for AppError {
+    fn from(rejection: JsonRejection) -> Self {
+        AppError::BadRequest(format!("Invalid request body: {}", rejection.body_text()))
+    }
+}
+
+impl From<tokio::task::JoinError> for AppError {
+    fn from(e: tokio::task::JoinError) -> Self {
+        AppError::Internal(e.to_string())
+    }
+}
@@ -1,6 +1,6 @@
 use semver::Version;
 use std::time::Duration;
-use tonic::transport::{Channel, ClientTlsConfig};
+use tonic::transport::{Certificate, Channel, ClientTlsConfig};
 
 #[derive(Debug, Clone)]
 pub struct VersionCheckResult {
@@ -16,14 +16,19 @@ pub mod platform {
 use platform::{platform_client::PlatformClient, get_status_request::GetStatusRequestV0};
 use platform::GetStatusRequest;
 
-pub async fn check_node_version(address: &str, port: u16) -> Result<VersionCheckResult, Box<dyn std::error::Error + Send + Sync>> {
+/// Ask the node's Platform `getStatus` for its software versions.
+/// `extra_root` is trusted in addition to the system roots.
+pub async fn check_node_version(address: &str, port: u16, extra_root: Option<&Certificate>) -> Result<VersionCheckResult, Box<dyn std::error::Error + Send + Sync>> {
     // Parse the address and create the endpoint
     let endpoint = format!("https://{}:{}", address, port);
     
     // Create a channel with TLS configuration
-    let tls = ClientTlsConfig::new()
+    let mut tls = ClientTlsConfig::new()
         .with_native_roots()
         .assume_http2(true);
+    if let Some(root) = extra_root {
+        tls = tls.ca_certificate(root.clone());
+    }
     
     let channel = Channel::from_shared(endpoint)?
         .tls_config(tls)?
@@ -129,4 +134,84 @@ fn is_version_2_or_higher(version_str: &str) -> bool {
     
     // If semver parsing fails, try a simple check
     cleaned_version.starts_with("2.") || cleaned_version.starts_with("3.") || cleaned_version.starts_with("4.")
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::mock_platform::{MockPlatformNode, StatusReply, TestCertificate};
+    use tonic::Code;
+
+    /// Run one version check against a fresh mock node on 127.0.0.1.
+    async fn check_mock_node(
+        reply: StatusReply,
+        delay: Duration,
+        trust_certificate: bool,
+    ) -> Result<VersionCheckResult, Box<dyn std::error::Error + Send + Sync>> {
+        let certificate = TestCertificate::self_signed(&["127.0.0.1"]);
+        let node = MockPlatformNode::start("127.0.0.1:0", &certificate, reply, delay).await;
+        let root = certificate.as_root();
+        check_node_version("127.0.0.1", node.addr.port(), trust_certificate.then_some(&root)).await
+    }
+
+    #[tokio::test]
+    async fn version_2_node_passes() {
+        let reply = StatusReply::versions("2.0.1", Some("2.0.1"), Some("1.4.0"));
+        let result = check_mock_node(reply, Duration::ZERO, true).await.unwrap();
+        assert!(result.success);
+        assert_eq!(result.dapi_version.as_deref(), Some("2.0.1"));
+        assert_eq!(result.drive_version.as_deref(), Some("2.0.1"));
+    }
+
+    #[tokio::test]
+    async fn old_node_fails_but_reports_versions() {
+        let reply = StatusReply::versions("1.8.0", Some("1.8.0"), Some("0.13.4"));
+        let result = check_mock_node(reply, Duration::ZERO, true).await.unwrap();
+        assert!(!result.success);
+        assert_eq!(result.dapi_version.as_deref(), Some("1.8.0"));
+        assert_eq!(result.drive_version.as_deref(), Some("1.8.0"));
+    }
+
+    #[tokio::test]
+    async fn slow_node_times_out() {
+        let reply = StatusReply::versions("2.0.1", Some("2.0.1"), None);
+        let started = std::time::Instant::now();
+        assert!(check_mock_node(reply, Duration::from_secs(5), true).await.is_err());
+        assert!(started.elapsed() < Duration::from_secs(4));
+    }
+
+    #[tokio::test]
+    async fn untrusted_certificate_is_rejected() {
+        let reply = StatusReply::versions("2.0.1", Some("2.0.1"), None);
+        assert!(check_mock_node(reply, Duration::ZERO, false).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn response_without_versions_fails() {
+        let result = check_mock_node(StatusReply::Empty, Duration::ZERO, true).await.unwrap();
+        assert!(!result.success);
+        assert_eq!(result.dapi_version, None);
+        assert_eq!(result.drive_version, None);
+    }
+
+    #[tokio::test]
+    async fn malformed_response_is_an_error() {
+        assert!(check_mock_node(StatusReply::Malformed, Duration::ZERO, true).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn grpc_error_is_returned() {
+        let reply = StatusReply::Error(Code::Unavailable);
+        assert!(check_mock_node(reply, Duration::ZERO, true).await.is_err());
+    }
+
+    #[test]
+    fn version_parsing() {
+        assert!(is_version_2_or_higher("2.0.0"));
+        assert!(is_version_2_or_higher("v2.1.0-dev.3"));
+        assert!(is_version_2_or_higher("10.0.0"));
+        assert!(!is_version_2_or_higher("1.8.1"));
+        assert!(!is_version_2_or_higher("0.25.21"));
+        assert!(!is_version_2_or_higher("garbage"));
+    }
+}
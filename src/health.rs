@@ -0,0 +1,131 @@
+use crate::config::Config;
+use crate::data_source::CoreDataSource;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use std::time::Instant;
+
+/// Sync state of the Dash Core node, from `getblockchaininfo` and `mnsync status`.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CoreSyncStatus {
+    pub chain: String,
+    pub blocks: u32,
+    pub headers: u32,
+    pub verification_progress: f64,
+    pub initial_block_download: bool,
+    pub blockchain_synced: bool,
+    pub masternode_sync_asset: String,
+    pub masternode_sync_finished: bool,
+    /// Quorums still sign with the legacy BLS scheme: the node lists the v19
+    /// deployment as not yet active.
+    #[serde(default)]
+    pub bls_legacy_scheme: bool,
+}
+
+impl CoreSyncStatus {
+    /// Build from the results of `getblockchaininfo` and `mnsync status`.
+    pub fn from_rpc(chain_info: &serde_json::Value, mnsync: &serde_json::Value) -> Self {
+        Self {
+            chain: chain_info.get("chain").and_then(|v| v.as_str()).unwrap_or_default().to_string(),
+            blocks: chain_info.get("blocks").and_then(|v| v.as_u64()).unwrap_or(0) as u32,
+            headers: chain_info.get("headers").and_then(|v| v.as_u64()).unwrap_or(0) as u32,
+            verification_progress: chain_info.get("verificationprogress").and_then(|v| v.as_f64()).unwrap_or(0.0),
+            initial_block_download: chain_info.get("initialblockdownload").and_then(|v| v.as_bool()).unwrap_or(true),
+            blockchain_synced: mnsync.get("IsBlockchainSynced").and_then(|v| v.as_bool()).unwrap_or(false),
+            masternode_sync_asset: mnsync.get("AssetName").and_then(|v| v.as_str()).unwrap_or_default().to_string(),
+            masternode_sync_finished: mnsync.get("IsSynced").and_then(|v| v.as_bool()).unwrap_or(false),
+            // Releases that no longer list v19 activated it long ago
+            bls_legacy_scheme: chain_info.pointer("/softforks/v19/active").and_then(|v| v.as_bool()) == Some(false),
+        }
+    }
+
+    pub fn is_synced(&self) -> bool {
+        !self.initial_block_download && self.blockchain_synced && self.masternode_sync_finished
+    }
+}
+
+/// How old the last successful refresh of some cached data is.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct FreshnessCheck {
+    pub last_refresh_age_secs: Option<u64>,
+    pub max_age_secs: u64,
+    pub fresh: bool,
+}
+
+impl FreshnessCheck {
+    pub fn new(last_refresh: Option<Instant>, max_age_secs: u64) -> Self {
+        let last_refresh_age_secs = last_refresh.map(|at| at.elapsed().as_secs());
+        Self {
+            last_refresh_age_secs,
+            max_age_secs,
+            fresh: matches!(last_refresh_age_secs, Some(age) if age <= max_age_secs),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ReadinessReport {
+    pub ready: bool,
+    pub rpc_reachable: bool,
+    pub core: Option<CoreSyncStatus>,
+    pub quorum_count: usize,
+    pub quorums: FreshnessCheck,
+    pub masternodes: FreshnessCheck,
+    pub problems: Vec<String>,
+}
+
+/// Check everything a load balancer cares about before routing traffic here:
+/// Dash Core is reachable and synced, and both caches were refreshed recently.
+pub async fn check_readiness(
+    config: &Config,
+    source: &dyn CoreDataSource,
+    quorum_count: usize,
+    quorums_refreshed_at: Option<Instant>,
+    masternodes_refreshed_at: Option<Instant>,
+) -> ReadinessReport {
+    let mut problems = Vec::new();
+
+    let core = match source.sync_status().await {
+        Ok(status) => Some(status),
+        Err(e) => {
+            problems.push(format!("{} unreachable: {}", config.source, e));
+            None
+        }
+    };
+    if let Some(status) = &core {
+        if !status.is_synced() {
+            problems.push(format!(
+                "Dash Core is not synced (blocks {}/{}, mnsync {})",
+                status.blocks, status.headers, status.masternode_sync_asset
+            ));
+        }
+    }
+
+    let quorums = FreshnessCheck::new(quorums_refreshed_at, config.health.max_quorum_age_secs);
+    if !quorums.fresh {
+        problems.push(match quorums.last_refresh_age_secs {
+            Some(age) => format!("Quorum list is stale ({}s since last refresh)", age),
+            None => "Quorum list has never been loaded".to_string(),
+        });
+    }
+    if quorum_count == 0 {
+        problems.push("Quorum list is empty".to_string());
+    }
+
+    let masternodes = FreshnessCheck::new(masternodes_refreshed_at, config.health.max_masternode_age_secs);
+    if !masternodes.fresh {
+        problems.push(match masternodes.last_refresh_age_secs {
+            Some(age) => format!("Masternode cache is stale ({}s since last refresh)", age),
+            None => "Masternode cache has never been populated".to_string(),
+        });
+    }
+
+    ReadinessReport {
+        ready: problems.is_empty(),
+        rpc_reachable: core.is_some(),
+        core,
+        quorum_count,
+        quorums,
+        masternodes,
+        problems,
+    }
+}
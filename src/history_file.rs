@@ -0,0 +1,29 @@
+//! Histories saved as JSON files, so they survive restarts.
+
+use crate::error::AppError;
+use serde::de::DeserializeOwned;
+use std::path::{Path, PathBuf};
+
+/// Read the `name` history saved at `path`. A missing file is an empty history.
+pub fn load<T: DeserializeOwned + Default>(path: &Path, name: &str) -> Result<T, AppError> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(T::default()),
+        Err(e) => return Err(AppError::Internal(format!("Failed to read {} {}: {}", name, path.display(), e))),
+    };
+    serde_json::from_str(&content).map_err(|e| AppError::Internal(format!("Invalid {} {}: {}", name, path.display(), e)))
+}
+
+/// Write the serialized `name` history to `path` on the blocking pool,
+/// replacing the file only once fully written. Callers serialize under their
+/// lock and write after releasing it.
+pub async fn save(path: PathBuf, content: String, name: &'static str) -> Result<(), AppError> {
+    tokio::task::spawn_blocking(move || {
+        let partial = path.with_extension("partial");
+        std::fs::write(&partial, content)
+            .and_then(|_| std::fs::rename(&partial, &path))
+            .map_err(|e| AppError::Internal(format!("Failed to write {} {}: {}", name, path.display(), e)))
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Failed to write {}: {}", name, e)))?
+}
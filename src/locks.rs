@@ -0,0 +1,252 @@
+//! Verification of ChainLocks (`clsig`) and deterministic InstantSend locks
+//! (`isdlock`) against the stored quorums of the LLMQ types that sign them.
+
+use crate::bls::{self, Hash, SIGN_HEIGHT_OFFSET};
+use crate::config::LlmqParams;
+use crate::error::AppError;
+use crate::quorum_list::{QuorumList, QuorumListEntry};
+use dashcore::consensus::{deserialize, serialize};
+use dashcore::{ChainLock, InstantLock};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use utoipa::ToSchema;
+
+/// Quorums of the LLMQ types signing ChainLocks and InstantSend locks,
+/// loaded at the same block as the platform quorum list.
+#[derive(Debug, Clone, Default)]
+pub struct LockQuorums {
+    pub chainlock: QuorumList,
+    pub instantsend: QuorumList,
+}
+
+/// The outcome of checking a lock. A lock that is well formed but doesn't
+/// verify is not an error; `reason` says why it failed.
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct LockVerification {
+    pub verified: bool,
+    pub llmq_type: u32,
+    /// Hex encoded hash of the quorum the signature was checked against, if one could be selected.
+    pub quorum_hash: Option<String>,
+    /// Hex encoded request id of the lock.
+    pub request_id: String,
+    /// Hex encoded hash the quorum signs for the lock, if a quorum could be selected.
+    pub sign_hash: Option<String>,
+    pub reason: Option<String>,
+}
+
+/// The height of the quorum list a serialized `clsig` message is signed by:
+/// [`SIGN_HEIGHT_OFFSET`] blocks below the locked block.
+pub fn chainlock_sign_height(message: &[u8]) -> Result<u32, AppError> {
+    Ok(decode_chainlock(message)?.block_height.saturating_sub(SIGN_HEIGHT_OFFSET))
+}
+
+/// Check a serialized `clsig` message. Its signing quorum is selected among
+/// `quorums` at the locked block's height, so they must have been loaded at
+/// or above its [`chainlock_sign_height`].
+pub fn verify_chainlock(message: &[u8], quorums: &QuorumList, llmq: LlmqParams) -> Result<LockVerification, AppError> {
+    let chain_lock = decode_chainlock(message)?;
+
+    let mut request_id = vec![5];
+    request_id.extend(b"clsig");
+    request_id.extend(chain_lock.block_height.to_le_bytes());
+    let request_id = displayed_hash(&request_id);
+
+    let quorum = bls::signing_quorums(quorums, llmq, chain_lock.block_height)
+        .ok_or_else(|| format!("The ChainLock quorums signing at height {} are no longer stored", chain_lock.block_height))
+        .and_then(|active| {
+            bls::select_signer(active, llmq, &request_id)
+                .ok_or_else(|| format!("No ChainLock quorums signing at height {} are stored", chain_lock.block_height))
+        });
+
+    let block_hash = displayed(serialize(&chain_lock.block_hash));
+    verify_with_quorum(llmq, quorum, request_id, &block_hash, &serialize(&chain_lock.signature))
+}
+
+fn decode_chainlock(message: &[u8]) -> Result<ChainLock, AppError> {
+    deserialize(message).map_err(|e| AppError::BadRequest(format!("Invalid clsig message: {}", e)))
+}
+
+/// Check a serialized `isdlock` message. Its signing quorum is selected by
+/// DIP-24 quorum index among the quorums of the rotation cycle it names.
+pub fn verify_instantlock(
+    message: &[u8],
+    quorums: &QuorumList,
+    llmq: LlmqParams,
+    anchor_height: u32,
+) -> Result<LockVerification, AppError> {
+    let lock: InstantLock =
+        deserialize(message).map_err(|e| AppError::BadRequest(format!("Invalid isdlock message: {}", e)))?;
+
+    let mut request_id = vec![6];
+    request_id.extend(b"islock");
+    request_id.extend(serialize(&lock.inputs));
+    let request_id = displayed_hash(&request_id);
+
+    let cycle_hash = displayed(serialize(&lock.cyclehash));
+    let quorum = select_cycle_quorum(quorums, llmq, &cycle_hash, anchor_height, &request_id);
+
+    let txid = displayed(serialize(&lock.txid));
+    verify_with_quorum(llmq, quorum, request_id, &txid, &serialize(&lock.signature))
+}
+
+/// The quorum of a rotated LLMQ type signing `request_id` for the cycle
+/// starting at `cycle_hash`. Locks of a finished cycle are signed at its last
+/// block, the ones of the current cycle at the tip (`anchor_height`).
+fn select_cycle_quorum<'a>(
+    quorums: &'a QuorumList,
+    llmq: LlmqParams,
+    cycle_hash: &Hash,
+    anchor_height: u32,
+    request_id: &Hash,
+) -> Result<&'a QuorumListEntry, String> {
+    // The first quorum of a cycle is the one created at the cycle's block
+    let cycle = quorums
+        .get_entry(cycle_hash)
+        .filter(|quorum| quorum.quorum_index == 0)
+        .ok_or_else(|| format!("Rotation cycle {} is not stored", hex::encode(cycle_hash)))?;
+    let cycle_end = cycle.height + llmq.dkg_interval;
+    let sign_height = if cycle_end < anchor_height { cycle_end - 1 } else { anchor_height };
+
+    bls::signing_quorums(quorums, llmq, sign_height)
+        .and_then(|active| bls::select_signer(active, llmq, request_id))
+        .ok_or_else(|| format!("The quorum signing at height {} is not stored", sign_height))
+}
+
+fn verify_with_quorum(
+    llmq: LlmqParams,
+    quorum: Result<&QuorumListEntry, String>,
+    request_id: Hash,
+    msg_hash: &Hash,
+    signature: &[u8],
+) -> Result<LockVerification, AppError> {
+    let mut verification = LockVerification {
+        verified: false,
+        llmq_type: llmq.id,
+        quorum_hash: None,
+        request_id: hex::encode(request_id),
+        sign_hash: None,
+        reason: None,
+    };
+    let quorum = match quorum {
+        Ok(quorum) => quorum,
+        Err(reason) => {
+            verification.reason = Some(reason);
+            return Ok(verification);
+        }
+    };
+
+    let quorum_hash: Hash = quorum
+        .quorum_hash
+        .as_slice()
+        .try_into()
+        .map_err(|_| AppError::Internal("Stored quorum hash is not 32 bytes".to_string()))?;
+    let sign_hash = bls::sign_hash(llmq.id as u8, &quorum_hash, &request_id, msg_hash);
+    verification.quorum_hash = Some(hex::encode(quorum_hash));
+    verification.sign_hash = Some(hex::encode(sign_hash));
+
+    match bls::verify_signature(&quorum.key, &sign_hash, signature) {
+        Ok(true) => verification.verified = true,
+        Ok(false) => verification.reason = Some("Signature does not match the quorum's public key".to_string()),
+        Err(AppError::BadRequest(reason)) => verification.reason = Some(reason),
+        Err(e) => return Err(e),
+    }
+    Ok(verification)
+}
+
+/// SHA256d of `data`, in the byte order Dash Core displays hashes in.
+fn displayed_hash(data: &[u8]) -> Hash {
+    displayed(Sha256::digest(Sha256::digest(data)).to_vec())
+}
+
+/// A consensus serialized 32-byte hash, in the byte order Dash Core displays it in.
+fn displayed(mut serialized: Vec<u8>) -> Hash {
+    serialized.reverse();
+    serialized.try_into().expect("hashes serialize to 32 bytes")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Network;
+    use blst::min_pk::SecretKey;
+
+    const TESTNET_CHAINLOCKS: LlmqParams = LlmqParams {
+        name: "llmq_50_60",
+        id: 1,
+        rotated: false,
+        signing_active_quorum_count: 24,
+        dkg_interval: 24,
+    };
+
+    fn quorum(id: u8, height: u32, quorum_index: u32) -> (QuorumListEntry, SecretKey) {
+        let secret = SecretKey::key_gen(&[id; 32], &[]).unwrap();
+        let mut entry = QuorumListEntry::new_extended(
+            vec![id; 32],
+            secret.sk_to_pk().to_bytes().to_vec(),
+            height,
+            Vec::new(),
+            String::new(),
+            0,
+            50,
+        );
+        entry.quorum_index = quorum_index;
+        (entry, secret)
+    }
+
+    fn clsig(height: u32, block_hash: [u8; 32], signature: &[u8]) -> Vec<u8> {
+        let mut message = height.to_le_bytes().to_vec();
+        message.extend(block_hash);
+        message.extend(signature);
+        message
+    }
+
+    #[test]
+    fn chainlocks_verify_with_the_selected_quorum() {
+        assert_eq!(Network::Testnet.chainlock_llmq(), TESTNET_CHAINLOCKS);
+        let quorums: Vec<_> = (1..=3).map(|id| quorum(id, 1000 + u32::from(id) * 24, 0)).collect();
+        let list: QuorumList = quorums.iter().map(|(entry, _)| entry.clone()).collect();
+
+        // Sign as the quorum the verifier is going to select
+        let unsigned = verify_chainlock(&clsig(1200, [7; 32], &[0; 96]), &list, TESTNET_CHAINLOCKS).unwrap();
+        let quorum_hash = unsigned.quorum_hash.unwrap();
+        let secret = &quorums.iter().find(|(entry, _)| hex::encode(&entry.quorum_hash) == quorum_hash).unwrap().1;
+        let sign_hash = bls::parse_hash("sign hash", &unsigned.sign_hash.unwrap()).unwrap();
+        let message = clsig(1200, [7; 32], &bls::sign(secret, &sign_hash));
+
+        let verification = verify_chainlock(&message, &list, TESTNET_CHAINLOCKS).unwrap();
+        assert!(verification.verified, "{:?}", verification.reason);
+        assert_eq!(verification.quorum_hash, Some(quorum_hash));
+
+        // Another block at the same height, or a height below every stored quorum, doesn't verify
+        let mut forged = message.clone();
+        forged[4] ^= 1;
+        let forged = verify_chainlock(&forged, &list, TESTNET_CHAINLOCKS).unwrap();
+        assert!(!forged.verified);
+        assert_eq!(forged.reason.as_deref(), Some("Signature does not match the quorum's public key"));
+        let early = verify_chainlock(&clsig(1030, [7; 32], &[0; 96]), &list, TESTNET_CHAINLOCKS).unwrap();
+        assert_eq!(early.reason.as_deref(), Some("No ChainLock quorums signing at height 1030 are stored"));
+        assert_eq!(chainlock_sign_height(&message).unwrap(), 1192);
+
+        assert!(matches!(
+            verify_chainlock(&message[..100], &list, TESTNET_CHAINLOCKS),
+            Err(AppError::BadRequest(_))
+        ));
+    }
+
+    #[test]
+    fn rotated_quorums_are_selected_by_index() {
+        let llmq = Network::Testnet.instantsend_llmq();
+        // One full cycle at 2880..2912, one quorum per index
+        let quorums: Vec<_> = (0..32).map(|index| quorum(index as u8 + 1, 2880 + index, index)).collect();
+        let list: QuorumList = quorums.iter().map(|(entry, _)| entry.clone()).collect();
+        let cycle_hash = [1; 32];
+
+        for request_byte in [0x00, 0x40, 0x7f, 0xff] {
+            let request_id = [request_byte; 32];
+            let selected = select_cycle_quorum(&list, llmq, &cycle_hash, 3000, &request_id).unwrap();
+            // The 5 bits below the top bit of the request id's last 64 bits
+            assert_eq!(selected.quorum_index, u32::from(request_byte >> 2) & 31);
+        }
+        assert!(select_cycle_quorum(&list, llmq, &[2; 32], 3000, &[0; 32]).is_err());
+    }
+}
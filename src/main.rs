@@ -1,18 +1,42 @@
+mod anchor;
 mod api;
+mod bls;
+mod cached_response;
 mod config;
+mod dapi_addresses;
+mod data_source;
+mod error;
+mod health;
+mod history_file;
+mod locks;
+mod quorum_cache;
+mod quorum_history;
 mod quorum_list;
 mod quorum_loader;
 mod masternode;
 mod masternode_loader;
 mod masternode_cache;
+mod masternode_history;
+mod probe_history;
+mod probe_scheduler;
 mod grpc_client;
+mod response_format;
+mod rpc;
+mod signing;
+mod snapshot;
 
-use api::SharedQuorumList;
-use config::Config;
-use quorum_list::QuorumList;
+#[cfg(test)]
+mod test_support;
+
+use api::{NetworkState, SharedNetworkState};
+use config::{Config, DataSourceConfig};
+use quorum_cache::QuorumCache;
 use masternode_cache::MasternodeCache;
-use std::sync::{Arc, RwLock};
+use std::sync::Arc;
+use std::time::Duration;
 use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -20,71 +44,172 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Load configuration
     let config = Config::load_from_env_or_file("config.toml");
+    let network_configs = config.network_configs().unwrap_or_else(|e| panic!("{}", e));
+    let default_network = config.default_network_name();
+    if !network_configs.iter().any(|(name, _)| *name == default_network) {
+        panic!("Default network '{}' is not one of the configured networks", default_network);
+    }
+
     println!("Configuration loaded:");
     println!("  Server: {}:{}", config.server.host, config.server.port);
-    println!("  RPC: {} (user: {})", config.rpc.url, config.rpc.username);
+    println!("  Default network: {}", default_network);
+    println!("  Shutdown timeout: {}s", config.server.shutdown_timeout_secs);
+
+    // Cancelled once a shutdown signal arrives; stops the HTTP server and every background task
+    let shutdown = CancellationToken::new();
+    let mut background_tasks: Vec<JoinHandle<()>> = Vec::new();
+
+    let mut networks: Vec<SharedNetworkState> = Vec::with_capacity(network_configs.len());
+    for (name, network_config) in network_configs {
+        let (network, tasks) = init_network(name, network_config, &shutdown).await;
+        networks.push(Arc::new(network));
+        background_tasks.extend(tasks);
+    }
+
+    // Start the API server
+    let app = api::create_router(&networks, &default_network);
+    let listener = TcpListener::bind(format!("{}:{}", config.server.host, config.server.port)).await?;
+    
+    println!("API Server starting on {}:{}", config.server.host, config.server.port);
+    
+    let server_shutdown = shutdown.clone();
+    let mut server_handle = tokio::spawn(async move {
+        axum::serve(listener, app)
+            .with_graceful_shutdown(server_shutdown.cancelled_owned())
+            .await
+    });
+
+    // The server only returns on its own if it failed; its error is returned
+    // once the background tasks are drained
+    let server_result = tokio::select! {
+        _ = shutdown_signal() => {
+            println!("\nShutdown signal received...");
+            None
+        }
+        result = &mut server_handle => Some(result),
+    };
+    shutdown.cancel();
+
+    // Give in-flight requests and background tasks a bounded amount of time to finish
+    let drain_timeout = Duration::from_secs(config.server.shutdown_timeout_secs);
+    println!("Draining in-flight requests and background tasks (timeout {:?})...", drain_timeout);
+    let drain = async {
+        if server_result.is_none() {
+            match server_handle.await {
+                Ok(Err(e)) => eprintln!("API server error during shutdown: {}", e),
+                Err(e) => eprintln!("API server task failed during shutdown: {}", e),
+                Ok(Ok(())) => {}
+            }
+        }
+        futures::future::join_all(background_tasks).await;
+        // Whatever was recorded since the last save
+        for network in &networks {
+            network.quorum_cache.save_history().await;
+            network.masternode_cache.save_histories().await;
+        }
+    };
+    if tokio::time::timeout(drain_timeout, drain).await.is_err() {
+        eprintln!("Warning: shutdown did not complete within {:?}, exiting anyway", drain_timeout);
+    }
+
+    println!("Quorum List Server shutting down...");
+    if let Some(result) = server_result {
+        result??;
+    }
+    Ok(())
+}
+
+/// Resolves on Ctrl-C, or on SIGTERM where available (e.g. `docker stop`).
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Error setting Ctrl-C handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Error setting SIGTERM handler")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+}
+
+/// Load the initial state for one network and start its background refresh.
+/// Returns the state together with the handles of the tasks it spawned.
+async fn init_network(
+    name: String,
+    config: Config,
+    shutdown: &CancellationToken,
+) -> (NetworkState, Vec<JoinHandle<()>>) {
+    println!("[{}] Network configuration:", name);
+    match &config.source {
+        DataSourceConfig::Rpc => println!("  RPC: {} (user: {}, mode: {:?})", config.rpc.url, config.rpc.username, config.rpc.mode),
+        DataSourceConfig::Fixture { path } => println!("  Source: fixture {}", path),
+        DataSourceConfig::Http { url } => println!("  Source: upstream {}", url),
+    }
     println!("  Network: {}", config.network);
     println!("  LLMQ Type: {} (ID: {})", config.get_llmq_type(), config.get_llmq_type_id());
-    println!("  DAPI Port: {}", config.get_dapi_port());
+    match config.dapi.port {
+        Some(port) => println!("  DAPI Port: {}", port),
+        None => println!("  DAPI Port: announced by each node (default {})", config.network.dapi_port()),
+    }
     println!("  Previous blocks offset: {}", config.quorum.previous_blocks_offset);
-    
+    println!("  Quorum refresh interval: {}s", config.quorum.refresh_interval_secs);
+
+    let source = data_source::from_config(&config)
+        .unwrap_or_else(|e| panic!("[{}] Failed to set up data source: {}", name, e));
+
     // Load initial quorums from Dash Core
-    println!("Loading initial quorums from Dash Core...");
-    let initial_quorums = match quorum_loader::load_initial_quorums(&config).await {
-        Ok(quorums) => {
-            println!("Successfully loaded {} quorums", quorums.len());
-            quorums
+    let quorum_cache = Arc::new(QuorumCache::new(config.clone(), source.clone()));
+    if let Some(signer) = quorum_cache.signer() {
+        println!("  Signing quorum lists with {} key {}", signer.algorithm(), hex::encode(signer.public_key()));
+    }
+    println!("[{}] Loading initial quorums from Dash Core...", name);
+    match quorum_cache.refresh().await {
+        Ok(count) => {
+            println!("[{}] Successfully loaded {} quorums", name, count);
         }
         Err(e) => {
-            println!("Warning: Failed to load initial quorums: {}. Starting with empty list.", e);
-            QuorumList::new()
+            println!("[{}] Warning: Failed to load initial quorums: {}. Starting with empty list.", name, e);
         }
-    };
-    
-    let shared_quorum_list: SharedQuorumList = Arc::new(RwLock::new(initial_quorums));
-    
+    }
+
     // Create masternode cache
-    let masternode_cache = Arc::new(MasternodeCache::new(config.clone()));
-    
+    let masternode_cache = Arc::new(MasternodeCache::new(config.clone(), source.clone()));
+
     // Populate masternode cache on startup
-    println!("Loading initial masternode list...");
+    println!("[{}] Loading initial masternode list...", name);
     match masternode_cache.get_masternodes().await {
-        Ok(masternodes) => {
-            println!("Successfully loaded {} masternodes into cache", masternodes.len());
+        Ok(snapshot) => {
+            println!("[{}] Successfully loaded {} masternodes into cache", name, snapshot.data.len());
         }
         Err(e) => {
-            eprintln!("Warning: Failed to load initial masternodes: {}. Cache will populate on first request.", e);
+            eprintln!("[{}] Warning: Failed to load initial masternodes: {}. Cache will populate on first request.", name, e);
         }
     }
-    
-    // Start background refresh for masternode cache
-    masternode_cache.clone().start_background_refresh().await;
-    
-    // Start the API server
-    let app = api::create_router(shared_quorum_list.clone(), config.clone(), masternode_cache.clone());
-    let listener = TcpListener::bind(format!("{}:{}", config.server.host, config.server.port)).await?;
-    
-    println!("API Server starting on {}:{}", config.server.host, config.server.port);
-    
-    let _server_handle = tokio::spawn(async move {
-        axum::serve(listener, app)
-            .await
-            .expect("Failed to start API server");
-    });
-    
-    // Set up graceful shutdown
-    let running = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(true));
-    let r = running.clone();
-    ctrlc::set_handler(move || {
-        println!("\nShutdown signal received...");
-        r.store(false, std::sync::atomic::Ordering::SeqCst);
-    }).expect("Error setting Ctrl-C handler");
-
-    // Keep the server running until shutdown signal
-    while running.load(std::sync::atomic::Ordering::SeqCst) {
-        tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
-    }
-    
-    println!("Quorum List Server shutting down...");
-    Ok(())
+
+    // Start background refresh for both caches
+    let refresh_tasks = vec![
+        quorum_cache.clone().start_background_refresh(shutdown.child_token()),
+        masternode_cache.clone().start_background_refresh(shutdown.child_token()),
+    ];
+
+    let network = NetworkState {
+        name,
+        config: Arc::new(config),
+        quorum_cache,
+        masternode_cache,
+        source,
+    };
+    (network, refresh_tasks)
 }
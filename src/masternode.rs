@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet};
+use utoipa::ToSchema;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MasternodeInfo {
@@ -34,18 +36,22 @@ pub struct MasternodeInfo {
     pub pubkey_operator: String,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, ToSchema)]
 pub struct EvoMasternodeInfo {
     #[serde(rename = "proTxHash")]
     pub pro_tx_hash: String,
     pub address: String,
     pub status: String,
     #[serde(rename = "versionCheck")]
+    #[schema(example = "success")]
     pub version_check: String, // "success", "fail", or "pending"
     #[serde(rename = "dapiVersion", skip_serializing_if = "Option::is_none")]
     pub dapi_version: Option<String>,
     #[serde(rename = "driveVersion", skip_serializing_if = "Option::is_none")]
     pub drive_version: Option<String>,
+    /// Port the node announces for DAPI, if any.
+    #[serde(skip)]
+    pub platform_http_port: Option<u16>,
 }
 
 impl From<MasternodeInfo> for Option<EvoMasternodeInfo> {
@@ -65,6 +71,7 @@ impl From<MasternodeInfo> for Option<EvoMasternodeInfo> {
                 version_check,
                 dapi_version: None,
                 drive_version: None,
+                platform_http_port: info.platform_http_port,
             })
         } else {
             None
@@ -73,4 +80,118 @@ impl From<MasternodeInfo> for Option<EvoMasternodeInfo> {
 }
 
 pub type MasternodeList = HashMap<String, MasternodeInfo>;
-pub type EvoMasternodeList = Vec<EvoMasternodeInfo>;
\ No newline at end of file
+pub type EvoMasternodeList = Vec<EvoMasternodeInfo>;
+
+/// A masternode as `protx list` and `protx listdiff` describe it.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeterministicMasternode {
+    #[serde(rename = "type")]
+    pub node_type: String,
+    pub pro_tx_hash: String,
+    pub state: DeterministicMasternodeState,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeterministicMasternodeState {
+    pub service: String,
+    pub registered_height: u32,
+    #[serde(rename = "PoSePenalty")]
+    pub pose_penalty: u32,
+    /// -1 unless the node is PoSe banned.
+    #[serde(rename = "PoSeBanHeight")]
+    pub pose_ban_height: i64,
+    /// -1 unless the node was ever revived from a ban.
+    #[serde(rename = "PoSeRevivedHeight")]
+    pub pose_revived_height: i64,
+    #[serde(rename = "platformNodeID")]
+    pub platform_node_id: Option<String>,
+    #[serde(rename = "platformHTTPPort")]
+    pub platform_http_port: Option<u16>,
+}
+
+/// The changes `protx listdiff` reports between two heights. Updated nodes
+/// map a proTxHash to the state fields that changed.
+#[derive(Debug, Clone, Deserialize)]
+pub struct MasternodeListDiff {
+    #[serde(rename = "blockHeight")]
+    pub block_height: u32,
+    #[serde(rename = "addedMNs")]
+    pub added: Vec<DeterministicMasternode>,
+    #[serde(rename = "removedMNs")]
+    pub removed: Vec<String>,
+    #[serde(rename = "updatedMNs")]
+    pub updated: Vec<HashMap<String, Map<String, Value>>>,
+}
+
+impl MasternodeListDiff {
+    /// Drop the changes to nodes that aren't Evo nodes. `base_evo` holds the
+    /// proTxHashes of the Evo nodes at the base height.
+    pub fn retain_evo(&mut self, base_evo: &HashSet<String>) {
+        self.added.retain(|masternode| masternode.node_type == "Evo");
+        self.removed.retain(|pro_tx_hash| base_evo.contains(pro_tx_hash));
+        self.updated.retain_mut(|update| {
+            update.retain(|pro_tx_hash, _| base_evo.contains(pro_tx_hash));
+            !update.is_empty()
+        });
+    }
+
+    /// The updated nodes, each with the state fields that changed.
+    pub fn updates(&self) -> impl Iterator<Item = MasternodeUpdate> + '_ {
+        self.updated.iter().flatten().map(|(pro_tx_hash, changes)| MasternodeUpdate {
+            pro_tx_hash: pro_tx_hash.clone(),
+            changes: changes.clone(),
+        })
+    }
+}
+
+/// State fields of a masternode that changed, with their new values.
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct MasternodeUpdate {
+    #[serde(rename = "proTxHash")]
+    pub pro_tx_hash: String,
+    /// Keyed by the field names `protx listdiff` uses, e.g. `PoSeBanHeight`.
+    #[schema(value_type = Object)]
+    pub changes: Map<String, Value>,
+}
+
+/// An Evo masternode's registry state at some height.
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct EvoMasternodeState {
+    #[serde(rename = "proTxHash")]
+    pub pro_tx_hash: String,
+    pub address: String,
+    #[schema(example = "ENABLED")]
+    pub status: String, // "ENABLED" or "POSE_BANNED"
+    #[serde(rename = "registeredHeight")]
+    pub registered_height: u32,
+    #[serde(rename = "posePenalty")]
+    pub pose_penalty: u32,
+    #[serde(rename = "poseBanHeight")]
+    pub pose_ban_height: Option<u32>,
+    #[serde(rename = "poseRevivedHeight")]
+    pub pose_revived_height: Option<u32>,
+    #[serde(rename = "platformNodeID", skip_serializing_if = "Option::is_none")]
+    pub platform_node_id: Option<String>,
+    #[serde(rename = "platformHTTPPort", skip_serializing_if = "Option::is_none")]
+    pub platform_http_port: Option<u16>,
+}
+
+impl From<DeterministicMasternode> for EvoMasternodeState {
+    fn from(masternode: DeterministicMasternode) -> Self {
+        let state = masternode.state;
+        let height = |height: i64| u32::try_from(height).ok().filter(|height| *height > 0);
+        Self {
+            pro_tx_hash: masternode.pro_tx_hash,
+            address: state.service,
+            status: if height(state.pose_ban_height).is_some() { "POSE_BANNED" } else { "ENABLED" }.to_string(),
+            registered_height: state.registered_height,
+            pose_penalty: state.pose_penalty,
+            pose_ban_height: height(state.pose_ban_height),
+            pose_revived_height: height(state.pose_revived_height),
+            platform_node_id: state.platform_node_id,
+            platform_http_port: state.platform_http_port,
+        }
+    }
+}
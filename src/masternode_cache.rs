@@ -1,209 +1,736 @@
 use crate::config::Config;
-use crate::masternode::EvoMasternodeList;
+use crate::data_source::SharedDataSource;
+use crate::error::AppError;
+use crate::history_file;
+use crate::masternode::{EvoMasternodeInfo, EvoMasternodeList};
+use crate::masternode_history::MasternodeHistory;
 use crate::masternode_loader;
+use crate::probe_history::{self, ProbeHistory, ProbeOutcome, ProbeResult};
+use crate::probe_scheduler;
 use crate::grpc_client;
-use std::sync::{Arc, RwLock};
+use crate::snapshot::Snapshot;
+use arc_swap::ArcSwapOption;
+use futures::StreamExt;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock, RwLockReadGuard};
 use std::time::{Duration, Instant};
 use tokio::sync::Mutex;
+use tonic::transport::Certificate;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+use tokio_util::task::TaskTracker;
 use chrono::Local;
 
+pub type SharedMasternodeSnapshot = Arc<Snapshot<EvoMasternodeList>>;
+
+/// How often version check results are published while a refresh is running.
+const PUBLISH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A node's version check outcome, DAPI version and Drive version.
+type VersionCheck = (String, Option<String>, Option<String>);
+
 pub struct MasternodeCache {
-    data: Arc<RwLock<Option<EvoMasternodeList>>>,
-    last_update: Arc<Mutex<Option<Instant>>>,
+    data: ArcSwapOption<Snapshot<EvoMasternodeList>>,
+    /// Held while the list is reloaded, so concurrent requests for a stale
+    /// list trigger one reload rather than one each.
+    refresh_lock: Mutex<()>,
+    /// Held by the round of version checks in progress.
+    version_check_lock: Arc<Mutex<()>>,
+    /// Rounds of version checks started by requests. The background refresh
+    /// waits for them before it stops.
+    version_checks: TaskTracker,
+    /// Held while a list is derived from the published one and published.
+    publish_lock: std::sync::Mutex<()>,
+    /// Changes to Evo nodes between refreshes.
+    history: RwLock<MasternodeHistory>,
+    /// Held while the history is extended and saved, so two callers never
+    /// apply the same diff and an older save never lands last.
+    history_record: Mutex<()>,
+    /// Version check results of every refresh, by proTxHash.
+    probes: RwLock<ProbeHistory>,
+    /// Held while the probe history is saved, so an older save never lands last.
+    probe_save: Mutex<()>,
+    /// Cancelled once the background refresh stops. Rounds of version checks
+    /// a request started end then, saving what they recorded.
+    stopping: CancellationToken,
     config: Arc<Config>,
+    source: SharedDataSource,
     update_interval: Duration,
+    /// Extra root certificate for DAPI TLS, from `dapi.ca_cert_path`.
+    dapi_root: Option<Certificate>,
 }
 
 impl MasternodeCache {
-    pub fn new(config: Config) -> Self {
+    pub fn new(config: Config, source: SharedDataSource) -> Self {
+        let dapi_root = config.dapi.ca_cert_path.as_ref().and_then(|path| {
+            match std::fs::read(path) {
+                Ok(pem) => Some(Certificate::from_pem(pem)),
+                Err(e) => {
+                    eprintln!("Warning: Failed to read DAPI CA certificate {}: {}", path, e);
+                    None
+                }
+            }
+        });
+
+        let probes = match &config.dapi.probe_history_path {
+            Some(path) => history_file::load(Path::new(path), "probe history").unwrap_or_else(|e| {
+                eprintln!("Warning: Starting with an empty probe history: {}", e);
+                ProbeHistory::new()
+            }),
+            None => ProbeHistory::new(),
+        };
+
+        let history = match &config.dapi.masternode_history_path {
+            Some(path) => history_file::load(Path::new(path), "masternode history").unwrap_or_else(|e| {
+                eprintln!("Warning: {}, starting a new masternode history", e);
+                MasternodeHistory::new()
+            }),
+            None => MasternodeHistory::new(),
+        };
+
         Self {
-            data: Arc::new(RwLock::new(None)),
-            last_update: Arc::new(Mutex::new(None)),
+            data: ArcSwapOption::empty(),
+            refresh_lock: Mutex::new(()),
+            version_check_lock: Arc::new(Mutex::new(())),
+            version_checks: TaskTracker::new(),
+            publish_lock: std::sync::Mutex::new(()),
+            history: RwLock::new(history),
+            history_record: Mutex::new(()),
+            probes: RwLock::new(probes),
+            probe_save: Mutex::new(()),
+            stopping: CancellationToken::new(),
             config: Arc::new(config),
+            source,
             update_interval: Duration::from_secs(600), // 10 minutes
+            dapi_root,
         }
     }
 
-    pub async fn get_masternodes(&self) -> Result<EvoMasternodeList, Box<dyn std::error::Error + Send + Sync>> {
-        // Check if we need to update the cache
-        let should_update = {
-            let last_update_guard = self.last_update.lock().await;
-            match *last_update_guard {
-                None => true,
-                Some(last) => last.elapsed() >= self.update_interval,
-            }
-        };
+    /// How long a refreshed list is served before it is refreshed again.
+    pub fn update_interval(&self) -> Duration {
+        self.update_interval
+    }
+
+    /// When the cache was last successfully refreshed.
+    pub fn last_update(&self) -> Option<Instant> {
+        self.data.load().as_ref().and_then(|snapshot| snapshot.refreshed_at)
+    }
 
-        if should_update {
-            self.update_cache().await?;
+    /// Changes to Evo nodes recorded since the server started.
+    pub fn history(&self) -> RwLockReadGuard<'_, MasternodeHistory> {
+        self.history.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Recent version check results and uptime of every probed node.
+    pub fn probe_history(&self) -> RwLockReadGuard<'_, ProbeHistory> {
+        self.probes.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// The published list, if it is younger than `update_interval`.
+    fn fresh_snapshot(&self) -> Option<SharedMasternodeSnapshot> {
+        self.data
+            .load_full()
+            .filter(|snapshot| matches!(snapshot.refreshed_at, Some(at) if at.elapsed() < self.update_interval))
+    }
+
+    /// The published list, refreshing it first if it is stale. A refresh
+    /// returns once the new list is published; its version checks continue in
+    /// the background and are published as they come in.
+    pub async fn get_masternodes(self: &Arc<Self>) -> Result<SharedMasternodeSnapshot, AppError> {
+        if let Some(snapshot) = self.fresh_snapshot() {
+            return Ok(snapshot);
         }
 
-        // Return the cached data
-        let cached_data = {
-            let data = self.data.read()
-                .map_err(|_| "Failed to read cache")?;
-            data.clone()
-        };
-        
-        match cached_data {
-            Some(masternodes) => Ok(masternodes),
-            None => {
-                // This shouldn't happen as we just updated, but handle it gracefully
-                self.update_cache().await?;
-                let data = self.data.read()
-                    .map_err(|_| "Failed to read cache")?;
-                Ok(data.as_ref().ok_or("No masternode data available")?.clone())
+        let _refreshing = self.refresh_lock.lock().await;
+        // Another request may have refreshed the list while we waited
+        if let Some(snapshot) = self.fresh_snapshot() {
+            return Ok(snapshot);
+        }
+
+        match self.load().await {
+            Ok(snapshot) => {
+                self.start_version_checks();
+                Ok(snapshot)
+            }
+            Err(e) => {
+                // Keep serving the previous list while it is younger than the readiness threshold
+                let max_age = Duration::from_secs(self.config.health.max_masternode_age_secs);
+                match (self.data.load_full(), self.last_update()) {
+                    (Some(snapshot), Some(last)) if last.elapsed() < max_age => {
+                        eprintln!("Masternode cache refresh failed, serving cached list: {}", e);
+                        Ok(snapshot)
+                    }
+                    (Some(_), Some(last)) => Err(AppError::StaleData(format!(
+                        "Masternode list is {}s old and refresh failed: {}",
+                        last.elapsed().as_secs(),
+                        e
+                    ))),
+                    _ => Err(e),
+                }
             }
         }
     }
 
-    async fn update_cache(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    /// Check the versions of the published list's nodes right away. While a
+    /// round is running, the nodes still `pending` once it ends are checked then.
+    fn start_version_checks(self: &Arc<Self>) {
+        let cache = self.clone();
+        let running = self.version_check_lock.clone().try_lock_owned();
+        self.version_checks.spawn(async move {
+            let (_checking, queued) = match running {
+                Ok(checking) => (checking, false),
+                Err(_) => tokio::select! {
+                    _ = cache.stopping.cancelled() => return,
+                    checking = cache.version_check_lock.clone().lock_owned() => (checking, true),
+                },
+            };
+            let Some(published) = cache.data.load_full() else {
+                return;
+            };
+            let masternodes: EvoMasternodeList =
+                published.data.iter().filter(|node| !queued || node.version_check == "pending").cloned().collect();
+            if !masternodes.is_empty() {
+                cache.check_versions(masternodes, Duration::ZERO, &cache.stopping).await;
+            }
+        });
+    }
+
+    /// Load the list and publish it, nodes keeping their previous version
+    /// check results until they are checked again.
+    async fn load(&self) -> Result<SharedMasternodeSnapshot, AppError> {
         println!("Updating masternode cache...");
+        let refreshed_at = Instant::now();
 
-        // Wrap the entire operation in a timeout (30 seconds)
-        let result = tokio::time::timeout(
-            tokio::time::Duration::from_secs(30),
-            self.update_cache_internal()
-        ).await;
+        // Fetch new data
+        let mut masternodes = masternode_loader::load_masternode_list(self.source.as_ref()).await?;
+        match self.source.block_count().await {
+            Ok(height) => self.record_history(height).await,
+            Err(e) => eprintln!("Can't record masternode history: {}", e),
+        }
 
-        match result {
-            Ok(Ok(())) => Ok(()),
-            Ok(Err(e)) => Err(e),
-            Err(_) => {
-                eprintln!("⚠️  CACHE UPDATE TIMED OUT after 30 seconds - this indicates network issues or too many slow nodes");
-                Err("Cache update timed out after 30 seconds".into())
+        let _publishing = self.publish_lock.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(previous) = self.data.load_full() {
+            let previous: HashMap<&str, &EvoMasternodeInfo> =
+                previous.data.iter().map(|node| (node.pro_tx_hash.as_str(), node)).collect();
+            for node in masternodes.iter_mut().filter(|node| node.status != "POSE_BANNED") {
+                if let Some(previous) = previous.get(node.pro_tx_hash.as_str()) {
+                    node.version_check = previous.version_check.clone();
+                    node.dapi_version = previous.dapi_version.clone();
+                    node.drive_version = previous.drive_version.clone();
+                }
             }
         }
+        Ok(self.publish(masternodes, Some(refreshed_at)))
     }
 
-    async fn update_cache_internal(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        // Fetch new data
-        let mut masternodes = masternode_loader::load_masternode_list(&self.config).await?;
-
+    /// Check the version of every Evo node in `masternodes`, with their starts
+    /// staggered across `spread`. Results are published onto the current list
+    /// as they come in, so readers never wait for the slowest nodes. Once
+    /// `shutdown` is cancelled the remaining checks are dropped; the results so
+    /// far are still published and saved.
+    async fn check_versions(&self, masternodes: EvoMasternodeList, spread: Duration, shutdown: &CancellationToken) {
         println!("Checking version for {} Evo masternodes...", masternodes.len());
-        
-        // Check version for each masternode
-        let check_tasks: Vec<_> = masternodes.iter().enumerate().map(|(idx, node)| {
-            let address = node.address.clone();
-            let status = node.status.clone();
-            let config = self.config.clone();
-
-            async move {
-                let start = std::time::Instant::now();
-
-                // Skip POSE_BANNED nodes
-                if status == "POSE_BANNED" {
-                    println!("⏭️  Node {} at {} - skipping (POSE_BANNED)", idx, address);
-                    return (idx, "fail".to_string(), None, None, start.elapsed());
-                }
 
-                // Parse address to get IP and port, applying localhost replacement if configured
-                let resolved_address = config.replace_localhost(&address);
-                let parts: Vec<&str> = resolved_address.split(':').collect();
-                if parts.len() != 2 {
-                    println!("❌ Node {} at {} - invalid address format", idx, address);
-                    return (idx, "fail".to_string(), None, None, start.elapsed());
+        // Skip POSE_BANNED nodes
+        let targets: Vec<_> = masternodes
+            .iter()
+            .enumerate()
+            .filter(|(idx, node)| {
+                let banned = node.status == "POSE_BANNED";
+                if banned {
+                    println!("⏭️  Node {} at {} - skipping (POSE_BANNED)", idx, node.address);
                 }
+                !banned
+            })
+            .map(|(idx, node)| (idx, node.address.clone(), node.platform_http_port))
+            .collect();
 
-                let ip = parts[0].to_string();
-                let port = config.get_dapi_port();
-
-                println!("🔍 Node {} at {} (resolved: {}:{}) - checking version...", idx, address, ip, port);
-
-                // Check version with additional timeout wrapper (2 seconds total)
-                let result = match tokio::time::timeout(
-                    tokio::time::Duration::from_secs(2),
-                    grpc_client::check_node_version(&ip, port)
-                ).await {
-                    Ok(Ok(result)) => {
-                        let elapsed = start.elapsed();
-                        if result.success {
-                            println!("✓ Node {} at {} - version 2.0+ (DAPI: {:?}, Drive: {:?}) [took {:?}]",
-                                idx, address, result.dapi_version, result.drive_version, elapsed);
-                            (idx, "success".to_string(), result.dapi_version, result.drive_version, elapsed)
-                        } else {
-                            println!("✗ Node {} at {} - version < 2.0 (DAPI: {:?}, Drive: {:?}) [took {:?}]",
-                                idx, address, result.dapi_version, result.drive_version, elapsed);
-                            (idx, "fail".to_string(), result.dapi_version, result.drive_version, elapsed)
-                        }
-                    },
-                    Ok(Err(e)) => {
-                        let elapsed = start.elapsed();
-                        println!("✗ Node {} at {} - error: {} [took {:?}]", idx, address, e, elapsed);
-                        (idx, "fail".to_string(), None, None, elapsed)
-                    },
-                    Err(_) => {
-                        let elapsed = start.elapsed();
-                        println!("⏱️  Node {} at {} - TIMEOUT after {:?} ⚠️  THIS NODE IS SLOW!", idx, address, elapsed);
-                        (idx, "fail".to_string(), None, None, elapsed)
-                    },
-                };
-                result
-            }
-        }).collect();
-        
-        // Execute all version checks concurrently
-        let overall_start = std::time::Instant::now();
-        let results = futures::future::join_all(check_tasks).await;
-        let total_elapsed = overall_start.elapsed();
+        let timeout = Duration::from_millis(self.config.dapi.probe_timeout_ms);
+        let mut results = probe_scheduler::probe_all(
+            targets,
+            self.config.dapi.probe_concurrency,
+            spread,
+            |(idx, address, platform_http_port)| self.check_version(idx, address, platform_http_port, timeout),
+        );
 
         // Track slow nodes
         let mut slow_nodes: Vec<(usize, String, std::time::Duration)> = vec![];
+        let overall_start = std::time::Instant::now();
+        let mut last_publish = Instant::now();
+        let mut unpublished = HashMap::new();
+        let (mut success_count, mut fail_count) = (0, 0);
 
-        // Update the version_check field and version info for each masternode
-        for (idx, version_check, dapi_version, drive_version, elapsed) in results {
-            masternodes[idx].version_check = version_check;
-            masternodes[idx].dapi_version = dapi_version;
-            masternodes[idx].drive_version = drive_version;
+        let interrupted = loop {
+            let result = tokio::select! {
+                biased;
+                _ = shutdown.cancelled() => break true,
+                result = results.next() => result,
+            };
+            let Some((idx, version_check, dapi_version, drive_version, elapsed)) = result else {
+                break false;
+            };
+            let node = &masternodes[idx];
+            self.probes.write().unwrap_or_else(|e| e.into_inner()).record(&node.pro_tx_hash, ProbeResult {
+                timestamp: probe_history::unix_now(),
+                outcome: if version_check == "success" { ProbeOutcome::Success } else { ProbeOutcome::Fail },
+                latency_ms: elapsed.as_millis() as u64,
+                dapi_version: dapi_version.clone(),
+                drive_version: drive_version.clone(),
+            });
+            if version_check == "success" {
+                success_count += 1;
+            } else {
+                fail_count += 1;
+            }
 
-            // Track nodes that took more than 2 seconds
-            if elapsed.as_secs() >= 2 {
-                slow_nodes.push((idx, masternodes[idx].address.clone(), elapsed));
+            // Track nodes that ran into the timeout
+            if elapsed >= timeout {
+                slow_nodes.push((idx, node.address.clone(), elapsed));
             }
-        }
 
-        let success_count = masternodes.iter().filter(|n| n.version_check == "success").count();
-        let fail_count = masternodes.iter().filter(|n| n.version_check == "fail").count();
-        println!("Version check complete: {} success, {} fail (total time: {:?})", success_count, fail_count, total_elapsed);
+            unpublished.insert(node.pro_tx_hash.clone(), (version_check, dapi_version, drive_version));
+            if last_publish.elapsed() >= PUBLISH_INTERVAL {
+                self.publish_version_checks(&std::mem::take(&mut unpublished));
+                last_publish = Instant::now();
+            }
+        };
+        drop(results);
+        self.publish_version_checks(&unpublished);
+        let total_elapsed = overall_start.elapsed();
+        self.save_probe_history().await;
+
+        if interrupted {
+            println!("Version check stopped by shutdown: {} success, {} fail (total time: {:?})", success_count, fail_count, total_elapsed);
+        } else {
+            println!("Version check complete: {} success, {} fail (total time: {:?})", success_count, fail_count, total_elapsed);
+        }
 
         // Report slow nodes
         if !slow_nodes.is_empty() {
-            println!("\n🐌 SLOW NODES DETECTED ({} nodes took >2s):", slow_nodes.len());
+            println!("\n🐌 SLOW NODES DETECTED ({} nodes took >{:?}):", slow_nodes.len(), timeout);
             slow_nodes.sort_by(|a, b| b.2.cmp(&a.2)); // Sort by duration, slowest first
             for (idx, address, duration) in slow_nodes.iter().take(10) {
                 println!("   Node {} at {} took {:?}", idx, address, duration);
             }
             println!();
         }
-        
-        // Update the cache
-        {
-            let mut data = self.data.write()
-                .map_err(|_| "Failed to write to cache")?;
-            *data = Some(masternodes);
+    }
+
+    /// Prune the probe history and save it to `dapi.probe_history_path`, if one is configured.
+    async fn save_probe_history(&self) {
+        let _saving = self.probe_save.lock().await;
+        // Serialized under the lock, written after releasing it
+        let content = {
+            let mut probes = self.probes.write().unwrap_or_else(|e| e.into_inner());
+            probes.prune(probe_history::unix_now());
+            self.config.dapi.probe_history_path.as_ref().map(|path| (path, serde_json::to_string(&*probes)))
+        };
+        match content {
+            Some((path, Ok(content))) => {
+                if let Err(e) = history_file::save(PathBuf::from(path), content, "probe history").await {
+                    eprintln!("Warning: {}", e);
+                }
+            }
+            Some((_, Err(e))) => eprintln!("Warning: Failed to serialize probe history: {}", e),
+            None => {}
+        }
+    }
+
+    /// Apply version check results, by proTxHash, to the published list. It
+    /// is only republished if one of them changed a node.
+    fn publish_version_checks(&self, checks: &HashMap<String, VersionCheck>) {
+        let _publishing = self.publish_lock.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(current) = self.data.load_full() else {
+            return;
+        };
+        let mut masternodes = current.data.clone();
+        let mut changed = false;
+        for node in masternodes.iter_mut().filter(|node| node.status != "POSE_BANNED") {
+            let Some((version_check, dapi_version, drive_version)) = checks.get(&node.pro_tx_hash) else {
+                continue;
+            };
+            if (&node.version_check, &node.dapi_version, &node.drive_version) != (version_check, dapi_version, drive_version) {
+                node.version_check = version_check.clone();
+                node.dapi_version = dapi_version.clone();
+                node.drive_version = drive_version.clone();
+                changed = true;
+            }
         }
-        
-        // Update the timestamp
+        if changed {
+            self.publish(masternodes, current.refreshed_at);
+        }
+    }
+
+    /// Check the DAPI version of the node at `address`, failing it after `timeout`.
+    async fn check_version(
+        &self,
+        idx: usize,
+        address: String,
+        platform_http_port: Option<u16>,
+        timeout: Duration,
+    ) -> (usize, String, Option<String>, Option<String>, Duration) {
+        let start = std::time::Instant::now();
+
+        // Parse address to get IP and port, applying localhost replacement if configured
+        let resolved_address = self.config.replace_localhost(&address);
+        let parts: Vec<&str> = resolved_address.split(':').collect();
+        if parts.len() != 2 {
+            println!("❌ Node {} at {} - invalid address format", idx, address);
+            return (idx, "fail".to_string(), None, None, start.elapsed());
+        }
+
+        let ip = parts[0].to_string();
+        let port = self.config.dapi_port_for(platform_http_port);
+
+        println!("🔍 Node {} at {} (resolved: {}:{}) - checking version...", idx, address, ip, port);
+
+        match tokio::time::timeout(timeout, grpc_client::check_node_version(&ip, port, self.dapi_root.as_ref())).await {
+            Ok(Ok(result)) => {
+                let elapsed = start.elapsed();
+                if result.success {
+                    println!("✓ Node {} at {} - version 2.0+ (DAPI: {:?}, Drive: {:?}) [took {:?}]",
+                        idx, address, result.dapi_version, result.drive_version, elapsed);
+                    (idx, "success".to_string(), result.dapi_version, result.drive_version, elapsed)
+                } else {
+                    println!("✗ Node {} at {} - version < 2.0 (DAPI: {:?}, Drive: {:?}) [took {:?}]",
+                        idx, address, result.dapi_version, result.drive_version, elapsed);
+                    (idx, "fail".to_string(), result.dapi_version, result.drive_version, elapsed)
+                }
+            },
+            Ok(Err(e)) => {
+                let elapsed = start.elapsed();
+                println!("✗ Node {} at {} - error: {} [took {:?}]", idx, address, e, elapsed);
+                (idx, "fail".to_string(), None, None, elapsed)
+            },
+            Err(_) => {
+                let elapsed = start.elapsed();
+                println!("⏱️  Node {} at {} - TIMEOUT after {:?} ⚠️  THIS NODE IS SLOW!", idx, address, elapsed);
+                (idx, "fail".to_string(), None, None, elapsed)
+            },
+        }
+    }
+
+    /// Publish `masternodes` as the list loaded at `refreshed_at`. Callers
+    /// hold `publish_lock` from reading the list they derive it from.
+    fn publish(&self, masternodes: EvoMasternodeList, refreshed_at: Option<Instant>) -> SharedMasternodeSnapshot {
+        let snapshot = Arc::new(match self.data.load().as_deref() {
+            Some(previous) => Snapshot::succeeding(previous, masternodes, refreshed_at),
+            None => Snapshot::new(masternodes, refreshed_at),
+        });
+        self.data.store(Some(snapshot.clone()));
+        snapshot
+    }
+
+    /// Add the changes to Evo nodes up to `height` to the history, then save
+    /// it. The first call only notes the nodes registered at `height` to diff
+    /// later ones against. Safe to call concurrently.
+    pub async fn record_history(&self, height: u32) {
+        let _recording = self.history_record.lock().await;
+        let source = self.source.as_ref();
+        let recorded_height = self.history().height();
+        match recorded_height {
+            None => match masternode_loader::load_masternode_list_at(source, height).await {
+                Ok(masternodes) => self.history.write().unwrap_or_else(|e| e.into_inner()).start(height, &masternodes),
+                Err(e) => return eprintln!("Can't load the masternode list at height {}: {}", height, e),
+            },
+            Some(base_height) if base_height < height => {
+                match masternode_loader::load_masternode_diff(source, base_height, height).await {
+                    Ok(diff) => self.history.write().unwrap_or_else(|e| e.into_inner()).apply(diff),
+                    Err(e) => return eprintln!("Can't load the masternode list diff {}..{}: {}", base_height, height, e),
+                }
+            }
+            // No new blocks, or the tip moved back in a reorg
+            Some(_) => return,
+        }
+        self.save_masternode_history().await;
+    }
+
+    /// Save the history to `dapi.masternode_history_path`, if one is
+    /// configured. Callers hold `history_record`.
+    async fn save_masternode_history(&self) {
+        let Some(path) = &self.config.dapi.masternode_history_path else {
+            return;
+        };
+        let content = serde_json::to_string(&*self.history());
+        match content {
+            Ok(content) => {
+                if let Err(e) = history_file::save(PathBuf::from(path), content, "masternode history").await {
+                    eprintln!("Warning: {}", e);
+                }
+            }
+            Err(e) => eprintln!("Warning: Failed to serialize masternode history: {}", e),
+        }
+    }
+
+    /// Save the masternode and probe histories, for shutdown.
+    pub async fn save_histories(&self) {
         {
-            let mut last_update = self.last_update.lock().await;
-            *last_update = Some(Instant::now());
+            let _recording = self.history_record.lock().await;
+            self.save_masternode_history().await;
         }
-        
-        println!("Masternode cache updated successfully");
-        Ok(())
+        self.save_probe_history().await;
     }
 
-    pub async fn start_background_refresh(self: Arc<Self>) {
+    /// Refresh the cache every `update_interval` until `shutdown` is cancelled,
+    /// spreading each refresh's version checks over `dapi.probe_spread_secs`.
+    /// A list being loaded when shutdown starts is abandoned; version checks,
+    /// including those requests started, stop early, saving what they
+    /// recorded, and the task ends once they have.
+    pub fn start_background_refresh(self: Arc<Self>, shutdown: CancellationToken) -> JoinHandle<()> {
         tokio::spawn(async move {
+            let spread = Duration::from_secs(self.config.dapi.probe_spread_secs).min(self.update_interval);
+            let mut next_refresh = tokio::time::Instant::now() + self.update_interval;
             loop {
-                tokio::time::sleep(self.update_interval).await;
+                tokio::select! {
+                    _ = shutdown.cancelled() => break,
+                    _ = tokio::time::sleep_until(next_refresh) => {}
+                }
+                // Counted from the start of a refresh, which the published list is dated by
+                next_refresh = tokio::time::Instant::now() + self.update_interval;
                 let now = Local::now();
                 println!("🔄 [{}] Background refresh: Starting masternode cache update...", now.format("%Y-%m-%d %H:%M:%S"));
-                match self.update_cache().await {
-                    Ok(_) => println!("✅ [{}] Background refresh: Masternode cache updated successfully", Local::now().format("%Y-%m-%d %H:%M:%S")),
+                let loaded = tokio::select! {
+                    _ = shutdown.cancelled() => break,
+                    loaded = async {
+                        let _refreshing = self.refresh_lock.lock().await;
+                        self.load().await
+                    } => loaded,
+                };
+                match loaded {
+                    Ok(loaded) => {
+                        let _checking = self.version_check_lock.lock().await;
+                        self.check_versions(loaded.data.clone(), spread, &shutdown).await;
+                        println!("✅ [{}] Background refresh: Masternode cache updated successfully", Local::now().format("%Y-%m-%d %H:%M:%S"));
+                    }
                     Err(e) => eprintln!("❌ [{}] Background refresh: Failed to update masternode cache: {}", Local::now().format("%Y-%m-%d %H:%M:%S"), e),
                 }
             }
+            self.stopping.cancel();
+            self.version_checks.close();
+            self.version_checks.wait().await;
+            println!("Background refresh: masternode cache refresh stopped");
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_source::RpcDataSource;
+    use crate::test_support::mock_platform::{MockPlatformNode, StatusReply, TestCertificate};
+    use crate::test_support::mock_rpc::{Exchange, MockRpcServer};
+    use crate::test_support::test_config;
+    use serde_json::json;
+    use std::collections::HashMap;
+    use tonic::Code;
+
+    fn evo_node(pro_tx_hash: &str, platform_http_port: u16, status: &str) -> serde_json::Value {
+        json!({
+            "proTxHash": pro_tx_hash,
+            "address": "127.0.0.1:19999",
+            "payee": "yPayee",
+            "status": status,
+            "type": "Evo",
+            "platformNodeID": "0000000000000000000000000000000000000001",
+            "platformP2PPort": 36656,
+            "platformHTTPPort": platform_http_port,
+            "pospenaltyscore": 0,
+            "consecutivePayments": 0,
+            "lastpaidtime": 0,
+            "lastpaidblock": 0,
+            "owneraddress": "yOwner",
+            "votingaddress": "yVoting",
+            "collateraladdress": "yCollateral",
+            "pubkeyoperator": "00",
+        })
+    }
+
+    #[tokio::test]
+    async fn version_checks_record_per_node_results() {
+        // Every mock node listens on its own port of 127.0.0.1, which it
+        // announces as its platformHTTPPort
+        let trusted = TestCertificate::self_signed(&["127.0.0.1"]);
+        let untrusted = TestCertificate::self_signed(&["127.0.0.1"]);
+        let nodes = [
+            ("current", &trusted, StatusReply::versions("2.0.1", Some("2.0.1"), None), Duration::ZERO),
+            ("old", &trusted, StatusReply::versions("1.8.0", Some("1.8.0"), None), Duration::ZERO),
+            ("slow", &trusted, StatusReply::versions("2.0.1", Some("2.0.1"), None), Duration::from_secs(5)),
+            ("untrusted", &untrusted, StatusReply::versions("2.0.1", Some("2.0.1"), None), Duration::ZERO),
+            ("empty", &trusted, StatusReply::Empty, Duration::ZERO),
+            ("malformed", &trusted, StatusReply::Malformed, Duration::ZERO),
+            ("erroring", &trusted, StatusReply::Error(Code::Unavailable), Duration::ZERO),
+        ];
+        let mut servers = Vec::new();
+        let mut masternode_list = serde_json::Map::new();
+        for (pro_tx_hash, certificate, reply, delay) in nodes {
+            let server = MockPlatformNode::start("127.0.0.1:0", certificate, reply, delay).await;
+            masternode_list.insert(format!("{}-0", pro_tx_hash), evo_node(pro_tx_hash, server.addr.port(), "ENABLED"));
+            servers.push(server);
+        }
+        let port = servers[0].addr.port();
+        masternode_list.insert("banned-0".to_string(), evo_node("banned", port, "POSE_BANNED"));
+
+        let rpc = MockRpcServer::start(vec![Exchange {
+            method: "masternode".to_string(),
+            params: vec![json!("list")],
+            result: serde_json::Value::Object(masternode_list),
+            error: None,
+        }]);
+
+        let ca_cert_path = std::env::temp_dir().join(format!("qls-test-ca-{}.pem", port));
+        std::fs::write(&ca_cert_path, &trusted.cert_pem).unwrap();
+        let mut config = test_config(&rpc.url);
+        config.dapi.ca_cert_path = Some(ca_cert_path.to_string_lossy().to_string());
+        let probe_history_path = std::env::temp_dir().join(format!("qls-test-probes-{}.json", port));
+        config.dapi.probe_history_path = Some(probe_history_path.to_string_lossy().to_string());
+        config.dapi.probe_concurrency = 2;
+        config.dapi.probe_timeout_ms = 1000;
+
+        let source = Arc::new(RpcDataSource::new(config.clone()));
+        let cache = Arc::new(MasternodeCache::new(config.clone(), source.clone()));
+        let published = cache.get_masternodes().await.unwrap();
+        // The list is published before any node is checked
+        assert!(published.data.iter().all(|node| node.status == "POSE_BANNED" || node.version_check == "pending"));
+        drop(cache.version_check_lock.lock().await);
+        std::fs::remove_file(&ca_cert_path).ok();
+        let masternodes = &cache.get_masternodes().await.unwrap().data;
+
+        let results: HashMap<&str, (&str, Option<&str>)> = masternodes
+            .iter()
+            .map(|node| (node.pro_tx_hash.as_str(), (node.version_check.as_str(), node.dapi_version.as_deref())))
+            .collect();
+        assert_eq!(
+            results,
+            HashMap::from([
+                ("current", ("success", Some("2.0.1"))),
+                ("old", ("fail", Some("1.8.0"))),
+                ("slow", ("fail", None)),
+                ("untrusted", ("fail", None)),
+                ("empty", ("fail", None)),
+                ("malformed", ("fail", None)),
+                ("erroring", ("fail", None)),
+                ("banned", ("fail", None)),
+            ])
+        );
+
+        // Probe results outlive the cache; banned nodes aren't probed
+        let restarted = MasternodeCache::new(config, source);
+        std::fs::remove_file(&probe_history_path).ok();
+        let now = probe_history::unix_now();
+        let history = restarted.probe_history();
+        let current = history.node("current", now).unwrap();
+        assert_eq!(current.uptime.last_hour, Some(100.0));
+        assert_eq!(
+            (current.probes[0].outcome, current.probes[0].dapi_version.as_deref()),
+            (ProbeOutcome::Success, Some("2.0.1"))
+        );
+        let slow = history.node("slow", now).unwrap();
+        assert_eq!((slow.uptime.last_week, slow.probes[0].outcome), (Some(0.0), ProbeOutcome::Fail));
+        // The slow node fails at the configured timeout, without holding up the others
+        assert!((1000..2000).contains(&slow.probes[0].latency_ms), "{}", slow.probes[0].latency_ms);
+        assert!(history.node("banned", now).is_none());
+    }
+
+    /// A node answering right away and one answering after 5 seconds, well
+    /// within the probe timeout, with the config to check them.
+    struct FastAndSlowNodes {
+        _nodes: [MockPlatformNode; 2],
+        _rpc: MockRpcServer,
+        config: Config,
+        probe_history_path: PathBuf,
+    }
+
+    impl FastAndSlowNodes {
+        async fn start() -> Self {
+            let certificate = TestCertificate::self_signed(&["127.0.0.1"]);
+            let reply = || StatusReply::versions("2.0.1", Some("2.0.1"), None);
+            let fast = MockPlatformNode::start("127.0.0.1:0", &certificate, reply(), Duration::ZERO).await;
+            let slow = MockPlatformNode::start("127.0.0.1:0", &certificate, reply(), Duration::from_secs(5)).await;
+            let rpc = MockRpcServer::start(vec![Exchange {
+                method: "masternode".to_string(),
+                params: vec![json!("list")],
+                result: json!({
+                    "fast-0": evo_node("fast", fast.addr.port(), "ENABLED"),
+                    "slow-0": evo_node("slow", slow.addr.port(), "ENABLED"),
+                }),
+                error: None,
+            }]);
+
+            let port = fast.addr.port();
+            let ca_cert_path = std::env::temp_dir().join(format!("qls-test-ca-{}.pem", port));
+            std::fs::write(&ca_cert_path, &certificate.cert_pem).unwrap();
+            let mut config = test_config(&rpc.url);
+            config.dapi.ca_cert_path = Some(ca_cert_path.to_string_lossy().to_string());
+            let probe_history_path = std::env::temp_dir().join(format!("qls-test-probes-{}.json", port));
+            config.dapi.probe_history_path = Some(probe_history_path.to_string_lossy().to_string());
+            config.dapi.probe_timeout_ms = 10_000;
+            Self { _nodes: [fast, slow], _rpc: rpc, config, probe_history_path }
+        }
+
+        fn cache(&self) -> MasternodeCache {
+            let cache = MasternodeCache::new(self.config.clone(), Arc::new(RpcDataSource::new(self.config.clone())));
+            std::fs::remove_file(self.config.dapi.ca_cert_path.as_ref().unwrap()).ok();
+            cache
+        }
+
+        /// The published version checks, and whether the fast and slow nodes' were saved.
+        fn results(&self, cache: &MasternodeCache) -> (HashMap<String, String>, bool, bool) {
+            let checks = cache
+                .data
+                .load_full()
+                .unwrap()
+                .data
+                .iter()
+                .map(|node| (node.pro_tx_hash.clone(), node.version_check.clone()))
+                .collect();
+            let saved = MasternodeCache::new(self.config.clone(), cache.source.clone());
+            std::fs::remove_file(&self.probe_history_path).ok();
+            let now = probe_history::unix_now();
+            let probes = saved.probe_history();
+            (checks, probes.node("fast", now).is_some(), probes.node("slow", now).is_some())
+        }
+    }
+
+    fn checks(fast: &str, slow: &str) -> HashMap<String, String> {
+        HashMap::from([("fast".to_string(), fast.to_string()), ("slow".to_string(), slow.to_string())])
+    }
+
+    #[tokio::test]
+    async fn version_checks_stopped_by_shutdown_save_their_results() {
+        let nodes = FastAndSlowNodes::start().await;
+        let cache = nodes.cache();
+        let loaded = cache.load().await.unwrap();
+        let shutdown = CancellationToken::new();
+        let started = Instant::now();
+        tokio::join!(cache.check_versions(loaded.data.clone(), Duration::ZERO, &shutdown), async {
+            tokio::time::sleep(Duration::from_millis(500)).await;
+            shutdown.cancel();
         });
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(nodes.results(&cache), (checks("success", "pending"), true, false));
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn version_checks_requests_start_are_queued_and_drained_on_shutdown() {
+        let nodes = FastAndSlowNodes::start().await;
+        let cache = Arc::new(nodes.cache());
+        let shutdown = CancellationToken::new();
+        let background = cache.clone().start_background_refresh(shutdown.clone());
+
+        // While a round is running, the nodes of a newly loaded list wait for it
+        let running = cache.version_check_lock.clone().lock_owned().await;
+        cache.get_masternodes().await.unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(cache.data.load_full().unwrap().data.iter().filter(|node| node.version_check == "pending").count(), 2);
+        drop(running);
+        tokio::time::sleep(Duration::from_millis(500)).await;
+
+        // Shutdown stops the queued round, and the refresh task ends once it has saved
+        let started = Instant::now();
+        shutdown.cancel();
+        background.await.unwrap();
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(nodes.results(&cache), (checks("success", "pending"), true, false));
+    }
+}
@@ -0,0 +1,215 @@
+//! Changes to Evo masternodes since the server started (or since its history
+//! file was started), from the masternode list diffs between its refreshes, so
+//! operators can audit them.
+
+use crate::masternode::{DeterministicMasternode, EvoMasternodeState, MasternodeListDiff};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashSet;
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MasternodeEventKind {
+    Registered,
+    Updated,
+    PoseBanned,
+    Revived,
+    Removed,
+}
+
+impl MasternodeEventKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Registered => "registered",
+            Self::Updated => "updated",
+            Self::PoseBanned => "pose_banned",
+            Self::Revived => "revived",
+            Self::Removed => "removed",
+        }
+    }
+}
+
+/// One change to an Evo masternode.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct MasternodeEvent {
+    /// Height the change happened at when the diff tells (registrations, bans
+    /// and revivals), otherwise the height of the refresh that noticed it.
+    pub height: u32,
+    #[serde(rename = "proTxHash")]
+    pub pro_tx_hash: String,
+    pub kind: MasternodeEventKind,
+    /// State fields that changed, with their new values. A registration lists
+    /// the node's state; a removal lists nothing.
+    #[schema(value_type = Object)]
+    pub changes: Map<String, Value>,
+}
+
+/// Events in the order they were recorded, and the Evo nodes registered at
+/// the height recorded last.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct MasternodeHistory {
+    events: Vec<MasternodeEvent>,
+    height: Option<u32>,
+    evo: HashSet<String>,
+}
+
+impl MasternodeHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Height the history is recorded up to; unset until the first list is seen.
+    pub fn height(&self) -> Option<u32> {
+        self.height
+    }
+
+    /// Start the history from the Evo nodes registered at `height`. Nodes
+    /// already registered there have no events.
+    pub fn start(&mut self, height: u32, masternodes: &[DeterministicMasternode]) {
+        self.height = Some(height);
+        self.evo = masternodes.iter().map(|masternode| masternode.pro_tx_hash.clone()).collect();
+    }
+
+    /// Record the changes to Evo nodes in `diff`, which must start at the
+    /// height the history is recorded up to.
+    pub fn apply(&mut self, mut diff: MasternodeListDiff) {
+        diff.retain_evo(&self.evo);
+        let mut events = Vec::new();
+
+        for masternode in std::mem::take(&mut diff.added) {
+            self.evo.insert(masternode.pro_tx_hash.clone());
+            let state = EvoMasternodeState::from(masternode);
+            let changes = match serde_json::to_value(&state) {
+                Ok(Value::Object(changes)) => changes,
+                _ => Map::new(),
+            };
+            events.push(MasternodeEvent {
+                height: state.registered_height,
+                pro_tx_hash: state.pro_tx_hash,
+                kind: MasternodeEventKind::Registered,
+                changes,
+            });
+        }
+
+        for update in diff.updates() {
+            let height_of = |field: &str| {
+                let height = update.changes.get(field).and_then(Value::as_i64)?;
+                u32::try_from(height).ok().filter(|height| *height > 0)
+            };
+            let (kind, height) = match update.changes.get("PoSeBanHeight").and_then(Value::as_i64) {
+                Some(ban_height) if ban_height > 0 => (MasternodeEventKind::PoseBanned, height_of("PoSeBanHeight")),
+                // Dash Core resets the ban height to -1 when a node is revived
+                Some(_) => (MasternodeEventKind::Revived, height_of("PoSeRevivedHeight")),
+                None => (MasternodeEventKind::Updated, None),
+            };
+            events.push(MasternodeEvent {
+                height: height.unwrap_or(diff.block_height),
+                pro_tx_hash: update.pro_tx_hash,
+                kind,
+                changes: update.changes,
+            });
+        }
+
+        for pro_tx_hash in diff.removed {
+            self.evo.remove(&pro_tx_hash);
+            events.push(MasternodeEvent {
+                height: diff.block_height,
+                pro_tx_hash,
+                kind: MasternodeEventKind::Removed,
+                changes: Map::new(),
+            });
+        }
+
+        events.sort_by_key(|event| event.height);
+        self.events.extend(events);
+        self.height = Some(diff.block_height);
+    }
+
+    /// Events at heights in `from..=to`, of one node or of every node, in the
+    /// order they were recorded.
+    pub fn events_between<'a>(
+        &'a self,
+        pro_tx_hash: Option<&'a str>,
+        from: u32,
+        to: u32,
+    ) -> impl Iterator<Item = &'a MasternodeEvent> {
+        self.events.iter().filter(move |event| {
+            pro_tx_hash.is_none_or(|pro_tx_hash| event.pro_tx_hash.eq_ignore_ascii_case(pro_tx_hash))
+                && (from..=to).contains(&event.height)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn masternode(id: &str, node_type: &str, registered_height: u32) -> Value {
+        json!({
+            "type": node_type,
+            "proTxHash": id,
+            "state": {
+                "service": format!("10.0.0.{}:19999", id.len()),
+                "registeredHeight": registered_height,
+                "PoSePenalty": 0,
+                "PoSeBanHeight": -1,
+                "PoSeRevivedHeight": -1,
+            },
+        })
+    }
+
+    fn diff(base_height: u32, block_height: u32, added: Vec<Value>, removed: &[&str], updated: Value) -> MasternodeListDiff {
+        serde_json::from_value(json!({
+            "baseHeight": base_height,
+            "blockHeight": block_height,
+            "addedMNs": added,
+            "removedMNs": removed,
+            "updatedMNs": updated,
+        }))
+        .unwrap()
+    }
+
+    fn summary<'a>(events: impl Iterator<Item = &'a MasternodeEvent>) -> Vec<(u32, String, &'static str)> {
+        events.map(|event| (event.height, event.pro_tx_hash.clone(), event.kind.as_str())).collect()
+    }
+
+    #[test]
+    fn diffs_become_evo_node_events() {
+        let mut history = MasternodeHistory::new();
+        let start: Vec<DeterministicMasternode> =
+            serde_json::from_value(json!([masternode("a", "Evo", 10), masternode("b", "Evo", 20)])).unwrap();
+        history.start(100, &start);
+
+        history.apply(diff(
+            100,
+            110,
+            vec![masternode("c", "Evo", 105), masternode("r", "Regular", 106)],
+            &["b", "r0"],
+            json!([{"a": {"PoSePenalty": 500, "PoSeBanHeight": 108}}, {"r1": {"service": "10.0.0.9:19999"}}]),
+        ));
+        history.apply(diff(
+            110,
+            120,
+            Vec::new(),
+            &[],
+            json!([{"a": {"PoSePenalty": 0, "PoSeBanHeight": -1, "PoSeRevivedHeight": 117}}, {"c": {"service": "10.0.0.3:19999"}}]),
+        ));
+
+        assert_eq!(history.height(), Some(120));
+        assert_eq!(
+            summary(history.events_between(None, 0, u32::MAX)),
+            [
+                (105, "c".to_string(), "registered"),
+                (108, "a".to_string(), "pose_banned"),
+                (110, "b".to_string(), "removed"),
+                (117, "a".to_string(), "revived"),
+                (120, "c".to_string(), "updated"),
+            ]
+        );
+        let registered = history.events_between(Some("c"), 0, 105).next().unwrap();
+        assert_eq!(registered.changes["address"], "10.0.0.1:19999");
+        assert_eq!(summary(history.events_between(Some("a"), 109, 120)), [(117, "a".to_string(), "revived")]);
+    }
+}
@@ -1,16 +1,12 @@
-use crate::config::Config;
-use crate::masternode::{MasternodeList, EvoMasternodeList};
-use dashcore_rpc::{Client, RpcApi, Auth};
-use std::error::Error;
+use crate::masternode::{DeterministicMasternode, MasternodeList, MasternodeListDiff, EvoMasternodeList};
+use crate::data_source::CoreDataSource;
+use crate::error::AppError;
 
 pub async fn load_masternode_list(
-    config: &Config,
-) -> Result<EvoMasternodeList, Box<dyn Error + Send + Sync>> {
-    let auth = Auth::UserPass(config.rpc.username.clone(), config.rpc.password.clone());
-    let client = Client::new(&config.rpc.url, auth)?;
-    
+    source: &dyn CoreDataSource,
+) -> Result<EvoMasternodeList, AppError> {
     // Call masternode list command
-    let result: serde_json::Value = client.call("masternode", &[serde_json::json!("list")])?;
+    let result = source.masternode_list().await?;
     
     // Parse the result as a HashMap of masternodes
     let masternode_list: MasternodeList = serde_json::from_value(result)?;
@@ -23,4 +19,25 @@ pub async fn load_masternode_list(
     
     println!("Loaded {} Evo masternodes from Dash Core", evo_masternodes.len());
     Ok(evo_masternodes)
-}
\ No newline at end of file
+}
+
+/// Load the Evo masternodes registered at `height`.
+pub async fn load_masternode_list_at(
+    source: &dyn CoreDataSource,
+    height: u32,
+) -> Result<Vec<DeterministicMasternode>, AppError> {
+    let result = source.masternode_list_at(height).await?;
+    let masternodes: Vec<DeterministicMasternode> = serde_json::from_value(result)?;
+    Ok(masternodes.into_iter().filter(|masternode| masternode.node_type == "Evo").collect())
+}
+
+/// Load the masternode list changes from `base_height` to `height`, for
+/// every node type.
+pub async fn load_masternode_diff(
+    source: &dyn CoreDataSource,
+    base_height: u32,
+    height: u32,
+) -> Result<MasternodeListDiff, AppError> {
+    let result = source.masternode_list_diff(base_height, height).await?;
+    Ok(serde_json::from_value(result)?)
+}
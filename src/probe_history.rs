@@ -0,0 +1,225 @@
+//! Rolling per-node record of DAPI version probes, and the uptime computed
+//! from it. Saved to disk after every refresh so it survives restarts.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use utoipa::ToSchema;
+
+/// How many of a node's latest probes are kept in full.
+pub const RECENT_PROBES: usize = 100;
+
+const HOUR_SECS: u64 = 3600;
+const DAY_SECS: u64 = 24 * HOUR_SECS;
+const WEEK_SECS: u64 = 7 * DAY_SECS;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ProbeOutcome {
+    Success,
+    Fail,
+}
+
+impl ProbeOutcome {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Success => "success",
+            Self::Fail => "fail",
+        }
+    }
+}
+
+/// One version check of a node's DAPI endpoint.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ProbeResult {
+    /// Unix time of the probe, in seconds.
+    pub timestamp: u64,
+    pub outcome: ProbeOutcome,
+    pub latency_ms: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dapi_version: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub drive_version: Option<String>,
+}
+
+/// Percentage of successful probes per window; unset when a window has no probes.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, ToSchema)]
+pub struct Uptime {
+    pub last_hour: Option<f64>,
+    /// Counted by the hour, so it may include up to an hour more.
+    pub last_day: Option<f64>,
+    /// Counted by the hour, so it may include up to an hour more.
+    pub last_week: Option<f64>,
+}
+
+/// A node's uptime and latest probes, oldest first.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct NodeProbeHistory {
+    #[serde(rename = "proTxHash")]
+    pub pro_tx_hash: String,
+    pub uptime: Uptime,
+    pub probes: Vec<ProbeResult>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct HourlyProbes {
+    /// Unix time divided by 3600.
+    hour: u64,
+    successes: u32,
+    probes: u32,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct NodeProbes {
+    recent: VecDeque<ProbeResult>,
+    hourly: VecDeque<HourlyProbes>,
+}
+
+impl NodeProbes {
+    fn last_hour(&self, now: u64) -> Option<f64> {
+        let probes: Vec<_> = self.recent.iter().filter(|probe| probe.timestamp + HOUR_SECS > now).collect();
+        let successes = probes.iter().filter(|probe| probe.outcome == ProbeOutcome::Success).count();
+        percentage(successes as u64, probes.len() as u64)
+    }
+
+    fn hourly_uptime(&self, now: u64, window: u64) -> Option<f64> {
+        let first_hour = now.saturating_sub(window) / HOUR_SECS;
+        let (successes, probes) = self
+            .hourly
+            .iter()
+            .filter(|hour| hour.hour >= first_hour)
+            .fold((0, 0), |(successes, probes), hour| (successes + u64::from(hour.successes), probes + u64::from(hour.probes)));
+        percentage(successes, probes)
+    }
+}
+
+fn percentage(successes: u64, probes: u64) -> Option<f64> {
+    (probes > 0).then(|| successes as f64 * 100.0 / probes as f64)
+}
+
+/// Probe records by proTxHash.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ProbeHistory {
+    nodes: HashMap<String, NodeProbes>,
+}
+
+impl ProbeHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, pro_tx_hash: &str, probe: ProbeResult) {
+        let node = self.nodes.entry(pro_tx_hash.to_string()).or_default();
+        let hour = probe.timestamp / HOUR_SECS;
+        if node.hourly.back().is_none_or(|last| last.hour != hour) {
+            node.hourly.push_back(HourlyProbes { hour, successes: 0, probes: 0 });
+        }
+        if let Some(last) = node.hourly.back_mut() {
+            last.probes += 1;
+            last.successes += u32::from(probe.outcome == ProbeOutcome::Success);
+        }
+
+        node.recent.push_back(probe);
+        if node.recent.len() > RECENT_PROBES {
+            node.recent.pop_front();
+        }
+    }
+
+    /// Forget probes older than a week, and nodes not probed within it.
+    pub fn prune(&mut self, now: u64) {
+        let first_hour = now.saturating_sub(WEEK_SECS) / HOUR_SECS;
+        self.nodes.retain(|_, node| {
+            while node.recent.front().is_some_and(|probe| probe.timestamp + WEEK_SECS < now) {
+                node.recent.pop_front();
+            }
+            while node.hourly.front().is_some_and(|hour| hour.hour < first_hour) {
+                node.hourly.pop_front();
+            }
+            !node.hourly.is_empty()
+        });
+    }
+
+    /// The latest probe of a node.
+    pub fn latest(&self, pro_tx_hash: &str) -> Option<&ProbeResult> {
+        self.nodes.get(pro_tx_hash)?.recent.back()
+    }
+
+    pub fn uptime(&self, pro_tx_hash: &str, now: u64) -> Option<Uptime> {
+        let node = self.nodes.get(pro_tx_hash)?;
+        Some(Uptime {
+            last_hour: node.last_hour(now),
+            last_day: node.hourly_uptime(now, DAY_SECS),
+            last_week: node.hourly_uptime(now, WEEK_SECS),
+        })
+    }
+
+    /// A node's uptime as of `now` and its latest probes, if it was ever probed.
+    pub fn node(&self, pro_tx_hash: &str, now: u64) -> Option<NodeProbeHistory> {
+        Some(NodeProbeHistory {
+            pro_tx_hash: pro_tx_hash.to_string(),
+            uptime: self.uptime(pro_tx_hash, now)?,
+            probes: self.nodes.get(pro_tx_hash)?.recent.iter().cloned().collect(),
+        })
+    }
+}
+
+/// The current Unix time in seconds.
+pub fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history_file;
+
+    const NOW: u64 = 1_700_000_000;
+
+    fn probe(age_secs: u64, outcome: ProbeOutcome) -> ProbeResult {
+        ProbeResult { timestamp: NOW - age_secs, outcome, latency_ms: 40, dapi_version: None, drive_version: None }
+    }
+
+    #[test]
+    fn uptime_is_computed_per_window() {
+        let mut history = ProbeHistory::new();
+        // Every 10 minutes for two days: failing until the last 6 hours, then only the last probe fails
+        for age in (0..2 * DAY_SECS / 600).rev().map(|step| step * 600) {
+            let outcome = if age >= 6 * HOUR_SECS || age == 0 { ProbeOutcome::Fail } else { ProbeOutcome::Success };
+            history.record("a", probe(age, outcome));
+        }
+
+        let uptime = history.uptime("a", NOW).unwrap();
+        // 5 of the 6 probes in the last hour succeeded
+        assert_eq!(uptime.last_hour.map(|uptime| uptime.round()), Some(83.0));
+        let last_day = uptime.last_day.unwrap();
+        assert!((20.0..30.0).contains(&last_day), "{}", last_day);
+        assert!(uptime.last_week.unwrap() < last_day);
+        assert_eq!(history.node("a", NOW).unwrap().probes.len(), RECENT_PROBES);
+
+        // Hours without probes don't count
+        assert_eq!(history.uptime("a", NOW + 2 * HOUR_SECS).unwrap().last_hour, None);
+        assert!(history.node("b", NOW).is_none());
+    }
+
+    #[tokio::test]
+    async fn old_probes_are_pruned_and_the_history_round_trips() {
+        let mut history = ProbeHistory::new();
+        history.record("old", probe(WEEK_SECS + 2 * HOUR_SECS, ProbeOutcome::Success));
+        history.record("a", probe(WEEK_SECS + 2 * HOUR_SECS, ProbeOutcome::Fail));
+        history.record("a", probe(60, ProbeOutcome::Success));
+        history.prune(NOW);
+
+        assert!(history.uptime("old", NOW).is_none());
+        assert_eq!(history.uptime("a", NOW).unwrap().last_week, Some(100.0));
+        assert_eq!(history.node("a", NOW).unwrap().probes, [probe(60, ProbeOutcome::Success)]);
+
+        let path = std::env::temp_dir().join(format!("quorum-list-server-{}-probes.json", std::process::id()));
+        history_file::save(path.clone(), serde_json::to_string(&history).unwrap(), "probe history").await.unwrap();
+        let loaded: ProbeHistory = history_file::load(&path, "probe history").unwrap();
+        std::fs::remove_file(&path).ok();
+        assert_eq!(loaded.node("a", NOW).unwrap().probes, history.node("a", NOW).unwrap().probes);
+        assert!(history_file::load::<ProbeHistory>(&path, "probe history").unwrap().nodes.is_empty());
+    }
+}
@@ -0,0 +1,99 @@
+//! Runs a round of version checks with bounded concurrency, their starts
+//! staggered with jitter across a window, so a refresh doesn't open a
+//! connection to every Evo node at once.
+
+use futures::stream::{self, Stream, StreamExt};
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::future::Future;
+use std::time::Duration;
+
+/// When each of `count` probes starts, relative to the start of the round:
+/// `spread` is cut into `count` equal slots and each probe starts at a random
+/// point of its own slot. Ascending.
+pub fn start_offsets(count: usize, spread: Duration, rng: &mut impl Rng) -> Vec<Duration> {
+    if count == 0 {
+        return Vec::new();
+    }
+    let slot = spread.div_f64(count as f64);
+    (0..count).map(|i| slot.mul_f64(i as f64 + rng.gen::<f64>())).collect()
+}
+
+/// Probe every target in random order, starting them staggered across
+/// `spread` and running at most `concurrency` at a time. Results are yielded
+/// as probes complete.
+pub fn probe_all<T, F, Fut>(
+    mut targets: Vec<T>,
+    concurrency: usize,
+    spread: Duration,
+    mut probe: F,
+) -> impl Stream<Item = Fut::Output>
+where
+    F: FnMut(T) -> Fut,
+    Fut: Future,
+{
+    let mut rng = rand::thread_rng();
+    targets.shuffle(&mut rng);
+    let offsets = start_offsets(targets.len(), spread, &mut rng);
+    let round_start = tokio::time::Instant::now();
+
+    // Offsets are ascending, so probes waiting for their start never hold a
+    // slot a probe due earlier could use
+    stream::iter(targets.into_iter().zip(offsets))
+        .map(move |(target, offset)| {
+            let probe = probe(target);
+            async move {
+                tokio::time::sleep_until(round_start + offset).await;
+                probe.await
+            }
+        })
+        .buffer_unordered(concurrency.max(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn starts_are_jittered_within_their_slots() {
+        let offsets = start_offsets(10, Duration::from_secs(100), &mut rand::thread_rng());
+        assert_eq!(offsets.len(), 10);
+        for (i, offset) in offsets.iter().enumerate() {
+            let slot_start = Duration::from_secs(10 * i as u64);
+            assert!((slot_start..slot_start + Duration::from_secs(10)).contains(offset), "{:?}", offset);
+        }
+
+        let offsets = start_offsets(3, Duration::ZERO, &mut rand::thread_rng());
+        assert_eq!(offsets, [Duration::ZERO; 3]);
+        assert!(start_offsets(0, Duration::from_secs(100), &mut rand::thread_rng()).is_empty());
+    }
+
+    #[tokio::test]
+    async fn probes_run_at_most_concurrency_at_a_time() {
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let most_in_flight = Arc::new(AtomicUsize::new(0));
+
+        let started = tokio::time::Instant::now();
+        let mut results: Vec<usize> = probe_all((0..10).collect(), 3, Duration::from_millis(50), |target| {
+            let in_flight = in_flight.clone();
+            let most_in_flight = most_in_flight.clone();
+            async move {
+                let now_in_flight = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                most_in_flight.fetch_max(now_in_flight, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+                target
+            }
+        })
+        .collect()
+        .await;
+
+        results.sort();
+        assert_eq!(results, (0..10).collect::<Vec<_>>());
+        assert_eq!(most_in_flight.load(Ordering::SeqCst), 3);
+        // The last probe starts in the last 5ms slot of the spread
+        assert!(started.elapsed() >= Duration::from_millis(65), "{:?}", started.elapsed());
+    }
+}
@@ -0,0 +1,271 @@
+use crate::anchor::BlockAnchor;
+use crate::bls::BlsScheme;
+use crate::config::Config;
+use crate::data_source::SharedDataSource;
+use crate::error::AppError;
+use crate::history_file;
+use crate::locks::LockQuorums;
+use crate::quorum_history::QuorumHistory;
+use crate::quorum_list::QuorumList;
+use crate::quorum_loader;
+use crate::signing::SnapshotSigner;
+use crate::snapshot::Snapshot;
+use arc_swap::ArcSwap;
+use quorum_list_client::snapshot_message;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock, RwLockReadGuard};
+use std::time::{Duration, Instant};
+use tokio::sync::{Mutex, OnceCell};
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+use chrono::Local;
+use indexmap::IndexMap;
+
+pub type SharedQuorumSnapshot = Arc<Snapshot<QuorumState>>;
+
+/// The quorums listed at one block, and its lock quorums when locks are verified.
+pub type QuorumsAtBlock = (QuorumList, Option<LockQuorums>);
+
+/// How many blocks' quorum lists `quorums_at` keeps.
+const BLOCKS_CACHED: usize = 32;
+
+/// A published quorum list, the block it was loaded at and, when a signing
+/// key is configured, the signature over both.
+#[derive(Debug, Default)]
+pub struct QuorumState {
+    pub list: QuorumList,
+    pub anchor: Option<BlockAnchor>,
+    pub signature: Option<Vec<u8>>,
+    /// The quorums signing ChainLocks and InstantSend locks at the same
+    /// block, when lock verification is enabled.
+    pub locks: Option<LockQuorums>,
+    /// The BLS scheme the quorums sign with.
+    pub bls_scheme: BlsScheme,
+}
+
+pub struct QuorumCache {
+    snapshot: ArcSwap<Snapshot<QuorumState>>,
+    config: Arc<Config>,
+    source: SharedDataSource,
+    update_interval: Duration,
+    signer: Option<SnapshotSigner>,
+    /// Every quorum published so far. Unlike the snapshot it is never cleared.
+    history: RwLock<QuorumHistory>,
+    /// Held while the list is reloaded, so refreshes never overlap and an
+    /// older list is never published over a newer one.
+    refresh_lock: Mutex<()>,
+    /// Held while the history is saved, so an older save never lands last.
+    history_save: Mutex<()>,
+    /// Quorums loaded by `quorums_at`, by block hash, least recently used first.
+    /// Concurrent requests for a block share one load.
+    blocks: std::sync::Mutex<IndexMap<String, Arc<OnceCell<Arc<QuorumsAtBlock>>>>>,
+}
+
+impl QuorumCache {
+    /// Panics if `config.signing` names a key that can't be loaded; serving
+    /// unsigned lists when signed ones were asked for is never intended.
+    pub fn new(config: Config, source: SharedDataSource) -> Self {
+        let update_interval = Duration::from_secs(config.quorum.refresh_interval_secs);
+        let signer = config.signing.as_ref().map(|signing| {
+            SnapshotSigner::from_config(signing).unwrap_or_else(|e| panic!("{}", e))
+        });
+        let history = match &config.quorum.history_path {
+            Some(path) => history_file::load(Path::new(path), "quorum history").unwrap_or_else(|e| {
+                eprintln!("Warning: {}, starting a new quorum history", e);
+                QuorumHistory::new()
+            }),
+            None => QuorumHistory::new(),
+        };
+        Self {
+            snapshot: ArcSwap::from_pointee(Snapshot::new(QuorumState::default(), None)),
+            config: Arc::new(config),
+            source,
+            update_interval,
+            signer,
+            history: RwLock::new(history),
+            refresh_lock: Mutex::new(()),
+            history_save: Mutex::new(()),
+            blocks: std::sync::Mutex::new(IndexMap::new()),
+        }
+    }
+
+    /// The key published lists are signed with, if any.
+    pub fn signer(&self) -> Option<&SnapshotSigner> {
+        self.signer.as_ref()
+    }
+
+    /// Lifecycles of the quorums published since the server started.
+    pub fn history(&self) -> RwLockReadGuard<'_, QuorumHistory> {
+        self.history.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// The currently published quorum list.
+    pub fn snapshot(&self) -> SharedQuorumSnapshot {
+        self.snapshot.load_full()
+    }
+
+    /// How often the background refresh reloads the list.
+    pub fn update_interval(&self) -> Duration {
+        self.update_interval
+    }
+
+    /// When the quorum list was last successfully reloaded from Dash Core.
+    pub fn last_update(&self) -> Option<Instant> {
+        self.snapshot.load().refreshed_at
+    }
+
+    /// Reload the quorum list from the data source at the best ChainLocked
+    /// block (or the tip), sign it and publish it. Returns the number of quorums loaded.
+    pub async fn refresh(&self) -> Result<usize, AppError> {
+        let _refreshing = self.refresh_lock.lock().await;
+        // Pin the list to one block so its anchor and signature describe exactly it
+        let anchor = BlockAnchor::best(self.source.as_ref()).await?;
+        let (mut list, mut locks) =
+            quorum_loader::load_with_lock_quorums_at_height(self.source.as_ref(), &self.config, anchor.height).await?;
+
+        let signature = match &self.signer {
+            Some(signer) => {
+                let quorums: Vec<(Vec<u8>, Vec<u8>)> =
+                    list.iter().map(|entry| (entry.quorum_hash.clone(), entry.key.clone())).collect();
+                let message = snapshot_message(anchor.height, &hex::decode(&anchor.block_hash)?, &quorums)
+                    .map_err(|e| AppError::Upstream(format!("Can't sign quorum list: {}", e)))?;
+                Some(signer.sign(&message))
+            }
+            None => None,
+        };
+
+        self.record_history(&list, locks.as_ref(), anchor.height).await;
+        self.save_history().await;
+        // Signing quorums are chosen by the height they were mined at
+        {
+            let history = self.history();
+            let mined_height = |quorum_hash: &[u8]| history.get(quorum_hash).and_then(|quorum| quorum.mined_height);
+            list.set_mined_heights(mined_height);
+            if let Some(locks) = &mut locks {
+                locks.chainlock.set_mined_heights(mined_height);
+                locks.instantsend.set_mined_heights(mined_height);
+            }
+        }
+        let bls_scheme = match self.source.sync_status().await {
+            Ok(status) => BlsScheme::of(&status),
+            Err(e) => {
+                eprintln!("Can't check the BLS scheme, assuming the basic one: {}", e);
+                BlsScheme::Basic
+            }
+        };
+
+        let count = list.len();
+        let state = QuorumState {
+            list,
+            anchor: Some(anchor),
+            signature,
+            locks,
+            bls_scheme,
+        };
+        self.snapshot.store(Arc::new(Snapshot::succeeding(&self.snapshot.load(), state, Some(Instant::now()))));
+        Ok(count)
+    }
+
+    /// Add the quorums loaded at `height` to the history, looking up the
+    /// mined height of the ones it doesn't have one for yet.
+    async fn record_history(&self, list: &QuorumList, locks: Option<&LockQuorums>, height: u32) {
+        let mut lists = vec![(self.config.get_llmq_type(), list)];
+        if let Some(locks) = locks {
+            lists.push((self.config.network.chainlock_llmq().name, &locks.chainlock));
+            lists.push((self.config.network.instantsend_llmq().name, &locks.instantsend));
+        }
+
+        let unmined: Vec<_> = {
+            let history = self.history();
+            lists
+                .iter()
+                .flat_map(|(_, list)| list.iter())
+                .filter(|entry| !entry.mined_block_hash.is_empty() && history.needs_mined_height(&entry.quorum_hash))
+                .map(|entry| (entry.quorum_hash.clone(), hex::encode(&entry.mined_block_hash)))
+                .collect()
+        };
+        let mut mined_heights = HashMap::new();
+        for (quorum_hash, mined_block_hash) in unmined {
+            match self.source.block_header(&mined_block_hash).await {
+                Ok(header) => match header["height"].as_u64() {
+                    Some(height) => {
+                        mined_heights.insert(quorum_hash, height as u32);
+                    }
+                    None => eprintln!("Block header of {} has no height", mined_block_hash),
+                },
+                Err(e) => eprintln!("Can't look up the mined height of quorum {}: {}", hex::encode(&quorum_hash), e),
+            }
+        }
+
+        let mut history = self.history.write().unwrap_or_else(|e| e.into_inner());
+        for (llmq_type, list) in lists {
+            history.record(llmq_type, list, height, &mined_heights);
+        }
+    }
+
+    /// The quorums listed at `height`, whose block is `block_hash`. The last
+    /// few blocks asked for are kept, so repeated requests don't reload them.
+    pub async fn quorums_at(&self, height: u32, block_hash: &str) -> Result<Arc<QuorumsAtBlock>, AppError> {
+        let cell = {
+            let mut blocks = self.blocks.lock().unwrap_or_else(|e| e.into_inner());
+            let cell = blocks.shift_remove(block_hash).unwrap_or_default();
+            blocks.insert(block_hash.to_string(), cell.clone());
+            if blocks.len() > BLOCKS_CACHED {
+                blocks.shift_remove_index(0);
+            }
+            cell
+        };
+        cell.get_or_try_init(|| async {
+            let loaded = quorum_loader::load_with_lock_quorums_at_height(self.source.as_ref(), &self.config, height).await?;
+            Ok(Arc::new(loaded))
+        })
+        .await
+        .cloned()
+    }
+
+    /// Save the history to `quorum.history_path`, if one is configured.
+    pub async fn save_history(&self) {
+        let Some(path) = &self.config.quorum.history_path else {
+            return;
+        };
+        let _saving = self.history_save.lock().await;
+        let content = match serde_json::to_string(&*self.history()) {
+            Ok(content) => content,
+            Err(e) => {
+                eprintln!("Warning: Failed to serialize quorum history: {}", e);
+                return;
+            }
+        };
+        if let Err(e) = history_file::save(PathBuf::from(path), content, "quorum history").await {
+            eprintln!("Warning: {}", e);
+        }
+    }
+
+    /// Publish an empty, unanchored list until the next refresh.
+    pub fn clear(&self) {
+        let refreshed_at = self.last_update();
+        self.snapshot.store(Arc::new(Snapshot::succeeding(&self.snapshot.load(), QuorumState::default(), refreshed_at)));
+    }
+
+    /// Refresh the quorum list every `update_interval` until `shutdown` is cancelled.
+    pub fn start_background_refresh(self: Arc<Self>, shutdown: CancellationToken) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    _ = shutdown.cancelled() => break,
+                    _ = tokio::time::sleep(self.update_interval) => {}
+                }
+                let result = tokio::select! {
+                    _ = shutdown.cancelled() => break,
+                    result = self.refresh() => result,
+                };
+                match result {
+                    Ok(count) => println!("✅ [{}] Background refresh: Loaded {} quorums", Local::now().format("%Y-%m-%d %H:%M:%S"), count),
+                    Err(e) => eprintln!("❌ [{}] Background refresh: Failed to reload quorums: {}", Local::now().format("%Y-%m-%d %H:%M:%S"), e),
+                }
+            }
+            println!("Background refresh: quorum refresh stopped");
+        })
+    }
+}
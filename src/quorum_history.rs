@@ -0,0 +1,200 @@
+//! Every quorum the server has published, kept after it rotates out of the
+//! active set so its key and validity can still be looked up.
+
+use crate::quorum_list::QuorumList;
+use indexmap::IndexMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashMap;
+use utoipa::ToSchema;
+
+/// When a quorum was active, as observed by the server's refreshes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct QuorumLifecycle {
+    /// Hex encoded 32-byte quorum hash.
+    pub quorum_hash: String,
+    /// LLMQ type name, e.g. `llmq_25_67`.
+    pub llmq_type: String,
+    /// Hex encoded 48-byte BLS quorum public key.
+    pub key: String,
+    pub creation_height: u32,
+    /// Height of the block the quorum's commitment was mined in, if known.
+    pub mined_height: Option<u32>,
+    /// Height of the first refresh that listed the quorum.
+    pub first_seen_height: u32,
+    /// Height of the last refresh that listed the quorum.
+    pub last_active_height: u32,
+    /// Height of the first refresh that no longer listed it. Unset while the quorum is active.
+    pub retired_height: Option<u32>,
+}
+
+impl QuorumLifecycle {
+    /// Whether the quorum was active at some height in `from..=to`. It counts
+    /// as active from the block it was mined in, or first seen if that's unknown.
+    fn active_between(&self, from: u32, to: u32) -> bool {
+        self.mined_height.unwrap_or(self.first_seen_height) <= to
+            && self.retired_height.is_none_or(|retired| retired > from)
+    }
+}
+
+/// Lifecycles of all quorums seen since the server started, or since the
+/// history file was started, by quorum hash, in the order they were first seen.
+#[derive(Debug, Default)]
+pub struct QuorumHistory {
+    quorums: IndexMap<Vec<u8>, QuorumLifecycle>,
+}
+
+/// Saved as the list of lifecycles, each keyed by its hex `quorum_hash`.
+impl Serialize for QuorumHistory {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.quorums.values())
+    }
+}
+
+impl<'de> Deserialize<'de> for QuorumHistory {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let quorums = Vec::<QuorumLifecycle>::deserialize(deserializer)?
+            .into_iter()
+            .map(|quorum| Ok((hex::decode(&quorum.quorum_hash).map_err(serde::de::Error::custom)?, quorum)))
+            .collect::<Result<_, D::Error>>()?;
+        Ok(Self { quorums })
+    }
+}
+
+impl QuorumHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, quorum_hash: &[u8]) -> Option<&QuorumLifecycle> {
+        self.quorums.get(quorum_hash)
+    }
+
+    /// Whether the mined height of `quorum_hash` still has to be looked up.
+    pub fn needs_mined_height(&self, quorum_hash: &[u8]) -> bool {
+        self.get(quorum_hash).is_none_or(|quorum| quorum.mined_height.is_none())
+    }
+
+    /// Record that `list` holds the active quorums of `llmq_type` at `height`.
+    /// Active quorums of that type missing from it retire at `height`.
+    /// `mined_heights` are the mined heights looked up for new quorums.
+    pub fn record(&mut self, llmq_type: &str, list: &QuorumList, height: u32, mined_heights: &HashMap<Vec<u8>, u32>) {
+        for entry in list.iter() {
+            let quorum = self.quorums.entry(entry.quorum_hash.clone()).or_insert_with(|| QuorumLifecycle {
+                quorum_hash: hex::encode(&entry.quorum_hash),
+                llmq_type: llmq_type.to_string(),
+                key: hex::encode(&entry.key),
+                creation_height: entry.height,
+                mined_height: None,
+                first_seen_height: height,
+                last_active_height: height,
+                retired_height: None,
+            });
+            quorum.mined_height = quorum.mined_height.or(mined_heights.get(&entry.quorum_hash).copied());
+            quorum.last_active_height = quorum.last_active_height.max(height);
+            // Listed again after a reorg
+            quorum.retired_height = None;
+        }
+
+        for (quorum_hash, quorum) in &mut self.quorums {
+            // A refresh behind an earlier one (after a reorg) doesn't retire newer quorums
+            if quorum.llmq_type == llmq_type
+                && quorum.retired_height.is_none()
+                && quorum.last_active_height < height
+                && !list.contains_quorum(quorum_hash)
+            {
+                quorum.retired_height = Some(height);
+            }
+        }
+    }
+
+    /// Quorums active at some height in `from..=to`, of `llmq_type` or of
+    /// every type, in the order they were first seen.
+    pub fn active_between<'a>(
+        &'a self,
+        llmq_type: Option<&'a str>,
+        from: u32,
+        to: u32,
+    ) -> impl Iterator<Item = &'a QuorumLifecycle> {
+        self.quorums.values().filter(move |quorum| {
+            llmq_type.is_none_or(|llmq_type| quorum.llmq_type == llmq_type) && quorum.active_between(from, to)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history_file;
+    use crate::quorum_list::QuorumListEntry;
+
+    fn list(quorums: &[(u8, u32)]) -> QuorumList {
+        quorums
+            .iter()
+            .map(|&(id, height)| QuorumListEntry::new_extended(vec![id; 32], vec![id; 48], height, Vec::new(), String::new(), 0, 25))
+            .collect()
+    }
+
+    fn ids<'a>(quorums: impl Iterator<Item = &'a QuorumLifecycle>) -> Vec<String> {
+        quorums.map(|quorum| quorum.quorum_hash[..2].to_string()).collect()
+    }
+
+    #[test]
+    fn quorums_retire_when_no_longer_listed() {
+        let mut history = QuorumHistory::new();
+        let mined = HashMap::from([(vec![1; 32], 110), (vec![2; 32], 134)]);
+        history.record("llmq_25_67", &list(&[(1, 100), (2, 124)]), 150, &mined);
+        history.record("llmq_25_67", &list(&[(2, 124), (3, 148)]), 160, &HashMap::new());
+        history.record("llmq_50_60", &list(&[(9, 96)]), 160, &HashMap::new());
+
+        let first = history.get(&[1; 32]).unwrap();
+        assert_eq!(
+            (first.mined_height, first.first_seen_height, first.last_active_height, first.retired_height),
+            (Some(110), 150, 150, Some(160))
+        );
+        let third = history.get(&[3; 32]).unwrap();
+        assert_eq!((third.mined_height, third.first_seen_height, third.retired_height), (None, 160, None));
+        assert_eq!(history.get(&[2; 32]).unwrap().last_active_height, 160);
+        // Quorums of other types are tracked separately
+        assert_eq!(history.get(&[9; 32]).unwrap().retired_height, None);
+
+        assert!(!history.needs_mined_height(&[1; 32]));
+        assert!(history.needs_mined_height(&[3; 32]));
+        assert!(history.needs_mined_height(&[4; 32]));
+
+        // An older list doesn't retire quorums seen after it
+        history.record("llmq_25_67", &list(&[(2, 124)]), 155, &HashMap::new());
+        assert_eq!(history.get(&[3; 32]).unwrap().retired_height, None);
+    }
+
+    #[tokio::test]
+    async fn history_survives_a_save_and_load() {
+        let mut history = QuorumHistory::new();
+        history.record("llmq_25_67", &list(&[(1, 100), (2, 124)]), 150, &HashMap::from([(vec![1; 32], 110)]));
+        history.record("llmq_25_67", &list(&[(2, 124)]), 160, &HashMap::new());
+
+        let path = std::env::temp_dir().join(format!("quorum-list-server-{}-quorums.json", std::process::id()));
+        history_file::save(path.clone(), serde_json::to_string(&history).unwrap(), "quorum history").await.unwrap();
+        let loaded: QuorumHistory = history_file::load(&path, "quorum history").unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(loaded.quorums, history.quorums);
+        assert_eq!(loaded.get(&[1; 32]).unwrap().retired_height, Some(160));
+        assert!(history_file::load::<QuorumHistory>(&path, "quorum history").unwrap().quorums.is_empty());
+    }
+
+    #[test]
+    fn history_is_filtered_by_type_and_active_heights() {
+        let mut history = QuorumHistory::new();
+        history.record("llmq_25_67", &list(&[(1, 100)]), 150, &HashMap::from([(vec![1; 32], 110)]));
+        history.record("llmq_50_60", &list(&[(9, 96)]), 150, &HashMap::new());
+        history.record("llmq_25_67", &list(&[(2, 124)]), 160, &HashMap::new());
+
+        assert_eq!(ids(history.active_between(None, 0, u32::MAX)), ["01", "09", "02"]);
+        assert_eq!(ids(history.active_between(Some("llmq_25_67"), 0, u32::MAX)), ["01", "02"]);
+        // Quorum 1 is active from its mined height until it retires at 160
+        assert_eq!(ids(history.active_between(Some("llmq_25_67"), 100, 109)), Vec::<String>::new());
+        assert_eq!(ids(history.active_between(Some("llmq_25_67"), 100, 110)), ["01"]);
+        assert_eq!(ids(history.active_between(Some("llmq_25_67"), 159, 159)), ["01"]);
+        assert_eq!(ids(history.active_between(Some("llmq_25_67"), 160, 200)), ["02"]);
+    }
+}
@@ -1,88 +1,170 @@
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use indexmap::IndexMap;
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::{BTreeMap, HashMap};
+use std::ops::RangeBounds;
 
-#[derive(
-    Clone,
-    Debug,
-    PartialEq,
-    Eq,
-    Serialize,
-    Deserialize,
-)]
+/// Quorums indexed by hash and by height. Iteration follows insertion
+/// order; serializes as `{"list": [...]}` like the original `Vec`-backed list.
+#[derive(Clone, Debug, Default)]
 pub struct QuorumList {
-    pub list: Vec<QuorumListEntry>,
+    entries: IndexMap<Vec<u8>, QuorumListEntry>,
+    /// Quorum hashes by creation height, in insertion order within a height.
+    by_height: BTreeMap<u32, Vec<Vec<u8>>>,
 }
 
 impl QuorumList {
     pub fn new() -> Self {
-        Self {
-            list: Vec::new(),
-        }
+        Self::default()
     }
 
+    /// Add a quorum unless one with the same hash is already listed.
     pub fn add_entry(&mut self, entry: QuorumListEntry) {
-        if !self.contains_quorum(&entry.quorum_hash) {
-            self.list.push(entry);
+        if self.contains_quorum(&entry.quorum_hash) {
+            return;
         }
+        self.by_height
+            .entry(entry.height)
+            .or_default()
+            .push(entry.quorum_hash.clone());
+        self.entries.insert(entry.quorum_hash.clone(), entry);
     }
 
+    /// Remove a quorum, keeping the order of the rest.
     pub fn remove_entry(&mut self, quorum_hash: &[u8]) -> bool {
-        if let Some(pos) = self.list.iter().position(|x| x.quorum_hash == quorum_hash) {
-            self.list.remove(pos);
-            true
-        } else {
-            false
+        let Some(entry) = self.entries.shift_remove(quorum_hash) else {
+            return false;
+        };
+        if let Some(hashes) = self.by_height.get_mut(&entry.height) {
+            hashes.retain(|hash| hash.as_slice() != quorum_hash);
+            if hashes.is_empty() {
+                self.by_height.remove(&entry.height);
+            }
         }
+        true
     }
 
     pub fn contains_quorum(&self, quorum_hash: &[u8]) -> bool {
-        self.list.iter().any(|entry| entry.quorum_hash == quorum_hash)
+        self.entries.contains_key(quorum_hash)
     }
 
     pub fn get_entry(&self, quorum_hash: &[u8]) -> Option<&QuorumListEntry> {
-        self.list.iter().find(|entry| entry.quorum_hash == quorum_hash)
+        self.entries.get(quorum_hash)
     }
 
     pub fn len(&self) -> usize {
-        self.list.len()
+        self.entries.len()
     }
 
     pub fn is_empty(&self) -> bool {
-        self.list.is_empty()
+        self.entries.is_empty()
     }
 
     pub fn clear(&mut self) {
-        self.list.clear();
+        self.entries.clear();
+        self.by_height.clear();
     }
 
+    /// All quorums in insertion order.
     pub fn iter(&self) -> impl Iterator<Item = &QuorumListEntry> {
-        self.list.iter()
+        self.entries.values()
+    }
+
+    /// Quorums created within `heights`, lowest height first.
+    pub fn range_by_height<R: RangeBounds<u32>>(&self, heights: R) -> impl Iterator<Item = &QuorumListEntry> {
+        self.by_height
+            .range(heights)
+            .flat_map(|(_, hashes)| hashes.iter())
+            .filter_map(|hash| self.entries.get(hash))
+    }
+
+    /// The `count` most recently created quorums, highest height first.
+    pub fn latest(&self, count: usize) -> impl Iterator<Item = &QuorumListEntry> {
+        self.latest_up_to(u32::MAX, count)
+    }
+
+    /// The `count` most recently created quorums at or below `max_height`,
+    /// highest height first.
+    pub fn latest_up_to(&self, max_height: u32, count: usize) -> impl Iterator<Item = &QuorumListEntry> {
+        self.by_height
+            .range(..=max_height)
+            .map(|(_, hashes)| hashes)
+            .rev()
+            .flat_map(|hashes| hashes.iter())
+            .filter_map(|hash| self.entries.get(hash))
+            .take(count)
+    }
+
+    /// Fill in the mined height of the quorums `mined_height` knows it for.
+    pub fn set_mined_heights(&mut self, mined_height: impl Fn(&[u8]) -> Option<u32>) {
+        for entry in self.entries.values_mut() {
+            entry.mined_height = entry.mined_height.or_else(|| mined_height(&entry.quorum_hash));
+        }
+    }
+
+    /// Quorums listed in `newer` but not here, and quorums listed here but
+    /// not in `newer`, each in the order of its own list.
+    pub fn diff<'a>(&'a self, newer: &'a QuorumList) -> (Vec<&'a QuorumListEntry>, Vec<&'a QuorumListEntry>) {
+        let added = newer.iter().filter(|entry| !self.contains_quorum(&entry.quorum_hash)).collect();
+        let removed = self.iter().filter(|entry| !newer.contains_quorum(&entry.quorum_hash)).collect();
+        (added, removed)
     }
 
     pub fn to_hashmap(&self) -> HashMap<Vec<u8>, Vec<u8>> {
-        self.list.iter()
+        self.iter()
             .map(|entry| (entry.quorum_hash.clone(), entry.key.clone()))
             .collect()
     }
 }
 
-impl Default for QuorumList {
-    fn default() -> Self {
-        Self::new()
+impl PartialEq for QuorumList {
+    fn eq(&self, other: &Self) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+impl Eq for QuorumList {}
+
+impl Serialize for QuorumList {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        struct Entries<'a>(&'a QuorumList);
+
+        impl Serialize for Entries<'_> {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_seq(self.0.iter())
+            }
+        }
+
+        let mut state = serializer.serialize_struct("QuorumList", 1)?;
+        state.serialize_field("list", &Entries(self))?;
+        state.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for QuorumList {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct Repr {
+            list: Vec<QuorumListEntry>,
+        }
+
+        Ok(Repr::deserialize(deserializer)?.list.into())
     }
 }
 
 impl From<Vec<QuorumListEntry>> for QuorumList {
     fn from(list: Vec<QuorumListEntry>) -> Self {
-        Self { list }
+        list.into_iter().collect()
     }
 }
 
 impl FromIterator<QuorumListEntry> for QuorumList {
     fn from_iter<T: IntoIterator<Item = QuorumListEntry>>(iter: T) -> Self {
-        Self {
-            list: iter.into_iter().collect(),
+        let mut list = Self::new();
+        for entry in iter {
+            list.add_entry(entry);
         }
+        list
     }
 }
 
@@ -104,6 +186,19 @@ pub struct QuorumListEntry {
     pub threshold_signature: String,
     pub mining_members_count: u32,
     pub valid_members_count: u32,
+    /// Position in its rotation cycle; always 0 for types without rotation.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub quorum_index: u32,
+    /// Block the quorum's commitment was mined in, when Dash Core lists it.
+    #[serde(default, with = "hex", skip_serializing_if = "Vec::is_empty")]
+    pub mined_block_hash: Vec<u8>,
+    /// Height of `mined_block_hash`, once looked up.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mined_height: Option<u32>,
+}
+
+fn is_zero(value: &u32) -> bool {
+    *value == 0
 }
 
 #[derive(
@@ -131,6 +226,9 @@ impl QuorumListEntry {
             threshold_signature: String::new(),
             mining_members_count: 0,
             valid_members_count: 0,
+            quorum_index: 0,
+            mined_block_hash: Vec::new(),
+            mined_height: None,
         }
     }
     
@@ -151,6 +249,103 @@ impl QuorumListEntry {
             threshold_signature,
             mining_members_count,
             valid_members_count,
+            quorum_index: 0,
+            mined_block_hash: Vec::new(),
+            mined_height: None,
         }
     }
-}
\ No newline at end of file
+
+    /// The height the quorum can sign from: the block its commitment was
+    /// mined in, or its creation height while that isn't known.
+    pub fn active_height(&self) -> u32 {
+        self.mined_height.unwrap_or(self.height)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn entry(id: u8, height: u32) -> QuorumListEntry {
+        QuorumListEntry::new_extended(vec![id; 32], vec![id; 48], height, Vec::new(), String::new(), 0, 25)
+    }
+
+    fn ids<'a>(entries: impl Iterator<Item = &'a QuorumListEntry>) -> Vec<u8> {
+        entries.map(|entry| entry.quorum_hash[0]).collect()
+    }
+
+    #[test]
+    fn lookups_and_ordering() {
+        let mut list: QuorumList = vec![entry(1, 300), entry(2, 100), entry(3, 200), entry(4, 200)].into();
+        list.add_entry(entry(2, 999)); // duplicate hash, ignored
+
+        assert_eq!(list.len(), 4);
+        assert_eq!(ids(list.iter()), [1, 2, 3, 4]);
+        assert_eq!(list.get_entry(&[2; 32]).unwrap().height, 100);
+        assert!(list.get_entry(&[9; 32]).is_none());
+
+        assert_eq!(ids(list.range_by_height(150..=300)), [3, 4, 1]);
+        assert_eq!(ids(list.range_by_height(..200)), [2]);
+        assert_eq!(ids(list.latest(3)), [1, 3, 4]);
+        assert_eq!(ids(list.latest(10)), [1, 3, 4, 2]);
+        assert_eq!(ids(list.latest_up_to(299, 2)), [3, 4]);
+        assert!(ids(list.latest_up_to(99, 2)).is_empty());
+
+        assert!(list.remove_entry(&[3; 32]));
+        assert!(!list.remove_entry(&[3; 32]));
+        assert_eq!(ids(list.iter()), [1, 2, 4]);
+        assert_eq!(ids(list.range_by_height(200..=200)), [4]);
+
+        list.clear();
+        assert!(list.is_empty());
+        assert_eq!(list.latest(1).count(), 0);
+    }
+
+    #[test]
+    fn diff_between_lists() {
+        let older: QuorumList = vec![entry(1, 100), entry(2, 124), entry(3, 148)].into();
+        let newer: QuorumList = vec![entry(4, 172), entry(2, 124), entry(3, 148), entry(5, 172)].into();
+
+        let (added, removed) = older.diff(&newer);
+        assert_eq!(ids(added.into_iter()), [4, 5]);
+        assert_eq!(ids(removed.into_iter()), [1]);
+        let (added, removed) = newer.diff(&newer);
+        assert!(added.is_empty() && removed.is_empty());
+    }
+
+    #[test]
+    fn serde_format_is_unchanged() {
+        let list: QuorumList = vec![entry(1, 300), entry(2, 100)].into();
+        let value = serde_json::to_value(&list).unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "list": [
+                    {
+                        "quorum_hash": hex::encode([1; 32]),
+                        "key": hex::encode([1; 48]),
+                        "height": 300,
+                        "members": [],
+                        "threshold_signature": "",
+                        "mining_members_count": 0,
+                        "valid_members_count": 25,
+                    },
+                    {
+                        "quorum_hash": hex::encode([2; 32]),
+                        "key": hex::encode([2; 48]),
+                        "height": 100,
+                        "members": [],
+                        "threshold_signature": "",
+                        "mining_members_count": 0,
+                        "valid_members_count": 25,
+                    },
+                ]
+            })
+        );
+
+        let round_trip: QuorumList = serde_json::from_value(value).unwrap();
+        assert_eq!(round_trip, list);
+        assert_eq!(round_trip.get_entry(&[2; 32]).unwrap().height, 100);
+    }
+}
@@ -1,177 +1,254 @@
-use crate::config::Config;
+use crate::config::{Config, LlmqParams};
+use crate::locks::LockQuorums;
 use crate::quorum_list::{QuorumList, QuorumListEntry};
-use dashcore_rpc::{Client, RpcApi, Auth};
+use crate::data_source::CoreDataSource;
 use serde::Deserialize;
-use std::error::Error;
+use serde_json::Value;
+use std::collections::HashMap;
+use crate::error::AppError;
 
-#[derive(Debug, Deserialize)]
-pub struct QuorumListResult {
-    pub quorums: serde_json::Value,
+/// `quorum listextended`: every LLMQ type name maps to a list of
+/// single-entry objects keyed by quorum hash. Only the types being loaded are
+/// parsed further, so an unexpected shape under any other type is ignored.
+pub type ListExtendedResult = HashMap<String, Value>;
+
+/// Quorums picked out of a listextended result, with their hex hashes.
+pub type ListedQuorums = Vec<(String, ListedQuorum)>;
+
+/// The details `quorum listextended` gives for one quorum. Fields that vary
+/// between Dash Core releases (`healthRatio`, ...) are ignored; `quorumIndex`
+/// is only listed for rotated types by some releases.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListedQuorum {
+    #[serde(default)]
+    pub creation_height: u32,
+    #[serde(default)]
+    pub num_valid_members: u32,
+    #[serde(default)]
+    pub quorum_index: u32,
+    /// Hash of the block the quorum's commitment was mined in.
+    #[serde(default)]
+    pub mined_block_hash: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
-pub struct QuorumInfo {
+/// The parts of a `quorum info` response the server uses.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuorumInfoResult {
+    pub height: u32,
+    #[serde(rename = "type")]
+    pub llmq_type: String,
     pub quorum_hash: String,
     pub quorum_public_key: String,
 }
 
-pub async fn load_initial_quorums(
-    config: &Config,
-) -> Result<QuorumList, Box<dyn Error + Send + Sync>> {
-    // Create RPC client
-    let auth = Auth::UserPass(config.rpc.username.clone(), config.rpc.password.clone());
-    let client = Client::new(&config.rpc.url, auth)?;
-    
-    // Get the extended quorum list (returns detailed info for all quorums)
-    let result: serde_json::Value = client.call("quorum", &[serde_json::json!("listextended")])?;
-    
-    let mut quorum_list = QuorumList::new();
-    
-    // Parse the extended quorum list - extract the appropriate LLMQ type based on network
-    let llmq_type = config.get_llmq_type();
-    if let Some(quorum_obj) = result.as_object() {
-        if let Some(llmq_quorums) = quorum_obj.get(llmq_type) {
-            if let Some(quorums_arr) = llmq_quorums.as_array() {
-                for quorum_item in quorums_arr {
-                    if let Some(quorum_obj) = quorum_item.as_object() {
-                        for (quorum_hash_str, quorum_info) in quorum_obj {
-                            if let Some(info_obj) = quorum_info.as_object() {
-                                let quorum_hash_bytes = hex::decode(quorum_hash_str)?;
-                                if quorum_hash_bytes.len() == 32 {
-                                    // Get the actual quorum public key via quorum info
-                                    let rpc_params = [
-                                        serde_json::json!("info"), 
-                                        serde_json::json!(config.get_llmq_type_id()),
-                                        serde_json::json!(quorum_hash_str)
-                                    ];
-                                    println!("DEBUG: Calling quorum with params: {:?}", rpc_params);
-                                    let info_result: serde_json::Value = client.call("quorum", &rpc_params)?;
-                                    
-                                    if let Some(detailed_info) = info_result.as_object() {
-                                        if let Some(pubkey_str) = detailed_info.get("quorumPublicKey").and_then(|v| v.as_str()) {
-                                            let public_key = hex::decode(pubkey_str)?;
-                                            
-                                            if public_key.len() == 48 {
-                                                let creation_height = info_obj.get("creationHeight")
-                                                    .and_then(|v| v.as_u64())
-                                                    .unwrap_or(0) as u32;
-                                                let valid_members_count = info_obj.get("numValidMembers")
-                                                    .and_then(|v| v.as_u64())
-                                                    .unwrap_or(0) as u32;
-                                                
-                                                let entry = QuorumListEntry::new_extended(
-                                                    quorum_hash_bytes,
-                                                    public_key,
-                                                    creation_height,
-                                                    Vec::new(), // No members data needed
-                                                    String::new(), // No threshold signature needed
-                                                    0, // No mining members count needed
-                                                    valid_members_count,
-                                                );
-                                                quorum_list.add_entry(entry);
-                                                println!("Loaded quorum: {} at height {} with {} members", 
-                                                    quorum_hash_str, creation_height, valid_members_count);
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
+/// A quorum that was left out of the list, and why.
+#[derive(Debug)]
+pub struct QuorumEntryError {
+    pub quorum_hash: String,
+    pub error: AppError,
+}
+
+/// The outcome of a load: every quorum that parsed, plus the ones that didn't.
+#[derive(Debug, Default)]
+pub struct ParsedQuorums {
+    pub list: QuorumList,
+    pub errors: Vec<QuorumEntryError>,
+}
+
+/// Pick the quorums of `llmq_type` out of a `quorum listextended` result.
+/// Malformed entries are reported in the second list instead of failing the
+/// whole result; only a response that isn't a listextended map at all is an error.
+pub fn parse_list_extended(
+    result: Value,
+    llmq_type: &str,
+) -> Result<(ListedQuorums, Vec<QuorumEntryError>), AppError> {
+    let mut by_type: ListExtendedResult = serde_json::from_value(result)?;
+    let mut listed = Vec::new();
+    let mut errors = Vec::new();
+
+    let items = match by_type.remove(llmq_type) {
+        None => Vec::new(),
+        Some(Value::Array(items)) => items,
+        Some(other) => {
+            let error = AppError::Upstream(format!("Invalid listextended {} list: {}", llmq_type, other));
+            return Ok((listed, vec![QuorumEntryError { quorum_hash: String::new(), error }]));
+        }
+    };
+
+    for item in items {
+        let item = match item {
+            Value::Object(item) => item,
+            other => {
+                let error = AppError::Upstream(format!("Invalid listextended entry: {}", other));
+                errors.push(QuorumEntryError { quorum_hash: String::new(), error });
+                continue;
+            }
+        };
+        for (quorum_hash, details) in item {
+            let parsed = parse_quorum_hash(&quorum_hash).and_then(|_| {
+                serde_json::from_value::<ListedQuorum>(details).map_err(|e| {
+                    AppError::Upstream(format!("Invalid listextended entry: {}", e))
+                })
+            });
+            match parsed {
+                Ok(quorum) => listed.push((quorum_hash, quorum)),
+                Err(error) => errors.push(QuorumEntryError { quorum_hash, error }),
             }
         }
     }
-    
-    println!("Loaded {} quorums from Dash Core", quorum_list.len());
-    Ok(quorum_list)
+
+    Ok((listed, errors))
+}
+
+/// Combine a listed quorum with its `quorum info` response into a list entry,
+/// checking that both describe the same quorum.
+pub fn build_entry(
+    quorum_hash: &str,
+    listed: &ListedQuorum,
+    info: Value,
+    llmq_type: &str,
+) -> Result<QuorumListEntry, AppError> {
+    let info: QuorumInfoResult = serde_json::from_value(info)
+        .map_err(|e| AppError::Upstream(format!("Invalid quorum info: {}", e)))?;
+
+    if !info.quorum_hash.eq_ignore_ascii_case(quorum_hash) {
+        return Err(AppError::VerificationFailed(format!(
+            "quorum info returned quorum {}",
+            info.quorum_hash
+        )));
+    }
+    if info.llmq_type != llmq_type {
+        return Err(AppError::VerificationFailed(format!(
+            "quorum info returned LLMQ type {}, expected {}",
+            info.llmq_type, llmq_type
+        )));
+    }
+    if info.height != listed.creation_height {
+        return Err(AppError::VerificationFailed(format!(
+            "quorum info height {} does not match listextended creationHeight {}",
+            info.height, listed.creation_height
+        )));
+    }
+
+    let public_key = hex::decode(&info.quorum_public_key)?;
+    if public_key.len() != 48 {
+        return Err(AppError::Upstream(format!(
+            "Quorum public key is {} bytes, expected 48",
+            public_key.len()
+        )));
+    }
+
+    let mut entry = QuorumListEntry::new_extended(
+        parse_quorum_hash(quorum_hash)?,
+        public_key,
+        listed.creation_height,
+        Vec::new(), // No members data needed
+        String::new(), // No threshold signature needed
+        0, // No mining members count needed
+        listed.num_valid_members,
+    );
+    entry.quorum_index = listed.quorum_index;
+    // Only quorum history uses it; not worth dropping the quorum over
+    entry.mined_block_hash =
+        listed.mined_block_hash.as_deref().and_then(|hash| hex::decode(hash).ok()).unwrap_or_default();
+    Ok(entry)
+}
+
+fn parse_quorum_hash(quorum_hash: &str) -> Result<Vec<u8>, AppError> {
+    let bytes = hex::decode(quorum_hash)?;
+    if bytes.len() != 32 {
+        return Err(AppError::Upstream(format!("Quorum hash is {} bytes, expected 32", bytes.len())));
+    }
+    Ok(bytes)
 }
 
-pub async fn get_current_block_height(
+/// Load the quorums of the configured LLMQ type, at the chain tip or at `height`.
+/// Quorums that fail to load are collected in `errors`; the load as a whole
+/// only fails if the data source itself can't be reached.
+pub async fn fetch_quorums(
+    source: &dyn CoreDataSource,
     config: &Config,
-) -> Result<u32, Box<dyn Error + Send + Sync>> {
-    let auth = Auth::UserPass(config.rpc.username.clone(), config.rpc.password.clone());
-    let client = Client::new(&config.rpc.url, auth)?;
-    
-    let result: serde_json::Value = client.call("getblockcount", &[])?;
-    let height = result.as_u64().ok_or("Invalid block count response")? as u32;
-    
-    Ok(height)
+    height: Option<u32>,
+) -> Result<ParsedQuorums, AppError> {
+    let list_extended = source.list_quorums(height).await?;
+    fetch_listed_quorums(source, list_extended, config.get_llmq_type(), config.get_llmq_type_id()).await
 }
 
+/// Load the quorums of `llmq_type` out of a `quorum listextended` result,
+/// looking up each one's public key with `quorum info`.
+async fn fetch_listed_quorums(
+    source: &dyn CoreDataSource,
+    list_extended: Value,
+    llmq_type: &str,
+    llmq_type_id: u32,
+) -> Result<ParsedQuorums, AppError> {
+    let (listed, mut errors) = parse_list_extended(list_extended, llmq_type)?;
+
+    let mut list = QuorumList::new();
+    for (quorum_hash, quorum) in listed {
+        // Get the actual quorum public key via quorum info
+        let entry = match source.quorum_info(llmq_type_id, &quorum_hash).await {
+            Ok(info) => build_entry(&quorum_hash, &quorum, info, llmq_type),
+            Err(e @ (AppError::RpcUnavailable(_) | AppError::UpstreamTimeout(_))) => return Err(e),
+            Err(e) => Err(e),
+        };
+        match entry {
+            Ok(entry) => list.add_entry(entry),
+            Err(error) => errors.push(QuorumEntryError { quorum_hash, error }),
+        }
+    }
+
+    Ok(ParsedQuorums { list, errors })
+}
+
+/// Load the quorums at `height`, logging the ones that had to be skipped.
 pub async fn load_quorums_at_height(
+    source: &dyn CoreDataSource,
     config: &Config,
     height: u32,
-) -> Result<QuorumList, Box<dyn Error + Send + Sync>> {
-    let auth = Auth::UserPass(config.rpc.username.clone(), config.rpc.password.clone());
-    let client = Client::new(&config.rpc.url, auth)?;
-    
-    // Get the extended quorum list at specific height
-    let result: serde_json::Value = client.call("quorum", &[
-        serde_json::json!("listextended"),
-        serde_json::json!(height)
-    ])?;
-    
-    let mut quorum_list = QuorumList::new();
-    
-    // Parse the extended quorum list - extract the appropriate LLMQ type based on network
-    let llmq_type = config.get_llmq_type();
-    if let Some(quorum_obj) = result.as_object() {
-        if let Some(llmq_quorums) = quorum_obj.get(llmq_type) {
-            if let Some(quorums_arr) = llmq_quorums.as_array() {
-                for quorum_item in quorums_arr {
-                    if let Some(quorum_obj) = quorum_item.as_object() {
-                        for (quorum_hash_str, quorum_info) in quorum_obj {
-                            if let Some(info_obj) = quorum_info.as_object() {
-                                let quorum_hash_bytes = hex::decode(quorum_hash_str)?;
-                                if quorum_hash_bytes.len() == 32 {
-                                    // Get the actual quorum public key via quorum info
-                                    let rpc_params = [
-                                        serde_json::json!("info"), 
-                                        serde_json::json!(config.get_llmq_type_id()),
-                                        serde_json::json!(quorum_hash_str),
-                                        serde_json::json!(true) // includeSkShare
-                                    ];
-                                    println!("DEBUG: Calling quorum with params: {:?}", rpc_params);
-                                    let info_result: serde_json::Value = client.call("quorum", &rpc_params)?;
-                                    
-                                    if let Some(detailed_info) = info_result.as_object() {
-                                        if let Some(pubkey_str) = detailed_info.get("quorumPublicKey").and_then(|v| v.as_str()) {
-                                            let public_key = hex::decode(pubkey_str)?;
-                                            
-                                            if public_key.len() == 48 {
-                                                let creation_height = info_obj.get("creationHeight")
-                                                    .and_then(|v| v.as_u64())
-                                                    .unwrap_or(0) as u32;
-                                                let valid_members_count = info_obj.get("numValidMembers")
-                                                    .and_then(|v| v.as_u64())
-                                                    .unwrap_or(0) as u32;
-                                                
-                                                let entry = QuorumListEntry::new_extended(
-                                                    quorum_hash_bytes,
-                                                    public_key,
-                                                    creation_height,
-                                                    Vec::new(), // No members data needed
-                                                    String::new(), // No threshold signature needed
-                                                    0, // No mining members count needed
-                                                    valid_members_count,
-                                                );
-                                                quorum_list.add_entry(entry);
-                                                println!("Loaded quorum at height {}: {} with {} members", 
-                                                    height, quorum_hash_str, valid_members_count);
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
+) -> Result<QuorumList, AppError> {
+    let parsed = fetch_quorums(source, config, Some(height)).await?;
+    for skipped in &parsed.errors {
+        eprintln!("Skipping quorum {}: {}", skipped.quorum_hash, skipped.error);
+    }
+    println!("Loaded {} quorums from Dash Core at height {}", parsed.list.len(), height);
+    Ok(parsed.list)
+}
+
+/// Load the quorums at `height` and, when locks are verified, the ChainLock
+/// and InstantSend quorums too, all from one `quorum listextended` call.
+pub async fn load_with_lock_quorums_at_height(
+    source: &dyn CoreDataSource,
+    config: &Config,
+    height: u32,
+) -> Result<(QuorumList, Option<LockQuorums>), AppError> {
+    if !config.quorum.verify_locks {
+        return Ok((load_quorums_at_height(source, config, height).await?, None));
+    }
+    let list_extended = source.list_quorums(Some(height)).await?;
+    let list = load_listed_quorums(source, list_extended.clone(), config.network.platform_llmq(), height).await?;
+    let locks = LockQuorums {
+        chainlock: load_listed_quorums(source, list_extended.clone(), config.network.chainlock_llmq(), height).await?,
+        instantsend: load_listed_quorums(source, list_extended, config.network.instantsend_llmq(), height).await?,
+    };
+    Ok((list, Some(locks)))
+}
+
+async fn load_listed_quorums(
+    source: &dyn CoreDataSource,
+    list_extended: Value,
+    llmq: LlmqParams,
+    height: u32,
+) -> Result<QuorumList, AppError> {
+    let parsed = fetch_listed_quorums(source, list_extended, llmq.name, llmq.id).await?;
+    for skipped in &parsed.errors {
+        eprintln!("Skipping {} quorum {}: {}", llmq.name, skipped.quorum_hash, skipped.error);
     }
-    
-    println!("Loaded {} quorums from Dash Core at height {}", quorum_list.len(), height);
-    Ok(quorum_list)
+    println!("Loaded {} {} quorums from Dash Core at height {}", parsed.list.len(), llmq.name, height);
+    Ok(parsed.list)
 }
 
+#[cfg(test)]
+mod tests;
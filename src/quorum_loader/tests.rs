@@ -0,0 +1,156 @@
+use super::*;
+use crate::data_source::{FixtureDataSource, RpcDataSource};
+use crate::rpc::Exchange;
+use crate::test_support::test_config;
+use serde_json::json;
+use std::path::{Path, PathBuf};
+
+fn fixture_path(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/rpc")
+        .join(format!("{}.json", name))
+}
+
+async fn fetch_fixture(name: &str) -> ParsedQuorums {
+    let source = FixtureDataSource::load(fixture_path(name)).unwrap();
+    fetch_quorums(&source, &test_config("http://127.0.0.1:1"), None).await.unwrap()
+}
+
+/// The public key each `quorum info` exchange in a fixture returns, by quorum hash.
+fn fixture_keys(name: &str) -> HashMap<String, String> {
+    let exchanges: Vec<Exchange> =
+        serde_json::from_str(&std::fs::read_to_string(fixture_path(name)).unwrap()).unwrap();
+    exchanges
+        .into_iter()
+        .filter(|exchange| exchange.params.first() == Some(&json!("info")))
+        .filter_map(|exchange| {
+            let key = exchange.result.get("quorumPublicKey")?.as_str()?.to_string();
+            Some((exchange.params[2].as_str()?.to_string(), key))
+        })
+        .collect()
+}
+
+fn summary(parsed: &ParsedQuorums) -> Vec<(String, u32, u32)> {
+    parsed
+        .list
+        .iter()
+        .map(|entry| (hex::encode(&entry.quorum_hash), entry.height, entry.valid_members_count))
+        .collect()
+}
+
+#[tokio::test]
+async fn parses_each_dash_core_version() {
+    let versions = [
+        // quorumIndex on every entry, healthRatio as a string
+        ("core-v18", [
+            "0000009676f761275ee0efa73b537c4efc7a0bf3b2b2a919301c78d493d1c54e",
+            "0000004f2a93165aa27b88230dfa3b4afcf75b67aa37e83079d489523ec5e9c8",
+            "0000007387fb59f608a97a01cb551ab8ec0b8fa236853e55e2d7acd4309ee138",
+        ]),
+        // healthRatio as a number, members carry memberIndex
+        ("core-v19", [
+            "000000fc078f7ec47a52610f6b7a9d9a3c0e9d5f89c5b12c5e8a0b8b97f48e3a",
+            "000000df63123eb375c85a0d43110870657d15cd4129994cc0bede7b7dc8c787",
+            "00000047bb062a01816ca6711f6da4f22c8c035335c0da870bd52cfd94ee5da9",
+        ]),
+        // quorumIndex only on rotated types, extra fields in quorum info
+        ("core-v20", [
+            "000000c60dd63f9fbcf9544a7235ed8de00dbe29ed1df1cbeb9f787d43dcb2b2",
+            "00000049037dc729f06b07705128ae905edc85aa2467c52c218a0147a55b74d5",
+            "000000befbb5fd09b0a103af56763e35a968afe2571dfe25f5b460def0929919",
+        ]),
+    ];
+
+    for (fixture, hashes) in versions {
+        let parsed = fetch_fixture(fixture).await;
+        assert!(parsed.errors.is_empty(), "{}: {:?}", fixture, parsed.errors);
+        assert_eq!(
+            summary(&parsed),
+            vec![
+                (hashes[0].to_string(), 1100016, 25),
+                (hashes[1].to_string(), 1099992, 24),
+                (hashes[2].to_string(), 1099968, 23),
+            ],
+            "{}",
+            fixture
+        );
+
+        let keys = fixture_keys(fixture);
+        for entry in parsed.list.iter() {
+            assert_eq!(hex::encode(&entry.key), keys[&hex::encode(&entry.quorum_hash)], "{}", fixture);
+        }
+    }
+}
+
+#[tokio::test]
+async fn malformed_entries_are_reported_without_dropping_the_list() {
+    let parsed = fetch_fixture("quorums-malformed").await;
+
+    assert_eq!(
+        summary(&parsed),
+        vec![("0000008f55fc621105d2a400d757a4f65f2d645a23ee2349d26c85ac77af5ded".to_string(), 1100016, 25)]
+    );
+
+    let errors: HashMap<&str, &str> = parsed
+        .errors
+        .iter()
+        .map(|skipped| (skipped.quorum_hash.as_str(), skipped.error.code()))
+        .collect();
+    assert_eq!(
+        errors,
+        HashMap::from([
+            ("not-hex", "upstream_error"),
+            // the good hash with its last byte cut off
+            ("0000008f55fc621105d2a400d757a4f65f2d645a23ee2349d26c85ac77af5d", "upstream_error"),
+            ("000000cbe828e5ed32aa10c2472294a60c8d1f0f4e3e2bd7193a8db8ec0fdc14", "upstream_error"),
+            ("0000001958c54144b62c3d074e7083244322835198bfd9aad5e14d04cb975540", "upstream_error"),
+            ("000000270ff0a5504991d651be21e488768c7fae06467760109fa870b5d256e3", "upstream_error"),
+            ("0000006e0808a5e1d5a99a629846490bcd6f62ffecee5418456aad87884b5f87", "upstream_error"),
+            ("000000907b59fbf045ba1b105db70b2aa1ca6575ecb9e63e0b0082b357b31cbe", "verification_failed"),
+            ("000000402f81cb107d56e41aa3d22acefc25b11f416ef5403f438480d1ef93c8", "verification_failed"),
+            ("000000cb0796ee29528f6ec4dbc394bf4103ba0b0d95d3a17cd06e2a960430e8", "verification_failed"),
+        ])
+    );
+}
+
+#[test]
+fn other_llmq_types_and_missing_types_are_ignored() {
+    let result = json!({
+        "llmq_50_60": [{ "0000001934fdd9cfdd6d0e892cb6ca4894abc58559de0ec04d51bc2801bad2a1": { "creationHeight": 1 } }],
+    });
+    let (listed, errors) = parse_list_extended(result, "llmq_25_67").unwrap();
+    assert!(listed.is_empty());
+    assert!(errors.is_empty());
+}
+
+#[test]
+fn unexpected_shapes_only_affect_their_own_llmq_type() {
+    let quorum = "0000008f55fc621105d2a400d757a4f65f2d645a23ee2349d26c85ac77af5ded";
+    let result = json!({
+        "llmq_50_60": { "not": "a list" },
+        "llmq_400_60": ["not an object", 7],
+        "llmq_25_67": [{ quorum: { "creationHeight": 1100016 } }, "not an object"],
+    });
+    let (listed, errors) = parse_list_extended(result, "llmq_25_67").unwrap();
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0].0, quorum);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].error.code(), "upstream_error");
+
+    let (listed, errors) = parse_list_extended(json!({ "llmq_25_67": 5 }), "llmq_25_67").unwrap();
+    assert!(listed.is_empty());
+    assert_eq!(errors.len(), 1);
+}
+
+#[test]
+fn response_that_is_not_a_listextended_map_is_an_error() {
+    let error = parse_list_extended(json!(["not", "a", "map"]), "llmq_25_67").unwrap_err();
+    assert_eq!(error.code(), "upstream_error");
+}
+
+#[tokio::test]
+async fn unreachable_source_fails_the_load() {
+    let config = test_config("http://127.0.0.1:1");
+    let error = fetch_quorums(&RpcDataSource::new(config.clone()), &config, None).await.unwrap_err();
+    assert_eq!(error.code(), "rpc_unavailable");
+}
@@ -0,0 +1,543 @@
+//! Content negotiation between JSON and the binary response formats.
+//!
+//! The binary formats all encode the messages in
+//! `proto/quorum_list_server.proto`, which carry hashes and keys as raw
+//! bytes instead of hex strings.
+
+use crate::api::{
+    ApiResponse, BlockHash, MasternodeDiffResponse, MasternodesAtHeightResponse, NoData, QuorumDiffResponse,
+    QuorumEntryResponse, QuorumStats, QuorumsAtHeightResponse, SignatureVerification, SignedQuorumListResponse,
+    SigningKeyResponse,
+};
+use crate::cached_response::parse_quality_list;
+use crate::health::{CoreSyncStatus, FreshnessCheck, ReadinessReport};
+use crate::locks::LockVerification;
+use crate::quorum_history::QuorumLifecycle;
+use crate::masternode::{EvoMasternodeInfo, EvoMasternodeState, MasternodeUpdate};
+use crate::masternode_history::MasternodeEvent;
+use crate::dapi_addresses::DapiAddress;
+use crate::probe_history::{NodeProbeHistory, Uptime};
+use axum::body::Bytes;
+use axum::extract::Request;
+use axum::http::{header, HeaderMap};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use prost::Message;
+use serde::Serialize;
+
+pub mod proto {
+    tonic::include_proto!("quorum_list_server.v1");
+}
+
+/// A response body format a client can ask for with `Accept`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResponseFormat {
+    Json,
+    Cbor,
+    Bincode,
+    Protobuf,
+}
+
+impl ResponseFormat {
+    /// All formats, JSON first: it wins when a client accepts several equally.
+    pub const ALL: [ResponseFormat; 4] =
+        [ResponseFormat::Json, ResponseFormat::Cbor, ResponseFormat::Bincode, ResponseFormat::Protobuf];
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            ResponseFormat::Json => "application/json",
+            ResponseFormat::Cbor => "application/cbor",
+            ResponseFormat::Bincode => "application/x-bincode",
+            ResponseFormat::Protobuf => "application/x-protobuf",
+        }
+    }
+
+    /// Pick the format for a request from its `Accept` header. Wildcards and
+    /// unsupported types fall back to JSON.
+    pub fn negotiate(headers: &HeaderMap) -> Self {
+        let Some(accept) = headers.get(header::ACCEPT).and_then(|value| value.to_str().ok()) else {
+            return ResponseFormat::Json;
+        };
+
+        let mut best = (ResponseFormat::Json, 0.0);
+        for (media_type, quality) in parse_quality_list(accept) {
+            let format = Self::ALL.into_iter().find(|format| format.content_type() == media_type);
+            if let Some(format) = format {
+                if quality > best.1 {
+                    best = (format, quality);
+                }
+            }
+        }
+        best.0
+    }
+
+    /// The format negotiated for the request being handled; JSON outside of
+    /// the `negotiate_format` middleware.
+    pub fn current() -> Self {
+        RESPONSE_FORMAT.try_with(|format| *format).unwrap_or(ResponseFormat::Json)
+    }
+}
+
+tokio::task_local! {
+    static RESPONSE_FORMAT: ResponseFormat;
+}
+
+/// Middleware making the negotiated format available to responses built
+/// while handling the request, through `ResponseFormat::current`.
+pub async fn negotiate_format(request: Request, next: Next) -> Response {
+    let format = ResponseFormat::negotiate(request.headers());
+    RESPONSE_FORMAT.scope(format, next.run(request)).await
+}
+
+/// Response data with a representation in the binary schema.
+pub trait IntoWire {
+    fn into_wire(self) -> Option<proto::response::Data>;
+}
+
+/// Serialize an API response in `format`.
+pub fn encode<T: Serialize + IntoWire>(format: ResponseFormat, response: ApiResponse<T>) -> Bytes {
+    let encoded = match format {
+        ResponseFormat::Json => serde_json::to_vec(&response).expect("API responses always serialize"),
+        ResponseFormat::Cbor => {
+            let mut encoded = Vec::new();
+            ciborium::into_writer(&response.into_wire(), &mut encoded)
+                .expect("API responses always serialize");
+            encoded
+        }
+        ResponseFormat::Bincode => bincode::encode_to_vec(response.into_wire(), bincode::config::standard())
+            .expect("API responses always serialize"),
+        ResponseFormat::Protobuf => response.into_wire().encode_to_vec(),
+    };
+    encoded.into()
+}
+
+impl<T: IntoWire> ApiResponse<T> {
+    fn into_wire(self) -> proto::Response {
+        proto::Response {
+            success: self.success,
+            message: self.message,
+            code: self.code.map(str::to_string),
+            anchor: self.anchor.map(|anchor| proto::BlockAnchor {
+                height: anchor.height,
+                block_hash: raw(&anchor.block_hash),
+                chainlock_signature: anchor.chainlock_signature.as_deref().map(raw),
+            }),
+            data: self.data.and_then(IntoWire::into_wire),
+        }
+    }
+}
+
+impl<T: Serialize + IntoWire> IntoResponse for ApiResponse<T> {
+    fn into_response(self) -> Response {
+        let format = ResponseFormat::current();
+        (
+            [(header::CONTENT_TYPE, format.content_type()), (header::VARY, "accept")],
+            encode(format, self),
+        )
+            .into_response()
+    }
+}
+
+/// Decode a hex string produced from raw bytes; anything else is sent empty.
+fn raw(hex_string: &str) -> Vec<u8> {
+    hex::decode(hex_string).unwrap_or_default()
+}
+
+impl IntoWire for NoData {
+    fn into_wire(self) -> Option<proto::response::Data> {
+        None
+    }
+}
+
+impl IntoWire for String {
+    fn into_wire(self) -> Option<proto::response::Data> {
+        Some(proto::response::Data::Text(self))
+    }
+}
+
+impl IntoWire for u32 {
+    fn into_wire(self) -> Option<proto::response::Data> {
+        Some(proto::response::Data::BlockCount(self))
+    }
+}
+
+impl IntoWire for BlockHash {
+    fn into_wire(self) -> Option<proto::response::Data> {
+        Some(proto::response::Data::BlockHash(raw(&self.0)))
+    }
+}
+
+impl IntoWire for serde_json::Value {
+    fn into_wire(self) -> Option<proto::response::Data> {
+        Some(proto::response::Data::CoreJson(self.to_string().into_bytes()))
+    }
+}
+
+impl From<QuorumEntryResponse> for proto::Quorum {
+    fn from(quorum: QuorumEntryResponse) -> Self {
+        Self {
+            quorum_hash: raw(&quorum.quorum_hash),
+            key: raw(&quorum.key),
+            height: quorum.height,
+            members: quorum
+                .members
+                .into_iter()
+                .map(|member| proto::QuorumMember {
+                    pro_tx_hash: raw(&member.proTxHash),
+                    pub_key_operator: raw(&member.pubKeyOperator),
+                    valid: member.valid,
+                    is_public_key_share: member.isPublicKeyShare,
+                })
+                .collect(),
+            threshold_signature: raw(&quorum.threshold_signature),
+            mining_members_count: quorum.mining_members_count,
+            valid_members_count: quorum.valid_members_count,
+        }
+    }
+}
+
+impl IntoWire for QuorumEntryResponse {
+    fn into_wire(self) -> Option<proto::response::Data> {
+        Some(proto::response::Data::Quorum(self.into()))
+    }
+}
+
+impl IntoWire for Vec<QuorumEntryResponse> {
+    fn into_wire(self) -> Option<proto::response::Data> {
+        let quorums = self.into_iter().map(Into::into).collect();
+        Some(proto::response::Data::Quorums(proto::QuorumList { quorums }))
+    }
+}
+
+impl IntoWire for QuorumStats {
+    fn into_wire(self) -> Option<proto::response::Data> {
+        Some(proto::response::Data::QuorumStats(proto::QuorumStats {
+            total_count: self.total_count as u64,
+            is_empty: self.is_empty,
+        }))
+    }
+}
+
+impl IntoWire for QuorumsAtHeightResponse {
+    fn into_wire(self) -> Option<proto::response::Data> {
+        Some(proto::response::Data::QuorumsAtHeight(proto::QuorumsAtHeight {
+            height: self.height,
+            quorums: self.quorums.into_iter().map(Into::into).collect(),
+        }))
+    }
+}
+
+impl IntoWire for QuorumDiffResponse {
+    fn into_wire(self) -> Option<proto::response::Data> {
+        Some(proto::response::Data::QuorumDiff(proto::QuorumDiff {
+            base_height: self.base_height,
+            height: self.height,
+            llmq_types: self
+                .llmq_types
+                .into_iter()
+                .map(|diff| proto::QuorumTypeDiff {
+                    llmq_type: diff.llmq_type,
+                    added: diff.added.into_iter().map(Into::into).collect(),
+                    removed: diff.removed.into_iter().map(Into::into).collect(),
+                })
+                .collect(),
+        }))
+    }
+}
+
+impl IntoWire for SignedQuorumListResponse {
+    fn into_wire(self) -> Option<proto::response::Data> {
+        Some(proto::response::Data::SignedQuorums(proto::SignedQuorumList {
+            height: self.height,
+            block_hash: raw(&self.block_hash),
+            algorithm: self.algorithm.to_string(),
+            signature: raw(&self.signature),
+            quorums: self.quorums.into_iter().map(Into::into).collect(),
+        }))
+    }
+}
+
+impl IntoWire for SigningKeyResponse {
+    fn into_wire(self) -> Option<proto::response::Data> {
+        Some(proto::response::Data::SigningKey(proto::SigningKey {
+            algorithm: self.algorithm.to_string(),
+            public_key: raw(&self.public_key),
+        }))
+    }
+}
+
+impl IntoWire for SignatureVerification {
+    fn into_wire(self) -> Option<proto::response::Data> {
+        Some(proto::response::Data::SignatureVerification(proto::SignatureVerification {
+            verified: self.verified,
+            llmq_type: self.llmq_type,
+            quorum_hash: raw(&self.quorum_hash),
+            sign_hash: raw(&self.sign_hash),
+        }))
+    }
+}
+
+impl IntoWire for LockVerification {
+    fn into_wire(self) -> Option<proto::response::Data> {
+        Some(proto::response::Data::LockVerification(proto::LockVerification {
+            verified: self.verified,
+            llmq_type: self.llmq_type,
+            quorum_hash: self.quorum_hash.as_deref().map(raw),
+            request_id: raw(&self.request_id),
+            sign_hash: self.sign_hash.as_deref().map(raw),
+            reason: self.reason,
+        }))
+    }
+}
+
+impl From<QuorumLifecycle> for proto::QuorumLifecycle {
+    fn from(quorum: QuorumLifecycle) -> Self {
+        Self {
+            quorum_hash: raw(&quorum.quorum_hash),
+            llmq_type: quorum.llmq_type,
+            key: raw(&quorum.key),
+            creation_height: quorum.creation_height,
+            mined_height: quorum.mined_height,
+            first_seen_height: quorum.first_seen_height,
+            last_active_height: quorum.last_active_height,
+            retired_height: quorum.retired_height,
+        }
+    }
+}
+
+impl IntoWire for QuorumLifecycle {
+    fn into_wire(self) -> Option<proto::response::Data> {
+        Some(proto::response::Data::QuorumLifecycle(self.into()))
+    }
+}
+
+impl IntoWire for Vec<QuorumLifecycle> {
+    fn into_wire(self) -> Option<proto::response::Data> {
+        let quorums = self.into_iter().map(Into::into).collect();
+        Some(proto::response::Data::QuorumHistory(proto::QuorumHistory { quorums }))
+    }
+}
+
+impl IntoWire for Vec<EvoMasternodeInfo> {
+    fn into_wire(self) -> Option<proto::response::Data> {
+        let masternodes = self
+            .into_iter()
+            .map(|masternode| proto::Masternode {
+                pro_tx_hash: raw(&masternode.pro_tx_hash),
+                address: masternode.address,
+                status: masternode.status,
+                version_check: masternode.version_check,
+                dapi_version: masternode.dapi_version,
+                drive_version: masternode.drive_version,
+            })
+            .collect();
+        Some(proto::response::Data::Masternodes(proto::MasternodeList { masternodes }))
+    }
+}
+
+impl From<EvoMasternodeState> for proto::EvoMasternodeState {
+    fn from(masternode: EvoMasternodeState) -> Self {
+        Self {
+            pro_tx_hash: raw(&masternode.pro_tx_hash),
+            address: masternode.address,
+            status: masternode.status,
+            registered_height: masternode.registered_height,
+            pose_penalty: masternode.pose_penalty,
+            pose_ban_height: masternode.pose_ban_height,
+            pose_revived_height: masternode.pose_revived_height,
+            platform_node_id: masternode.platform_node_id.as_deref().map(raw),
+            platform_http_port: masternode.platform_http_port.map(u32::from),
+        }
+    }
+}
+
+impl From<MasternodeUpdate> for proto::MasternodeUpdate {
+    fn from(update: MasternodeUpdate) -> Self {
+        Self {
+            pro_tx_hash: raw(&update.pro_tx_hash),
+            changes_json: serde_json::Value::Object(update.changes).to_string().into_bytes(),
+        }
+    }
+}
+
+impl IntoWire for MasternodesAtHeightResponse {
+    fn into_wire(self) -> Option<proto::response::Data> {
+        Some(proto::response::Data::MasternodesAtHeight(proto::MasternodesAtHeight {
+            height: self.height,
+            masternodes: self.masternodes.into_iter().map(Into::into).collect(),
+        }))
+    }
+}
+
+impl IntoWire for MasternodeDiffResponse {
+    fn into_wire(self) -> Option<proto::response::Data> {
+        Some(proto::response::Data::MasternodeDiff(proto::MasternodeDiff {
+            base_height: self.base_height,
+            height: self.height,
+            added: self.added.into_iter().map(Into::into).collect(),
+            removed: self.removed.into_iter().map(Into::into).collect(),
+            updated: self.updated.into_iter().map(Into::into).collect(),
+        }))
+    }
+}
+
+impl IntoWire for Vec<MasternodeEvent> {
+    fn into_wire(self) -> Option<proto::response::Data> {
+        let events = self
+            .into_iter()
+            .map(|event| proto::MasternodeEvent {
+                height: event.height,
+                pro_tx_hash: raw(&event.pro_tx_hash),
+                kind: event.kind.as_str().to_string(),
+                changes_json: serde_json::Value::Object(event.changes).to_string().into_bytes(),
+            })
+            .collect();
+        Some(proto::response::Data::MasternodeHistory(proto::MasternodeHistory { events }))
+    }
+}
+
+impl From<Uptime> for proto::Uptime {
+    fn from(uptime: Uptime) -> Self {
+        Self {
+            last_hour: uptime.last_hour,
+            last_day: uptime.last_day,
+            last_week: uptime.last_week,
+        }
+    }
+}
+
+impl IntoWire for NodeProbeHistory {
+    fn into_wire(self) -> Option<proto::response::Data> {
+        Some(proto::response::Data::ProbeHistory(proto::NodeProbeHistory {
+            pro_tx_hash: raw(&self.pro_tx_hash),
+            uptime: Some(self.uptime.into()),
+            probes: self
+                .probes
+                .into_iter()
+                .map(|probe| proto::ProbeResult {
+                    timestamp: probe.timestamp,
+                    outcome: probe.outcome.as_str().to_string(),
+                    latency_ms: probe.latency_ms,
+                    dapi_version: probe.dapi_version,
+                    drive_version: probe.drive_version,
+                })
+                .collect(),
+        }))
+    }
+}
+
+impl IntoWire for Vec<DapiAddress> {
+    fn into_wire(self) -> Option<proto::response::Data> {
+        let addresses = self
+            .into_iter()
+            .map(|address| proto::DapiAddress {
+                pro_tx_hash: raw(&address.pro_tx_hash),
+                uri: address.uri,
+                host: address.host,
+                port: u32::from(address.port),
+                latency_ms: address.latency_ms,
+                uptime: Some(address.uptime.into()),
+            })
+            .collect();
+        Some(proto::response::Data::DapiAddresses(proto::DapiAddressList { addresses }))
+    }
+}
+
+impl From<CoreSyncStatus> for proto::CoreSyncStatus {
+    fn from(status: CoreSyncStatus) -> Self {
+        Self {
+            chain: status.chain,
+            blocks: status.blocks,
+            headers: status.headers,
+            verification_progress: status.verification_progress,
+            initial_block_download: status.initial_block_download,
+            blockchain_synced: status.blockchain_synced,
+            masternode_sync_asset: status.masternode_sync_asset,
+            masternode_sync_finished: status.masternode_sync_finished,
+            bls_legacy_scheme: status.bls_legacy_scheme,
+        }
+    }
+}
+
+impl IntoWire for CoreSyncStatus {
+    fn into_wire(self) -> Option<proto::response::Data> {
+        Some(proto::response::Data::CoreStatus(self.into()))
+    }
+}
+
+impl From<FreshnessCheck> for proto::FreshnessCheck {
+    fn from(check: FreshnessCheck) -> Self {
+        Self {
+            last_refresh_age_secs: check.last_refresh_age_secs,
+            max_age_secs: check.max_age_secs,
+            fresh: check.fresh,
+        }
+    }
+}
+
+impl IntoWire for ReadinessReport {
+    fn into_wire(self) -> Option<proto::response::Data> {
+        Some(proto::response::Data::Readiness(proto::ReadinessReport {
+            ready: self.ready,
+            rpc_reachable: self.rpc_reachable,
+            core: self.core.map(Into::into),
+            quorum_count: self.quorum_count as u64,
+            quorums: Some(self.quorums.into()),
+            masternodes: Some(self.masternodes.into()),
+            problems: self.problems,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn negotiate(accept: &str) -> ResponseFormat {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::ACCEPT, HeaderValue::from_str(accept).unwrap());
+        ResponseFormat::negotiate(&headers)
+    }
+
+    #[test]
+    fn negotiation_prefers_json_unless_a_binary_format_is_asked_for() {
+        assert_eq!(ResponseFormat::negotiate(&HeaderMap::new()), ResponseFormat::Json);
+        assert_eq!(negotiate("*/*"), ResponseFormat::Json);
+        assert_eq!(negotiate("text/html, application/xml;q=0.9"), ResponseFormat::Json);
+        assert_eq!(negotiate("application/cbor"), ResponseFormat::Cbor);
+        assert_eq!(negotiate("application/x-bincode, */*;q=0.1"), ResponseFormat::Bincode);
+        assert_eq!(negotiate("application/json;q=0.5, application/x-protobuf"), ResponseFormat::Protobuf);
+        assert_eq!(negotiate("application/json, application/cbor"), ResponseFormat::Json);
+    }
+
+    #[test]
+    fn formats_encode_the_same_message() {
+        let response = ApiResponse::success(BlockHash("00".repeat(31) + "ff"));
+        let mut expected_hash = vec![0u8; 32];
+        expected_hash[31] = 0xff;
+
+        let decoded = proto::Response::decode(encode(ResponseFormat::Protobuf, response.clone())).unwrap();
+        assert!(decoded.success);
+        assert_eq!(decoded.data, Some(proto::response::Data::BlockHash(expected_hash.clone())));
+
+        let bincode = encode(ResponseFormat::Bincode, response.clone());
+        assert_eq!(
+            bincode.to_vec(),
+            bincode::encode_to_vec(&decoded, bincode::config::standard()).unwrap()
+        );
+
+        let cbor: ciborium::Value = ciborium::from_reader(&encode(ResponseFormat::Cbor, response)[..]).unwrap();
+        let data = cbor
+            .as_map()
+            .unwrap()
+            .iter()
+            .find(|(key, _)| key.as_text() == Some("data"))
+            .map(|(_, value)| value.clone())
+            .unwrap();
+        assert_eq!(
+            data,
+            ciborium::Value::Map(vec![("block_hash".into(), ciborium::Value::Bytes(expected_hash))])
+        );
+    }
+}
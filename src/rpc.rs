@@ -0,0 +1,398 @@
+//! Dash Core RPC access, optionally recording the traffic to a cassette file
+//! or replaying a previously recorded one instead of contacting Dash Core.
+
+use crate::config::{Config, RpcConfig, RpcMode};
+use crate::error::AppError;
+use dashcore_rpc::{Auth, Client, RpcApi};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// One RPC call and the response Dash Core gave to it.
+///
+/// A cassette is a JSON array of these, the same format as the scripted
+/// fixtures in `tests/fixtures/rpc/`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Exchange {
+    pub method: String,
+    #[serde(default)]
+    pub params: Vec<Value>,
+    #[serde(default)]
+    pub result: Value,
+    /// When set, the call failed with this JSON-RPC error instead of returning `result`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<Value>,
+}
+
+impl Exchange {
+    /// The recorded outcome, with JSON-RPC errors mapped the way live ones are.
+    pub fn into_result(self) -> Result<Value, AppError> {
+        match self.error {
+            Some(error) => Err(AppError::Upstream(format!(
+                "RPC error {}: {}",
+                error.get("code").and_then(|v| v.as_i64()).unwrap_or_default(),
+                error.get("message").and_then(|v| v.as_str()).unwrap_or_default()
+            ))),
+            None => Ok(self.result),
+        }
+    }
+}
+
+struct CassetteState {
+    exchanges: Vec<Exchange>,
+    /// Which exchanges have already been served in replay mode.
+    replayed: Vec<bool>,
+    /// The file exchanges are appended to in record mode.
+    recording: Option<Recording>,
+}
+
+/// A cassette file being recorded into. Each exchange is appended in place,
+/// so the file stays a complete JSON array and a crash loses nothing
+/// already recorded.
+struct Recording {
+    file: File,
+    /// Exchanges in the file.
+    recorded: usize,
+    max_exchanges: usize,
+}
+
+/// A recorded sequence of RPC exchanges backed by a file.
+pub struct Cassette {
+    path: PathBuf,
+    state: Mutex<CassetteState>,
+}
+
+fn read_exchanges(path: &Path) -> Result<Vec<Exchange>, AppError> {
+    let content = std::fs::read_to_string(path).map_err(|e| {
+        AppError::Internal(format!("Failed to read RPC cassette {}: {}", path.display(), e))
+    })?;
+    serde_json::from_str(&content).map_err(|e| {
+        AppError::Internal(format!("Invalid RPC cassette {}: {}", path.display(), e))
+    })
+}
+
+impl Cassette {
+    /// Open `path` for recording. An existing cassette is appended to unless
+    /// `overwrite` is set; recording stops at `max_exchanges`.
+    fn record_to(path: &Path, overwrite: bool, max_exchanges: usize) -> Result<Self, AppError> {
+        let existing = if overwrite || !path.exists() { Vec::new() } else { read_exchanges(path)? };
+        let write_error =
+            |e: std::io::Error| AppError::Internal(format!("Failed to write RPC cassette {}: {}", path.display(), e));
+
+        // Written back once in the layout appending relies on: a JSON array ending in `]`
+        let content = serde_json::to_string_pretty(&existing)
+            .map_err(|e| AppError::Internal(format!("Failed to serialize RPC cassette: {}", e)))?;
+        let partial = path.with_extension("partial");
+        std::fs::write(&partial, content).and_then(|_| std::fs::rename(&partial, path)).map_err(write_error)?;
+        let file = OpenOptions::new().write(true).open(path).map_err(write_error)?;
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            state: Mutex::new(CassetteState {
+                exchanges: Vec::new(),
+                replayed: Vec::new(),
+                recording: Some(Recording { file, recorded: existing.len(), max_exchanges }),
+            }),
+        })
+    }
+
+    pub fn load(path: &Path) -> Result<Self, AppError> {
+        let exchanges = read_exchanges(path)?;
+        let replayed = vec![false; exchanges.len()];
+        Ok(Self {
+            path: path.to_path_buf(),
+            state: Mutex::new(CassetteState { exchanges, replayed, recording: None }),
+        })
+    }
+
+    /// Append an exchange to the file, unless it already holds `max_exchanges`.
+    fn record(&self, exchange: Exchange) -> Result<(), AppError> {
+        let mut state = self.state.lock()
+            .map_err(|_| AppError::Internal("Failed to lock RPC cassette".to_string()))?;
+        let Some(recording) = state.recording.as_mut() else {
+            return Err(AppError::Internal("RPC cassette is not open for recording".to_string()));
+        };
+        if recording.recorded >= recording.max_exchanges {
+            return Ok(());
+        }
+
+        let exchange = serde_json::to_string_pretty(&exchange)
+            .map_err(|e| AppError::Internal(format!("Failed to serialize RPC cassette: {}", e)))?;
+        let separator = if recording.recorded == 0 { "\n" } else { ",\n" };
+        // Overwrite the closing `]` and close the array again after the new exchange
+        recording
+            .file
+            .seek(SeekFrom::End(-1))
+            .and_then(|_| recording.file.write_all(format!("{}{}\n]", separator, exchange).as_bytes()))
+            .map_err(|e| AppError::Internal(format!("Failed to write RPC cassette {}: {}", self.path.display(), e)))?;
+
+        recording.recorded += 1;
+        if recording.recorded == recording.max_exchanges {
+            eprintln!(
+                "Warning: RPC cassette {} holds {} exchanges; recording stopped",
+                self.path.display(),
+                recording.max_exchanges
+            );
+        }
+        Ok(())
+    }
+
+    /// Find the recorded response to a call. Repeated calls are answered in
+    /// recording order; once they run out the last response is served again.
+    pub fn replay(&self, method: &str, params: &[Value]) -> Result<Exchange, AppError> {
+        let mut state = self.state.lock()
+            .map_err(|_| AppError::Internal("Failed to lock RPC cassette".to_string()))?;
+        let matches: Vec<usize> = state
+            .exchanges
+            .iter()
+            .enumerate()
+            .filter(|(_, exchange)| exchange.method == method && exchange.params == params)
+            .map(|(index, _)| index)
+            .collect();
+
+        let index = matches
+            .iter()
+            .copied()
+            .find(|&index| !state.replayed[index])
+            .or_else(|| matches.last().copied())
+            .ok_or_else(|| {
+                AppError::Upstream(format!(
+                    "No recorded response for {} {} in {}",
+                    method,
+                    Value::from(params.to_vec()),
+                    self.path.display()
+                ))
+            })?;
+        state.replayed[index] = true;
+        Ok(state.exchanges[index].clone())
+    }
+}
+
+type CassetteRegistry = Mutex<HashMap<(RpcMode, PathBuf), Arc<Cassette>>>;
+
+/// Cassettes are opened once per process and mode so that every client
+/// created from the same config records into, or replays from, one sequence.
+fn open_cassette(mode: RpcMode, path: &str, rpc: &RpcConfig) -> Result<Arc<Cassette>, AppError> {
+    static CASSETTES: OnceLock<CassetteRegistry> = OnceLock::new();
+
+    let path = PathBuf::from(path);
+    let mut cassettes = CASSETTES
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .map_err(|_| AppError::Internal("Failed to lock RPC cassettes".to_string()))?;
+    if let Some(cassette) = cassettes.get(&(mode, path.clone())) {
+        return Ok(cassette.clone());
+    }
+
+    let cassette = Arc::new(match mode {
+        RpcMode::Replay => Cassette::load(&path)?,
+        _ => Cassette::record_to(&path, rpc.cassette_overwrite, rpc.cassette_max_exchanges)?,
+    });
+    cassettes.insert((mode, path), cassette.clone());
+    Ok(cassette)
+}
+
+enum Backend {
+    Live(Client),
+    Record(Client, Arc<Cassette>),
+    Replay(Arc<Cassette>),
+}
+
+/// A Dash Core RPC client honouring `rpc.mode`.
+pub struct RpcClient {
+    backend: Backend,
+}
+
+impl RpcClient {
+    pub fn new(config: &Config) -> Result<Self, AppError> {
+        let cassette = match (config.rpc.mode, &config.rpc.cassette) {
+            (RpcMode::Live, _) => None,
+            (mode, Some(path)) => Some(open_cassette(mode, path, &config.rpc)?),
+            (mode, None) => {
+                return Err(AppError::Internal(format!(
+                    "rpc.cassette must be set when rpc.mode is {:?}",
+                    mode
+                )))
+            }
+        };
+
+        let backend = match (config.rpc.mode, cassette) {
+            (RpcMode::Replay, Some(cassette)) => Backend::Replay(cassette),
+            (mode, cassette) => {
+                let auth = Auth::UserPass(config.rpc.username.clone(), config.rpc.password.clone());
+                let client = Client::new(&config.rpc.url, auth)?;
+                match (mode, cassette) {
+                    (RpcMode::Record, Some(cassette)) => Backend::Record(client, cassette),
+                    _ => Backend::Live(client),
+                }
+            }
+        };
+
+        Ok(Self { backend })
+    }
+
+    pub fn call(&self, method: &str, params: &[Value]) -> Result<Value, AppError> {
+        match &self.backend {
+            Backend::Live(client) => Ok(client.call(method, params)?),
+            Backend::Record(client, cassette) => {
+                use dashcore_rpc::jsonrpc::error::Error as JsonRpcError;
+
+                let result: Result<Value, dashcore_rpc::Error> = client.call(method, params);
+                let (recorded_result, recorded_error) = match &result {
+                    Ok(value) => (value.clone(), None),
+                    Err(dashcore_rpc::Error::JsonRpc(JsonRpcError::Rpc(rpc_error))) => (
+                        Value::Null,
+                        Some(serde_json::json!({ "code": rpc_error.code, "message": rpc_error.message })),
+                    ),
+                    // Transport failures say nothing about Dash Core's behaviour; don't record them
+                    Err(_) => return Ok(result?),
+                };
+                // Recording is a side effect; a cassette that can't be written doesn't fail the call
+                if let Err(e) = cassette.record(Exchange {
+                    method: method.to_string(),
+                    params: params.to_vec(),
+                    result: recorded_result,
+                    error: recorded_error,
+                }) {
+                    eprintln!("Warning: Failed to record {} call: {}", method, e);
+                }
+                Ok(result?)
+            }
+            Backend::Replay(cassette) => cassette.replay(method, params)?.into_result(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_source::RpcDataSource;
+    use crate::test_support::mock_rpc::MockRpcServer;
+    use crate::test_support::test_config;
+    use crate::quorum_loader;
+    use serde_json::json;
+
+    fn cassette_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("quorum-list-server-{}-{}.json", std::process::id(), name))
+    }
+
+    #[tokio::test]
+    async fn recorded_session_replays_without_dash_core() {
+        let path = cassette_path("session");
+        let (mut config, live) = {
+            let server = MockRpcServer::from_fixture("testnet");
+            let mut config = test_config(&server.url);
+            config.rpc.mode = RpcMode::Record;
+            config.rpc.cassette = Some(path.to_string_lossy().into_owned());
+
+            let live = quorum_loader::fetch_quorums(&RpcDataSource::new(config.clone()), &config, None).await.unwrap().list;
+            let client = RpcClient::new(&config).unwrap();
+            let unknown = client.call("quorum", &[json!("info"), json!(6), json!("00")]);
+            assert!(matches!(unknown, Err(AppError::Upstream(_))));
+            (config, live)
+        };
+
+        let recorded: Vec<Exchange> = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(recorded.len(), 4);
+        assert_eq!(recorded[0].params, [json!("listextended")]);
+        assert!(recorded[3].error.is_some());
+
+        // The mock server is gone; everything below comes from the cassette
+        config.rpc.mode = RpcMode::Replay;
+        config.rpc.url = "http://127.0.0.1:1".to_string();
+        let replayed = quorum_loader::fetch_quorums(&RpcDataSource::new(config.clone()), &config, None).await.unwrap().list;
+        assert_eq!(replayed.len(), 2);
+        assert_eq!(serde_json::to_value(&replayed).unwrap(), serde_json::to_value(&live).unwrap());
+
+        let client = RpcClient::new(&config).unwrap();
+        let error = client.call("quorum", &[json!("info"), json!(6), json!("00")]).unwrap_err();
+        assert_eq!(error.code(), "upstream_error");
+        let unrecorded = client.call("getblockcount", &[]).unwrap_err();
+        assert!(unrecorded.to_string().contains("No recorded response for getblockcount"));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn replay_serves_repeated_calls_in_order() {
+        let path = cassette_path("repeated");
+        let exchanges = json!([
+            { "method": "getblockcount", "result": 100 },
+            { "method": "getblockcount", "result": 101 },
+        ]);
+        std::fs::write(&path, exchanges.to_string()).unwrap();
+
+        let mut config = test_config("http://127.0.0.1:1");
+        config.rpc.mode = RpcMode::Replay;
+        config.rpc.cassette = Some(path.to_string_lossy().into_owned());
+        let client = RpcClient::new(&config).unwrap();
+
+        assert_eq!(client.call("getblockcount", &[]).unwrap(), json!(100));
+        assert_eq!(client.call("getblockcount", &[]).unwrap(), json!(101));
+        assert_eq!(client.call("getblockcount", &[]).unwrap(), json!(101));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn recording_appends_to_an_existing_cassette_up_to_its_cap() {
+        let path = cassette_path("appended");
+        let exchange = |height: u32| Exchange {
+            method: "getblockhash".to_string(),
+            params: vec![json!(height)],
+            result: json!(format!("hash-{}", height)),
+            error: None,
+        };
+        let heights = |path: &Path| -> Vec<Value> {
+            read_exchanges(path).unwrap().into_iter().map(|exchange| exchange.params[0].clone()).collect()
+        };
+
+        let cassette = Cassette::record_to(&path, false, 10).unwrap();
+        assert!(heights(&path).is_empty());
+        cassette.record(exchange(1)).unwrap();
+        cassette.record(exchange(2)).unwrap();
+        assert_eq!(heights(&path), [json!(1), json!(2)]);
+
+        // A restart continues the recording, until the cassette is full
+        let cassette = Cassette::record_to(&path, false, 3).unwrap();
+        for height in 3..=5 {
+            cassette.record(exchange(height)).unwrap();
+        }
+        assert_eq!(heights(&path), [json!(1), json!(2), json!(3)]);
+
+        let cassette = Cassette::record_to(&path, true, 3).unwrap();
+        cassette.record(exchange(6)).unwrap();
+        assert_eq!(heights(&path), [json!(6)]);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn recording_failures_still_return_the_live_response() {
+        let server = MockRpcServer::from_fixture("testnet");
+        let config = test_config(&server.url);
+        let path = cassette_path("unwritable");
+        std::fs::write(&path, "[]").unwrap();
+
+        // A cassette opened for replay refuses every recording
+        let auth = Auth::UserPass(config.rpc.username.clone(), config.rpc.password.clone());
+        let client = RpcClient {
+            backend: Backend::Record(Client::new(&config.rpc.url, auth).unwrap(), Arc::new(Cassette::load(&path).unwrap())),
+        };
+        assert!(client.call("getblockcount", &[]).unwrap().is_u64());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "[]");
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn record_and_replay_require_a_cassette() {
+        let mut config = test_config("http://127.0.0.1:1");
+        config.rpc.mode = RpcMode::Replay;
+        assert!(matches!(RpcClient::new(&config), Err(AppError::Internal(_))));
+    }
+}
@@ -0,0 +1,63 @@
+//! Signing of published quorum lists. The message format and verification
+//! live in `client.rs`, shared with clients.
+
+use crate::config::SigningConfig;
+use crate::error::AppError;
+use quorum_list_client::SignatureAlgorithm;
+
+pub enum SnapshotSigner {
+    Ed25519(ed25519_dalek::SigningKey),
+    Secp256k1(k256::ecdsa::SigningKey),
+}
+
+impl SnapshotSigner {
+    /// Load the secret key named by `config`.
+    pub fn from_config(config: &SigningConfig) -> Result<Self, AppError> {
+        let content = std::fs::read_to_string(&config.key_path).map_err(|e| {
+            AppError::Internal(format!("Failed to read signing key {}: {}", config.key_path, e))
+        })?;
+        let secret: [u8; 32] = hex::decode(content.trim())
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| {
+                AppError::Internal(format!("Signing key {} is not 32 hex encoded bytes", config.key_path))
+            })?;
+
+        match config.algorithm {
+            SignatureAlgorithm::Ed25519 => Ok(Self::Ed25519(ed25519_dalek::SigningKey::from_bytes(&secret))),
+            SignatureAlgorithm::Secp256k1 => k256::ecdsa::SigningKey::from_slice(&secret)
+                .map(Self::Secp256k1)
+                .map_err(|_| AppError::Internal(format!("Signing key {} is not a valid secp256k1 key", config.key_path))),
+        }
+    }
+
+    pub fn algorithm(&self) -> SignatureAlgorithm {
+        match self {
+            SnapshotSigner::Ed25519(_) => SignatureAlgorithm::Ed25519,
+            SnapshotSigner::Secp256k1(_) => SignatureAlgorithm::Secp256k1,
+        }
+    }
+
+    /// The public key clients verify with: 32 bytes for ed25519, 33-byte
+    /// compressed SEC1 for secp256k1.
+    pub fn public_key(&self) -> Vec<u8> {
+        match self {
+            SnapshotSigner::Ed25519(key) => key.verifying_key().to_bytes().to_vec(),
+            SnapshotSigner::Secp256k1(key) => key.verifying_key().to_sec1_bytes().to_vec(),
+        }
+    }
+
+    pub fn sign(&self, message: &[u8]) -> Vec<u8> {
+        match self {
+            SnapshotSigner::Ed25519(key) => {
+                use ed25519_dalek::Signer;
+                key.sign(message).to_bytes().to_vec()
+            }
+            SnapshotSigner::Secp256k1(key) => {
+                use k256::ecdsa::signature::Signer;
+                let signature: k256::ecdsa::Signature = key.sign(message);
+                signature.to_bytes().to_vec()
+            }
+        }
+    }
+}
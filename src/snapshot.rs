@@ -0,0 +1,74 @@
+use crate::cached_response::CachedBody;
+use crate::response_format::ResponseFormat;
+use axum::body::Bytes;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant, SystemTime};
+
+/// One published version of some cached data. Snapshots are never mutated
+/// after publishing: a refresh builds a new one and swaps it in, so readers
+/// holding the old `Arc` are never blocked and never see a partial update.
+#[derive(Debug)]
+pub struct Snapshot<T> {
+    pub data: T,
+    /// When `data` was loaded; `None` if it never was.
+    pub refreshed_at: Option<Instant>,
+    /// Wall-clock publishing time.
+    pub published_at: SystemTime,
+    /// The serialized response for `data` in each format, built by the first
+    /// request that needs it.
+    response_bodies: [OnceLock<CachedBody>; ResponseFormat::ALL.len()],
+    /// The most recent body an earlier snapshot served in each format, so an
+    /// unchanged body keeps its `Last-Modified` across refreshes.
+    previous_bodies: [Option<BodyVersion>; ResponseFormat::ALL.len()],
+}
+
+/// Which body was served, and since when.
+#[derive(Debug, Clone)]
+struct BodyVersion {
+    digest: String,
+    last_modified: SystemTime,
+}
+
+impl<T> Snapshot<T> {
+    pub fn new(data: T, refreshed_at: Option<Instant>) -> Self {
+        Self {
+            data,
+            refreshed_at,
+            published_at: SystemTime::now(),
+            response_bodies: Default::default(),
+            previous_bodies: Default::default(),
+        }
+    }
+
+    /// A snapshot replacing `previous`. Response bodies identical to the ones
+    /// `previous` served keep their `Last-Modified`.
+    pub fn succeeding(previous: &Snapshot<T>, data: T, refreshed_at: Option<Instant>) -> Self {
+        let mut snapshot = Self::new(data, refreshed_at);
+        for (index, previous_body) in snapshot.previous_bodies.iter_mut().enumerate() {
+            *previous_body = match previous.response_bodies[index].get() {
+                Some(body) => Some(BodyVersion { digest: body.digest().to_string(), last_modified: body.last_modified() }),
+                None => previous.previous_bodies[index].clone(),
+            };
+        }
+        snapshot
+    }
+
+    /// The response body for this snapshot in `format`, serialized at most once.
+    pub fn response_body(&self, format: ResponseFormat, serialize: impl FnOnce(&T) -> Bytes) -> &CachedBody {
+        self.response_bodies[format as usize].get_or_init(|| {
+            let body = CachedBody::new(serialize(&self.data), format.content_type(), self.published_at);
+            match &self.previous_bodies[format as usize] {
+                Some(previous) if previous.digest == body.digest() => body.with_last_modified(previous.last_modified),
+                _ => body,
+            }
+        })
+    }
+
+    /// How long clients may cache this snapshot: until the next refresh
+    /// is due, given that refreshes happen every `refresh_interval`.
+    pub fn max_age(&self, refresh_interval: Duration) -> Duration {
+        self.refreshed_at
+            .map(|at| refresh_interval.saturating_sub(at.elapsed()))
+            .unwrap_or_default()
+    }
+}
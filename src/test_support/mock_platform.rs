@@ -0,0 +1,150 @@
+use crate::grpc_client::platform::{
+    get_status_response::{
+        get_status_response_v0::{version::Software, Version as StatusVersion},
+        GetStatusResponseV0, Version,
+    },
+    platform_server::{Platform, PlatformServer},
+    GetStatusRequest, GetStatusResponse,
+};
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::sync::oneshot;
+use tokio_stream::wrappers::TcpListenerStream;
+use tower::util::MapResponseLayer;
+use tonic::body::BoxBody;
+use tonic::transport::{Certificate, Identity, Server, ServerTlsConfig};
+use tonic::{Code, Request, Response, Status};
+
+/// A self-signed certificate valid for the given hosts/IPs.
+pub struct TestCertificate {
+    pub cert_pem: String,
+    pub key_pem: String,
+}
+
+impl TestCertificate {
+    pub fn self_signed(hosts: &[&str]) -> Self {
+        let names: Vec<String> = hosts.iter().map(|host| host.to_string()).collect();
+        let certified = rcgen::generate_simple_self_signed(names).expect("Failed to generate certificate");
+        Self {
+            cert_pem: certified.cert.pem(),
+            key_pem: certified.key_pair.serialize_pem(),
+        }
+    }
+
+    /// The certificate as a root a client can trust.
+    pub fn as_root(&self) -> Certificate {
+        Certificate::from_pem(&self.cert_pem)
+    }
+}
+
+/// What a mock node answers to `getStatus`.
+#[derive(Debug, Clone)]
+pub enum StatusReply {
+    Versions {
+        dapi: String,
+        drive: Option<String>,
+        tenderdash: Option<String>,
+    },
+    /// A well-formed response with no version information in it.
+    Empty,
+    Error(Code),
+    /// An HTML page instead of a gRPC response, as a misconfigured proxy
+    /// in front of the node would send.
+    Malformed,
+}
+
+impl StatusReply {
+    pub fn versions(dapi: &str, drive: Option<&str>, tenderdash: Option<&str>) -> Self {
+        StatusReply::Versions {
+            dapi: dapi.to_string(),
+            drive: drive.map(str::to_string),
+            tenderdash: tenderdash.map(str::to_string),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct MockPlatform {
+    reply: StatusReply,
+    delay: Duration,
+}
+
+#[tonic::async_trait]
+impl Platform for MockPlatform {
+    async fn get_status(
+        &self,
+        _request: Request<GetStatusRequest>,
+    ) -> Result<Response<GetStatusResponse>, Status> {
+        tokio::time::sleep(self.delay).await;
+        match &self.reply {
+            StatusReply::Versions { dapi, drive, tenderdash } => {
+                let v0 = GetStatusResponseV0 {
+                    version: Some(StatusVersion {
+                        software: Some(Software {
+                            dapi: dapi.clone(),
+                            drive: drive.clone(),
+                            tenderdash: tenderdash.clone(),
+                        }),
+                        protocol: None,
+                    }),
+                    ..Default::default()
+                };
+                Ok(Response::new(GetStatusResponse { version: Some(Version::V0(v0)) }))
+            }
+            StatusReply::Empty => Ok(Response::new(GetStatusResponse { version: None })),
+            StatusReply::Error(code) => Err(Status::new(*code, "mock platform error")),
+            // Replaced by `malformed_response` before it is sent
+            StatusReply::Malformed => Ok(Response::new(GetStatusResponse { version: None })),
+        }
+    }
+}
+
+/// A local Platform gRPC server speaking TLS with a test certificate.
+/// Shuts down when dropped.
+pub struct MockPlatformNode {
+    pub addr: SocketAddr,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl MockPlatformNode {
+    /// Serve on `addr` (port 0 picks a free port) on the current runtime.
+    pub async fn start(addr: &str, certificate: &TestCertificate, reply: StatusReply, delay: Duration) -> Self {
+        let listener = tokio::net::TcpListener::bind(addr).await.expect("Failed to bind mock platform node");
+        let addr = listener.local_addr().unwrap();
+        let tls = ServerTlsConfig::new().identity(Identity::from_pem(&certificate.cert_pem, &certificate.key_pem));
+        let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+        let malformed = matches!(reply, StatusReply::Malformed);
+
+        let server = Server::builder()
+            .tls_config(tls)
+            .expect("Invalid mock platform TLS config")
+            .layer(MapResponseLayer::new(move |response| if malformed { malformed_response() } else { response }))
+            .add_service(PlatformServer::new(MockPlatform { reply, delay }))
+            .serve_with_incoming_shutdown(TcpListenerStream::new(listener), async {
+                let _ = shutdown_rx.await;
+            });
+        tokio::spawn(server);
+
+        Self {
+            addr,
+            shutdown: Some(shutdown_tx),
+        }
+    }
+}
+
+/// A response that isn't gRPC at all: no gRPC content type or status, and a
+/// body that doesn't decode as a gRPC message.
+fn malformed_response() -> axum::http::Response<BoxBody> {
+    axum::http::Response::builder()
+        .header(axum::http::header::CONTENT_TYPE, "text/html")
+        .body(tonic::body::boxed("<html><body>502 Bad Gateway</body></html>".to_string()))
+        .unwrap()
+}
+
+impl Drop for MockPlatformNode {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+}
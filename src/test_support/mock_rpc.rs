@@ -0,0 +1,125 @@
+use axum::{body::Bytes, extract::State, routing::post, Json, Router};
+use serde_json::{json, Value};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tokio::sync::oneshot;
+
+pub use crate::rpc::Exchange;
+
+struct MockState {
+    exchanges: Vec<Exchange>,
+    calls: Mutex<Vec<(String, Vec<Value>)>>,
+}
+
+/// A Dash Core JSON-RPC stand-in serving scripted responses.
+///
+/// Requests are matched on method and params exactly; unmatched calls get a
+/// "Method not found" error. The server runs on its own thread and runtime
+/// because the RPC client blocks the calling thread.
+pub struct MockRpcServer {
+    pub url: String,
+    state: Arc<MockState>,
+    shutdown: Option<oneshot::Sender<()>>,
+    thread: Option<std::thread::JoinHandle<()>>,
+}
+
+impl MockRpcServer {
+    /// Start a server scripted by `tests/fixtures/rpc/<name>.json`.
+    pub fn from_fixture(name: &str) -> Self {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/rpc")
+            .join(format!("{}.json", name));
+        let content = std::fs::read_to_string(&path)
+            .unwrap_or_else(|e| panic!("Failed to read fixture {}: {}", path.display(), e));
+        let exchanges: Vec<Exchange> = serde_json::from_str(&content)
+            .unwrap_or_else(|e| panic!("Invalid fixture {}: {}", path.display(), e));
+        Self::start(exchanges)
+    }
+
+    pub fn start(exchanges: Vec<Exchange>) -> Self {
+        let state = Arc::new(MockState {
+            exchanges,
+            calls: Mutex::new(Vec::new()),
+        });
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("Failed to bind mock RPC server");
+        listener.set_nonblocking(true).expect("Failed to configure mock RPC listener");
+        let url = format!("http://{}", listener.local_addr().unwrap());
+
+        let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+        let app = Router::new().route("/", post(handle_call)).with_state(state.clone());
+        let thread = std::thread::spawn(move || {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .expect("Failed to build mock RPC runtime");
+            runtime.block_on(async move {
+                let listener = tokio::net::TcpListener::from_std(listener).unwrap();
+                axum::serve(listener, app)
+                    .with_graceful_shutdown(async {
+                        let _ = shutdown_rx.await;
+                    })
+                    .await
+                    .unwrap();
+            });
+        });
+
+        Self {
+            url,
+            state,
+            shutdown: Some(shutdown_tx),
+            thread: Some(thread),
+        }
+    }
+
+    /// Every call received so far, as `(method, params)`.
+    pub fn calls(&self) -> Vec<(String, Vec<Value>)> {
+        self.state.calls.lock().unwrap().clone()
+    }
+}
+
+impl Drop for MockRpcServer {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+async fn handle_call(State(state): State<Arc<MockState>>, body: Bytes) -> Json<Value> {
+    // Parsed by hand so the client's Content-Type header doesn't matter
+    let request: Value = serde_json::from_slice(&body).unwrap_or(Value::Null);
+    let id = request.get("id").cloned().unwrap_or(Value::Null);
+    let method = request.get("method").and_then(|m| m.as_str()).unwrap_or_default().to_string();
+    let params = request
+        .get("params")
+        .and_then(|p| p.as_array())
+        .cloned()
+        .unwrap_or_default();
+
+    state.calls.lock().unwrap().push((method.clone(), params.clone()));
+
+    let exchange = state
+        .exchanges
+        .iter()
+        .find(|exchange| exchange.method == method && exchange.params == params);
+
+    let response = match exchange {
+        Some(Exchange { error: Some(error), .. }) => {
+            json!({ "jsonrpc": "2.0", "id": id, "result": null, "error": error })
+        }
+        Some(exchange) => {
+            json!({ "jsonrpc": "2.0", "id": id, "result": exchange.result, "error": null })
+        }
+        None => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "result": null,
+            "error": { "code": -32601, "message": format!("Method not found: {} {:?}", method, params) },
+        }),
+    };
+    Json(response)
+}
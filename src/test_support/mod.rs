@@ -0,0 +1,34 @@
+//! Helpers shared by tests: mock upstreams and ready-made network state.
+
+pub mod mock_platform;
+pub mod mock_rpc;
+
+use crate::api::{NetworkState, SharedNetworkState};
+use crate::config::{Config, Network};
+use crate::data_source::{RpcDataSource, SharedDataSource};
+use crate::masternode_cache::MasternodeCache;
+use crate::quorum_cache::QuorumCache;
+use std::sync::Arc;
+
+/// A testnet config pointing its RPC client at `rpc_url`.
+pub fn test_config(rpc_url: &str) -> Config {
+    let mut config = Config {
+        network: Network::Testnet,
+        ..Config::default()
+    };
+    config.rpc.url = rpc_url.to_string();
+    config
+}
+
+/// Network state for `config` with empty caches, as `main` builds it before the first refresh.
+/// Data comes from Dash Core over RPC, as configured.
+pub fn network_state(name: &str, config: Config) -> SharedNetworkState {
+    let source: SharedDataSource = Arc::new(RpcDataSource::new(config.clone()));
+    Arc::new(NetworkState {
+        name: name.to_string(),
+        quorum_cache: Arc::new(QuorumCache::new(config.clone(), source.clone())),
+        masternode_cache: Arc::new(MasternodeCache::new(config.clone(), source.clone())),
+        config: Arc::new(config),
+        source,
+    })
+}